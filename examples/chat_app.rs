@@ -1,59 +1,23 @@
 //! Example demonstrating the full chat application using tui_chat widgets.
 
-use std::io;
-use crossterm::{
-    cursor::{Hide, MoveTo, Show},
-    event::{self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, Event},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
+use std::{io, time::Duration};
 use ratatui::{backend::CrosstermBackend, Terminal};
-use tui_chat::ChatApp;
+use tui_chat::{ChatApp, DemoConversation};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste, Hide)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
 
     // create app and run it
     let mut app = ChatApp::new();
-
-    loop {
-        terminal.draw(|f| {
-            app.render(f);
-        })?;
-
-        if let Some((x, y)) = app.get_cursor_pos() {
-            execute!(terminal.backend_mut(), MoveTo(x, y), Show)?;
-        } else {
-            execute!(terminal.backend_mut(), Hide)?;
-        }
-
-        match event::read()? {
-            Event::Key(key) => app.on_key(key),
-            Event::Mouse(mouse) => app.on_mouse(mouse),
-            Event::Paste(content) => app.on_paste(content),
-            _ => {}
-        }
-
-        if app.should_quit() {
-            break;
-        }
+    app.set_window_title(Some("tui-chat".to_string()));
+    app.set_smooth_scroll(true);
+    app.set_label_code_blocks(true);
+    app.set_simulated_replies(true);
+    for msg in DemoConversation::new(1).take(20) {
+        app.add_message(0, msg);
     }
 
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture,
-        DisableBracketedPaste,
-        Show
-    )?;
-    terminal.show_cursor()?;
-
+    // Tick a few times a second so transient state such as toasts can expire
+    app.run(&mut terminal, Duration::from_millis(250))?;
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,36 @@
+//! A stable interface for chat transports such as IRC, Matrix or XMPP adapters.
+
+use std::{future::Future, io};
+
+use crate::{ChatMessage, ConnectionStatus, Presence};
+
+/// Something that happened on the backend, applied to the widgets with
+/// [`ChatApp::apply_backend_event`](crate::ChatApp::apply_backend_event).
+///
+/// Conversations are identified by name, as shown in the tab bar.
+#[derive(Clone, Debug)]
+pub enum BackendEvent {
+    /// A message arrived in a conversation.
+    Message { conversation: String, message: ChatMessage },
+    /// The users now typing in a conversation, replacing the previous list.
+    Typing { conversation: String, users: Vec<String> },
+    /// A user's presence changed, in every conversation they are a member of.
+    Presence { user: String, presence: Presence },
+    /// The connection went up or down.
+    Connection(ConnectionStatus),
+}
+
+/// A chat transport, driven by the host's async runtime.
+///
+/// [`ChatApp::connect_backend`](crate::ChatApp::connect_backend) connects it and
+/// [`ChatApp::send_outgoing`](crate::ChatApp::send_outgoing) hands it the messages the user
+/// sent. The host awaits [`next_event`](Self::next_event) alongside terminal input, for
+/// example in a `tokio::select!`, and passes each event to
+/// [`ChatApp::apply_backend_event`](crate::ChatApp::apply_backend_event).
+pub trait ChatBackend {
+    fn connect(&mut self) -> impl Future<Output = io::Result<()>>;
+    /// Sends a message the user wrote in `conversation`.
+    fn send(&mut self, conversation: &str, msg: &ChatMessage) -> impl Future<Output = io::Result<()>>;
+    /// Waits for the next event, or returns `None` once the backend has shut down.
+    fn next_event(&mut self) -> impl Future<Output = Option<BackendEvent>>;
+}
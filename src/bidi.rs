@@ -0,0 +1,126 @@
+//! Bidirectional text: reordering rendered lines from logical to visual order.
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use unicode_bidi::{BidiClass, BidiInfo, bidi_class};
+
+fn is_rtl_char(c: char) -> bool {
+    matches!(bidi_class(c), BidiClass::R | BidiClass::AL | BidiClass::RLE | BidiClass::RLO | BidiClass::RLI)
+}
+
+/// Whether `text` contains more strong right-to-left characters than left-to-right ones.
+pub(crate) fn is_predominantly_rtl(text: &str) -> bool {
+    let (mut rtl, mut ltr) = (0usize, 0usize);
+    for c in text.chars() {
+        match bidi_class(c) {
+            BidiClass::R | BidiClass::AL => rtl += 1,
+            BidiClass::L => ltr += 1,
+            _ => {}
+        }
+    }
+    rtl > ltr
+}
+
+/// Byte offsets of the characters of a single line, in visual (left-to-right display) order.
+fn visual_order(text: &str) -> Vec<usize> {
+    if !text.chars().any(is_rtl_char) {
+        return text.char_indices().map(|(i, _)| i).collect();
+    }
+    let info = BidiInfo::new(text, None);
+    let mut order = Vec::with_capacity(text.len());
+    for para in &info.paragraphs {
+        let (levels, runs) = info.visual_runs(para, para.range.clone());
+        for run in runs {
+            let chars = text[run.clone()].char_indices().map(|(i, _)| run.start + i);
+            if levels[run.start].is_rtl() {
+                order.extend(chars.rev());
+            } else {
+                order.extend(chars);
+            }
+        }
+    }
+    order
+}
+
+/// Reorders a rendered line into visual order, keeping each character's style.
+///
+/// Lines without right-to-left characters are returned unchanged.
+pub(crate) fn reorder_line(line: Line<'static>) -> Line<'static> {
+    let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    if !text.chars().any(is_rtl_char) {
+        return line;
+    }
+    // Start offset and style of every span, to look up the style of a character by its offset.
+    let mut starts = Vec::with_capacity(line.spans.len());
+    let mut offset = 0;
+    for span in &line.spans {
+        starts.push((offset, span.style));
+        offset += span.content.len();
+    }
+    let style_at = |byte: usize| -> Style {
+        let i = starts.partition_point(|(start, _)| *start <= byte);
+        starts[i.saturating_sub(1)].1
+    };
+
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut current = String::new();
+    let mut current_style = None;
+    for byte in visual_order(&text) {
+        let style = style_at(byte);
+        if current_style != Some(style) {
+            if let Some(style) = current_style {
+                spans.push(Span::styled(std::mem::take(&mut current), style));
+            }
+            current_style = Some(style);
+        }
+        current.push(text[byte..].chars().next().expect("offset is a char boundary"));
+    }
+    if let Some(style) = current_style {
+        spans.push(Span::styled(current, style));
+    }
+    let mut reordered = Line::from(spans).style(line.style);
+    reordered.alignment = line.alignment;
+    reordered
+}
+
+/// Reorders a single line of plain text into visual order.
+pub(crate) fn reorder_text(text: &str) -> String {
+    visual_order(text).into_iter().map(|byte| text[byte..].chars().next().expect("char boundary")).collect()
+}
+
+/// Maps a logical character column in `text` to the column it is displayed at.
+///
+/// A column past the end of the text maps to the end of the line in its base direction.
+pub(crate) fn visual_column(text: &str, column: usize) -> usize {
+    let order = visual_order(text);
+    match text.char_indices().nth(column) {
+        Some((byte, _)) => order.iter().position(|&b| b == byte).unwrap_or(column),
+        None if BidiInfo::new(text, None).paragraphs.first().is_some_and(|p| p.level.is_rtl()) => 0,
+        None => order.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Stylize};
+
+    #[test]
+    fn test_reorders_rtl_runs_keeping_styles() {
+        let line = Line::from(vec![Span::raw("bob: "), "אבג".red(), Span::raw(" ok")]);
+        let reordered = reorder_line(line);
+        let text: String = reordered.spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "bob: גבא ok");
+        assert_eq!(reordered.spans[1].content, "גבא");
+        assert_eq!(reordered.spans[1].style.fg, Some(Color::Red));
+
+        assert_eq!(reorder_text("שלום"), "םולש");
+        assert_eq!(visual_column("אבג", 0), 2);
+        assert_eq!(visual_column("אבג", 3), 0);
+        assert_eq!(visual_column("abc", 3), 3);
+        assert!(is_predominantly_rtl("שלום עולם, hi"));
+        assert!(!is_predominantly_rtl("hello שלום"));
+    }
+}
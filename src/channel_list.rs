@@ -0,0 +1,278 @@
+//! Sidebar listing conversations/channels.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Margin, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+use std::collections::HashSet;
+
+use crate::{Strings, Theme, keymap};
+
+/// A single row in a [`ChannelList`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChannelEntry {
+    pub name: String,
+    pub unread: usize,
+}
+
+/// A sidebar widget listing channels with unread counts and filter-as-you-type.
+///
+/// Indices returned by [`ChannelList::selected`] always refer to the full, unfiltered
+/// channel list, so they can be passed straight to a
+/// [`ConversationManager`](crate::ConversationManager).
+pub struct ChannelList {
+    channels: Vec<ChannelEntry>,
+    // Indices of the channels with an unsent draft.
+    drafts: HashSet<usize>,
+    filter: String,
+    // Index into the full channel list.
+    selected: Option<usize>,
+    state: ListState,
+    strings: Strings,
+    theme: Theme,
+    // Where the list was drawn at the last render.
+    area: Rect,
+}
+
+impl Default for ChannelList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChannelList {
+    pub fn new() -> Self {
+        Self {
+            channels: Vec::new(),
+            drafts: HashSet::new(),
+            filter: String::new(),
+            selected: None,
+            state: ListState::default(),
+            strings: Strings::default(),
+            theme: Theme::default(),
+            area: Rect::default(),
+        }
+    }
+
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    /// Sets the theme, which decides whether the draft marker is drawn in ASCII.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Replaces the listed channels, keeping the selection and draft marks that are still in
+    /// range.
+    pub fn set_channels(&mut self, channels: Vec<ChannelEntry>) {
+        self.channels = channels;
+        self.drafts.retain(|&i| i < self.channels.len());
+        self.selected = match self.selected {
+            Some(i) if i < self.channels.len() => Some(i),
+            _ if self.channels.is_empty() => None,
+            _ => Some(0),
+        };
+    }
+
+    pub fn channels(&self) -> &[ChannelEntry] {
+        &self.channels
+    }
+
+    /// Marks the channel at `index` as having an unsent draft, or clears the mark.
+    pub fn set_draft(&mut self, index: usize, draft: bool) {
+        if !draft {
+            self.drafts.remove(&index);
+        } else if index < self.channels.len() {
+            self.drafts.insert(index);
+        }
+    }
+
+    pub fn has_draft(&self, index: usize) -> bool {
+        self.drafts.contains(&index)
+    }
+
+    /// Returns the index of the selected channel in the unfiltered list.
+    pub fn selected(&self) -> Option<usize> {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.channels.len() {
+            self.selected = Some(index);
+        }
+    }
+
+    /// The index in the unfiltered list of the channel drawn at a screen position, as of
+    /// the last render.
+    pub fn channel_at(&self, column: u16, row: u16) -> Option<usize> {
+        let inner = self.area.inner(Margin::new(1, 1));
+        if !inner.contains((column, row).into()) {
+            return None;
+        }
+        self.visible().get((row - inner.y) as usize + self.state.offset()).copied()
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: impl Into<String>) {
+        self.filter = filter.into();
+        self.snap_selection();
+    }
+
+    /// Indices of the channels whose name matches the filter, case-insensitively.
+    pub fn visible(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        self.channels
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.name.to_lowercase().contains(&filter))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn select_next(&mut self) {
+        self.step(1);
+    }
+
+    pub fn select_prev(&mut self) {
+        self.step(-1);
+    }
+
+    fn step(&mut self, delta: isize) {
+        let visible = self.visible();
+        if visible.is_empty() {
+            return;
+        }
+        let pos = self.selected.and_then(|s| visible.iter().position(|&i| i == s));
+        let next = match pos {
+            Some(p) => (p as isize + delta).rem_euclid(visible.len() as isize) as usize,
+            None => 0,
+        };
+        self.selected = Some(visible[next]);
+    }
+
+    /// Moves the selection onto a visible channel if the filter hid the current one.
+    fn snap_selection(&mut self) {
+        let visible = self.visible();
+        if !self.selected.is_some_and(|s| visible.contains(&s)) {
+            self.selected = visible.first().copied().or(self.selected);
+        }
+    }
+
+    /// Handles navigation and filter editing keys, returning `true` if the key was consumed.
+    ///
+    /// Up/Down move the selection, typed characters extend the filter, Backspace
+    /// shortens it and Esc clears it. Enter is left to the host to activate the selection.
+    pub fn on_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Up => self.select_prev(),
+            KeyCode::Down => self.select_next(),
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.snap_selection();
+            }
+            KeyCode::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.snap_selection();
+            }
+            KeyCode::Char(c) if keymap::is_text(&key) => {
+                self.filter.push(c);
+                self.snap_selection();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, focused: bool) {
+        let visible = self.visible();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|&i| {
+                let channel = &self.channels[i];
+                let mut spans = vec![Span::raw(channel.name.clone())];
+                if channel.unread > 0 {
+                    spans.push(Span::styled(
+                        format!(" ({})", channel.unread),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ));
+                }
+                if self.drafts.contains(&i) {
+                    spans.push(Span::raw(format!(" {}", self.strings.draft_marker(&self.theme))));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let title = if self.filter.is_empty() {
+            self.strings.channels_title.clone()
+        } else {
+            format!("{} /{}", self.strings.channels_title, self.filter)
+        };
+        let mut block = Block::default().borders(Borders::ALL).title(title);
+        if focused {
+            block = block.border_style(Style::default().add_modifier(Modifier::BOLD));
+        }
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        self.state.select(self.selected.and_then(|s| visible.iter().position(|&i| i == s)));
+        self.area = area;
+        frame.render_stateful_widget(list, area, &mut self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_keeps_indices_of_full_list() {
+        let mut list = ChannelList::new();
+        list.set_channels(
+            ["general", "random", "rust-dev"]
+                .into_iter()
+                .map(|name| ChannelEntry { name: name.to_string(), ..Default::default() })
+                .collect(),
+        );
+        assert_eq!(list.selected(), Some(0));
+        for c in "r".chars() {
+            list.on_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(list.visible(), vec![0, 1, 2]);
+        list.on_key(KeyEvent::from(KeyCode::Char('u')));
+        assert_eq!(list.visible(), vec![2]);
+        assert_eq!(list.selected(), Some(2));
+        list.on_key(KeyEvent::from(KeyCode::Esc));
+        list.select_next();
+        assert_eq!(list.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_draft_marker_falls_back_to_ascii() {
+        use ratatui::{Terminal, backend::TestBackend};
+
+        let mut list = ChannelList::new();
+        let entry = |name: &str| ChannelEntry { name: name.to_string(), unread: 0 };
+        list.set_channels(vec![entry("general"), entry("random")]);
+        list.set_draft(1, true);
+        list.set_draft(5, true);
+        assert!(list.has_draft(1) && !list.has_draft(0) && !list.has_draft(5));
+
+        let mut terminal = Terminal::new(TestBackend::new(16, 4)).unwrap();
+        terminal.draw(|f| list.render(f, f.area(), false)).unwrap();
+        assert!(crate::testing::buffer_to_string(terminal.backend().buffer()).contains("random ✎"));
+        list.set_theme(Theme { plain_glyphs: true, ..Theme::default() });
+        terminal.draw(|f| list.render(f, f.area(), false)).unwrap();
+        assert!(crate::testing::buffer_to_string(terminal.backend().buffer()).contains("random *"));
+        list.set_channels(vec![entry("general")]);
+        assert!(!list.has_draft(1));
+    }
+}
@@ -0,0 +1,185 @@
+//! Conversation header with title, topic and typing indicator.
+
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+use crate::{Strings, strings::fill};
+
+/// Where the typing indicator is shown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TypingPosition {
+    /// On a second header row, under the title.
+    #[default]
+    Header,
+    /// On a row pinned under the chat, above the input.
+    BelowChat,
+}
+
+/// A one-to-two-line header showing the conversation title, topic and who is typing.
+#[derive(Clone, Debug, Default)]
+pub struct ChatHeader {
+    title: String,
+    topic: String,
+    typing: Vec<String>,
+    typing_position: TypingPosition,
+    typing_timeout: Option<Duration>,
+    typing_until: Option<Instant>, // the typing users are cleared then
+    strings: Strings,
+}
+
+impl ChatHeader {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn set_title(&mut self, title: impl Into<String>) {
+        self.title = title.into();
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn set_topic(&mut self, topic: impl Into<String>) {
+        self.topic = topic.into();
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Sets the templates used for the typing indicator.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    /// Sets the users currently typing. An empty slice hides the typing line.
+    ///
+    /// With a [typing timeout](Self::set_typing_timeout) the users are cleared unless they
+    /// are set again before it runs out.
+    pub fn set_typing(&mut self, users: &[&str]) {
+        self.typing = users.iter().map(|u| u.to_string()).collect();
+        self.typing_until = self.typing_timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    pub fn typing(&self) -> &[String] {
+        &self.typing
+    }
+
+    /// The typing indicator, such as `"alice and bob are typing…"`, while someone is typing.
+    pub fn typing_line(&self) -> Option<String> {
+        typing_text(&self.strings, &self.typing)
+    }
+
+    /// Shows the typing indicator in the header or pinned under the chat. In the latter
+    /// case the host renders [`typing_line`](Self::typing_line) itself, as
+    /// [`ChatApp`](crate::ChatApp) does.
+    pub fn set_typing_position(&mut self, position: TypingPosition) {
+        self.typing_position = position;
+    }
+
+    pub fn typing_position(&self) -> TypingPosition {
+        self.typing_position
+    }
+
+    /// Clears the typing users once `timeout` passes without a new
+    /// [`set_typing`](Self::set_typing), checked by [`tick`](Self::tick), for transports
+    /// that never send "stopped typing". `None`, the default, keeps them until they are
+    /// replaced.
+    pub fn set_typing_timeout(&mut self, timeout: Option<Duration>) {
+        self.typing_timeout = timeout;
+        self.typing_until = timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    pub fn typing_timeout(&self) -> Option<Duration> {
+        self.typing_timeout
+    }
+
+    /// Clears the typing users if the typing timeout ran out by `now`. Returns whether
+    /// they were cleared.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        if self.typing.is_empty() || self.typing_until.is_none_or(|until| now < until) {
+            return false;
+        }
+        self.typing.clear();
+        true
+    }
+
+    /// Number of rows the header needs: one, plus one while someone is typing and the
+    /// indicator is shown in the header.
+    pub fn height(&self) -> u16 {
+        if self.typing.is_empty() || self.typing_position != TypingPosition::Header { 1 } else { 2 }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let mut title = vec![Span::styled(self.title.clone(), Style::default().add_modifier(Modifier::BOLD))];
+        if !self.topic.is_empty() {
+            title.push(Span::styled(format!(" — {}", self.topic), Style::default().add_modifier(Modifier::DIM)));
+        }
+        let mut lines = vec![Line::from(title)];
+        if self.typing_position == TypingPosition::Header
+            && let Some(typing) = self.typing_line()
+        {
+            lines.push(Line::styled(typing, Style::default().add_modifier(Modifier::DIM | Modifier::ITALIC)));
+        }
+        frame.render_widget(Paragraph::new(lines), area);
+    }
+}
+
+/// Formats a typing indicator such as `"alice and bob are typing…"`.
+///
+/// Up to three names are listed; beyond that the rest are summarised as a count.
+pub(crate) fn typing_text<S: AsRef<str>>(strings: &Strings, users: &[S]) -> Option<String> {
+    let names: Vec<&str> = users.iter().map(|u| u.as_ref()).collect();
+    let text = match names.as_slice() {
+        [] => return None,
+        [a] => fill(&strings.typing_one, &[("a", a)]),
+        [a, b] => fill(&strings.typing_two, &[("a", a), ("b", b)]),
+        [a, b, c] => fill(&strings.typing_three, &[("a", a), ("b", b), ("c", c)]),
+        [a, b, rest @ ..] => fill(&strings.typing_many, &[("a", a), ("b", b), ("count", &rest.len())]),
+    };
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typing_text_truncates_long_lists() {
+        let strings = Strings::default();
+        assert_eq!(typing_text::<&str>(&strings, &[]), None);
+        assert_eq!(typing_text(&strings, &["alice"]).unwrap(), "alice is typing…");
+        assert_eq!(typing_text(&strings, &["alice", "bob"]).unwrap(), "alice and bob are typing…");
+        assert_eq!(typing_text(&strings, &["a", "b", "c", "d", "e"]).unwrap(), "a, b and 3 others are typing…");
+    }
+
+    #[test]
+    fn test_typing_times_out() {
+        let mut header = ChatHeader::new("general");
+        header.set_typing_timeout(Some(Duration::from_secs(5)));
+        header.set_typing(&["alice"]);
+        let start = Instant::now();
+        assert!(!header.tick(start + Duration::from_secs(1)));
+        header.set_typing(&["alice", "bob"]);
+        assert!(!header.tick(start + Duration::from_secs(4)));
+        assert!(header.tick(start + Duration::from_secs(6)));
+        assert!(header.typing().is_empty());
+        assert!(!header.tick(start + Duration::from_secs(7)));
+
+        header.set_typing(&["alice"]);
+        header.set_typing_position(TypingPosition::BelowChat);
+        assert_eq!(header.height(), 1);
+        assert_eq!(header.typing_line().as_deref(), Some("alice is typing…"));
+    }
+}
@@ -0,0 +1,318 @@
+//! Multiple conversations with a tab bar.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::Tabs,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{ChatArea, ChatHeader, ChatMessage, InputArea, MemberList, MessageId, Strings, Theme, store::HistoryCursor};
+
+/// A named conversation with its own message history and draft input.
+pub struct Conversation {
+    name: String,
+    chat_area: ChatArea,
+    input_area: InputArea,
+    members: MemberList,
+    header: ChatHeader,
+    pub(crate) history: HistoryCursor,
+}
+
+impl Conversation {
+    pub fn new(name: impl Into<String>) -> Self {
+        let name = name.into();
+        Self {
+            header: ChatHeader::new(name.clone()),
+            name,
+            chat_area: ChatArea::new(),
+            input_area: InputArea::new(),
+            members: MemberList::new(),
+            history: HistoryCursor::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Renames the conversation as shown in the tab bar and channel list.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Number of messages after the last one read, see [`ChatArea::set_last_read`]. The
+    /// active conversation's messages are read as they arrive, unless some before them
+    /// were still unread.
+    pub fn unread(&self) -> usize {
+        self.chat_area.unread_count()
+    }
+
+    /// Marks the messages of a conversation that is in view without being active as read,
+    /// as in the unfocused pane of a split view.
+    pub(crate) fn mark_read(&mut self) {
+        self.chat_area.mark_all_read();
+    }
+
+    /// The unsent text in the conversation's input.
+    pub fn draft(&self) -> String {
+        self.input_area.text()
+    }
+
+    /// Whether the input holds unsent text, which the tab bar and channel list mark.
+    pub fn has_draft(&self) -> bool {
+        self.input_area.buffer.chars().any(|c| !c.is_whitespace())
+    }
+
+    pub fn chat_area(&self) -> &ChatArea {
+        &self.chat_area
+    }
+
+    pub fn chat_area_mut(&mut self) -> &mut ChatArea {
+        &mut self.chat_area
+    }
+
+    pub fn input_area(&self) -> &InputArea {
+        &self.input_area
+    }
+
+    pub fn input_area_mut(&mut self) -> &mut InputArea {
+        &mut self.input_area
+    }
+
+    pub fn members(&self) -> &MemberList {
+        &self.members
+    }
+
+    pub fn members_mut(&mut self) -> &mut MemberList {
+        &mut self.members
+    }
+
+    /// The header shown above the chat, titled with the conversation name by default.
+    pub fn header(&self) -> &ChatHeader {
+        &self.header
+    }
+
+    pub fn header_mut(&mut self) -> &mut ChatHeader {
+        &mut self.header
+    }
+}
+
+/// Owns several conversations and tracks which one is active.
+///
+/// Each conversation keeps its own scroll position and draft input, so switching
+/// tabs never loses state. There is always at least one conversation.
+pub struct ConversationManager {
+    conversations: Vec<Conversation>,
+    active: usize,
+    strings: Strings,
+    theme: Theme,
+}
+
+impl Default for ConversationManager {
+    fn default() -> Self {
+        Self::new("Chat")
+    }
+}
+
+impl ConversationManager {
+    /// Creates a manager holding a single conversation.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            conversations: vec![Conversation::new(name)],
+            active: 0,
+            strings: Strings::default(),
+            theme: Theme::default(),
+        }
+    }
+
+    /// Sets the draft marker shown in the tab bar.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    /// Sets the theme of the tab bar, which decides whether the draft marker is drawn in
+    /// ASCII. Each conversation's widgets keep their own theme.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Adds a conversation and returns its index. The active conversation is unchanged.
+    pub fn add_conversation(&mut self, name: impl Into<String>) -> usize {
+        self.conversations.push(Conversation::new(name));
+        self.conversations.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.conversations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conversations.is_empty()
+    }
+
+    pub fn conversations(&self) -> &[Conversation] {
+        &self.conversations
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Conversation> {
+        self.conversations.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Conversation> {
+        self.conversations.get_mut(index)
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn active(&self) -> &Conversation {
+        &self.conversations[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Conversation {
+        &mut self.conversations[self.active]
+    }
+
+    /// Adds a message to a conversation, counting it as unread unless that conversation is
+    /// active and was read up to here.
+    pub fn add_message(&mut self, index: usize, msg: ChatMessage) -> Option<MessageId> {
+        let active = self.active;
+        let chat_area = &mut self.conversations.get_mut(index)?.chat_area;
+        let read = index == active && chat_area.unread_count() == 0;
+        let id = chat_area.add_message(msg);
+        if read {
+            chat_area.mark_all_read();
+        }
+        Some(id)
+    }
+
+    /// Makes a conversation active and marks its messages read. Returns `false` for an invalid index.
+    pub fn select(&mut self, index: usize) -> bool {
+        let Some(conversation) = self.conversations.get_mut(index) else {
+            return false;
+        };
+        conversation.chat_area.mark_all_read();
+        // The draft and cursor stay with the conversation being left, but an unfinished
+        // input method composition belongs to the input that had focus.
+        if index != self.active {
+            self.conversations[self.active].input_area.preedit.clear();
+        }
+        self.active = index;
+        true
+    }
+
+    pub fn select_next(&mut self) {
+        self.select((self.active + 1) % self.conversations.len());
+    }
+
+    pub fn select_prev(&mut self) {
+        let len = self.conversations.len();
+        self.select((self.active + len - 1) % len);
+    }
+
+    /// Handles conversation switching keys, returning `true` if the key was consumed.
+    ///
+    /// Ctrl+Tab and Ctrl+Shift+Tab cycle through conversations, and Alt+1 to Alt+9 jump
+    /// directly to a tab. Alt+digits past the last tab are consumed without effect.
+    pub fn on_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Tab if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if key.modifiers.contains(KeyModifiers::SHIFT) {
+                    self.select_prev();
+                } else {
+                    self.select_next();
+                }
+                true
+            }
+            KeyCode::BackTab if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_prev();
+                true
+            }
+            KeyCode::Char(c @ '1'..='9') if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.select(c as usize - '1' as usize);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Renders the tab bar, showing unread counts and unsent drafts next to inactive conversations.
+    fn tab_titles(&self) -> Vec<String> {
+        self.conversations
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let mut title = c.name.clone();
+                if c.unread() > 0 {
+                    title.push_str(&format!(" ({})", c.unread()));
+                }
+                if i != self.active && c.has_draft() {
+                    title.push_str(&format!(" {}", self.strings.draft_marker(&self.theme)));
+                }
+                title
+            })
+            .collect()
+    }
+
+    /// The index of the tab drawn at `column` when the tab bar is rendered in `area`.
+    pub fn tab_at(&self, area: Rect, column: u16) -> Option<usize> {
+        let mut x = area.x as usize;
+        for (i, title) in self.tab_titles().iter().enumerate() {
+            // Each title is padded by a space either side and followed by a divider.
+            let width = title.width() + 2;
+            if (x..x + width).contains(&(column as usize)) {
+                return Some(i);
+            }
+            x += width + 1;
+        }
+        None
+    }
+
+    pub fn render_tabs(&self, frame: &mut Frame, area: Rect) {
+        let titles = self.tab_titles();
+        let tabs = Tabs::new(titles)
+            .select(self.active)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_widget(tabs, area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(content: &str) -> ChatMessage {
+        ChatMessage::new("AI", content)
+    }
+
+    #[test]
+    fn test_unread_counts_and_switching() {
+        let mut manager = ConversationManager::default();
+        let other = manager.add_conversation("other");
+        manager.add_message(0, msg("seen"));
+        manager.add_message(other, msg("one"));
+        manager.add_message(other, msg("two"));
+        assert_eq!(manager.get(0).unwrap().unread(), 0);
+        assert_eq!(manager.get(other).unwrap().unread(), 2);
+
+        manager.active_mut().input_area_mut().insert_str("draft");
+        let key = KeyEvent::new(KeyCode::Char('2'), KeyModifiers::ALT);
+        assert!(manager.on_key(key));
+        assert_eq!(manager.active_index(), other);
+        assert_eq!(manager.active().unread(), 0);
+        assert!(manager.on_key(KeyEvent::new(KeyCode::Char('9'), KeyModifiers::ALT)));
+        assert_eq!(manager.active_index(), other);
+
+        assert!(manager.get(0).unwrap().has_draft());
+        assert!(!manager.active().has_draft());
+
+        manager.select_next();
+        assert_eq!(manager.active_index(), 0);
+        assert_eq!(manager.active().input_area().buffer, "draft");
+        assert_eq!(manager.active().draft(), "draft");
+    }
+}
@@ -0,0 +1,115 @@
+//! Popup for choosing a point in time to jump to.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{Strings, timestamp};
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+/// A popup for picking a date and time, adjusted with the arrow keys.
+///
+/// Feed keys to [`DatePicker::on_key`] until it returns `Some`, then read [`DatePicker::time`].
+#[derive(Clone, Debug)]
+pub struct DatePicker {
+    // Seconds since the Unix epoch, rounded down to the minute.
+    secs: u64,
+    strings: Strings,
+    #[cfg(feature = "chrono")]
+    time_format: timestamp::TimeFormat,
+}
+
+impl DatePicker {
+    pub fn new(time: SystemTime) -> Self {
+        let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Self {
+            secs: secs - secs % MINUTE,
+            strings: Strings::default(),
+            #[cfg(feature = "chrono")]
+            time_format: timestamp::TimeFormat::default(),
+        }
+    }
+
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    /// Sets the timezone and pattern the selected time is shown in.
+    #[cfg(feature = "chrono")]
+    pub fn set_time_format(&mut self, time_format: timestamp::TimeFormat) {
+        self.time_format = time_format;
+    }
+
+    /// The selected time.
+    pub fn time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.secs)
+    }
+
+    fn shift(&mut self, secs: u64, forward: bool) {
+        self.secs = if forward { self.secs.saturating_add(secs) } else { self.secs.saturating_sub(secs) };
+    }
+
+    /// Handles a key, returning `Some(true)` to jump to the selected time or `Some(false)` to cancel.
+    ///
+    /// Left/Right move by a day, Up/Down by an hour, and with Shift held Up/Down move by ten minutes.
+    pub fn on_key(&mut self, key: KeyEvent) -> Option<bool> {
+        let hour_step = if key.modifiers.contains(KeyModifiers::SHIFT) { 10 * MINUTE } else { HOUR };
+        match key.code {
+            KeyCode::Enter => return Some(true),
+            KeyCode::Esc => return Some(false),
+            KeyCode::Left => self.shift(DAY, false),
+            KeyCode::Right => self.shift(DAY, true),
+            KeyCode::Down => self.shift(hour_step, false),
+            KeyCode::Up => self.shift(hour_step, true),
+            _ => {}
+        }
+        None
+    }
+
+    /// Draws the popup centered in `area`.
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let [popup] = Layout::horizontal([Constraint::Length(36.min(area.width))]).flex(Flex::Center).areas(area);
+        let [popup] = Layout::vertical([Constraint::Length(5.min(area.height))]).flex(Flex::Center).areas(popup);
+        #[cfg(feature = "chrono")]
+        let label = format!("{} {}", self.time_format.format_date_time(self.time()), self.time_format.zone());
+        #[cfg(not(feature = "chrono"))]
+        let label = format!("{} UTC", timestamp::format_date_time(self.time()));
+        let lines = vec![
+            Line::styled(
+                label,
+                Style::default().add_modifier(Modifier::BOLD),
+            )
+            .centered(),
+            Line::default(),
+            Line::styled(self.strings.date_picker_help.as_str(), Style::default().add_modifier(Modifier::DIM)).centered(),
+        ];
+        let block = Block::default().borders(Borders::ALL).title(self.strings.date_picker_title.as_str());
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrows_adjust_day_and_hour() {
+        let mut picker = DatePicker::new(UNIX_EPOCH + Duration::from_secs(2 * DAY + 14 * HOUR + 59));
+        assert_eq!(picker.on_key(KeyEvent::from(KeyCode::Left)), None);
+        assert_eq!(picker.on_key(KeyEvent::from(KeyCode::Down)), None);
+        assert_eq!(picker.on_key(KeyEvent::new(KeyCode::Up, KeyModifiers::SHIFT)), None);
+        assert_eq!(timestamp::format_date_time(picker.time()), "1970-01-02 13:10");
+        assert_eq!(picker.on_key(KeyEvent::from(KeyCode::Enter)), Some(true));
+    }
+}
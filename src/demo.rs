@@ -0,0 +1,224 @@
+//! Generator of fake conversations for examples, screenshots and performance testing.
+
+use std::{
+    ops::RangeInclusive,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::ChatMessage;
+
+const WORDS: &[&str] = &[
+    "the", "build", "is", "green", "again", "I", "think", "we", "should", "ship", "it", "today", "did", "you", "see",
+    "latest", "logs", "looks", "like", "a", "race", "in", "scheduler", "can", "someone", "review", "my", "branch",
+    "tests", "pass", "locally", "but", "fail", "on", "CI", "maybe", "cache", "cold", "start", "after", "lunch",
+    "sounds", "good", "thanks", "for", "quick", "fix", "let's", "pair", "on", "this", "tomorrow", "morning",
+];
+
+const SNIPPETS: &[&str] = &[
+    "```rust\nfn main() {\n    println!(\"hello\");\n}\n```",
+    "```sh\ncargo test --all-features\n```",
+    "```python\nfor i in range(3):\n    print(i)\n```",
+    "```json\n{ \"retries\": 3, \"timeout\": 30 }\n```",
+];
+
+/// An endless source of realistic-looking chat messages.
+///
+/// Use it as an [`Iterator`] to fill a chat up front, or call [`DemoConversation::poll`]
+/// from the event loop to have messages arrive over time. The same seed always produces
+/// the same conversation.
+#[derive(Clone, Debug)]
+pub struct DemoConversation {
+    senders: Vec<String>,
+    words: RangeInclusive<usize>,
+    code_percent: u32,
+    markdown_percent: u32,
+    interval: Duration,
+    clock: SystemTime,
+    next_arrival: Option<Instant>,
+    state: u64,
+}
+
+impl Default for DemoConversation {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl DemoConversation {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            senders: ["alice", "bob", "AI"].map(String::from).to_vec(),
+            words: 3..=30,
+            code_percent: 10,
+            markdown_percent: 20,
+            interval: Duration::from_secs(2),
+            clock: SystemTime::now(),
+            next_arrival: None,
+            // Xorshift gets stuck at zero.
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Senders to pick from at random. An empty list keeps the current senders.
+    pub fn with_senders<S: Into<String>>(mut self, senders: impl IntoIterator<Item = S>) -> Self {
+        let senders: Vec<String> = senders.into_iter().map(Into::into).collect();
+        if !senders.is_empty() {
+            self.senders = senders;
+        }
+        self
+    }
+
+    /// Number of words in a message.
+    pub fn with_length(mut self, words: RangeInclusive<usize>) -> Self {
+        self.words = words;
+        self
+    }
+
+    /// Percentage of messages that end with a fenced code block.
+    pub fn with_code_percent(mut self, percent: u32) -> Self {
+        self.code_percent = percent.min(100);
+        self
+    }
+
+    /// Percentage of messages that use inline markdown such as bold, inline code or a list.
+    pub fn with_markdown_percent(mut self, percent: u32) -> Self {
+        self.markdown_percent = percent.min(100);
+        self
+    }
+
+    /// Average time between messages, used for timestamps and by [`DemoConversation::poll`].
+    /// Each gap varies randomly between half and one and a half times this.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Timestamp of the first message. Defaults to the time of construction.
+    pub fn with_start(mut self, start: SystemTime) -> Self {
+        self.clock = start;
+        self
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    /// A number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+
+    fn chance(&mut self, percent: u32) -> bool {
+        self.below(100) < percent as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    /// The next gap between messages.
+    fn gap(&mut self) -> Duration {
+        let millis = self.interval.as_millis() as u64;
+        Duration::from_millis(millis / 2 + self.next_u64() % (millis + 1))
+    }
+
+    fn content(&mut self) -> String {
+        let (min, max) = (*self.words.start(), (*self.words.end()).max(*self.words.start()));
+        let len = (min + self.below(max - min + 1)).max(1);
+        let mut words: Vec<String> = (0..len).map(|_| self.pick(WORDS).to_string()).collect();
+        if let Some(first) = words.first_mut()
+            && let Some(c) = first.chars().next()
+        {
+            first.replace_range(..c.len_utf8(), &c.to_uppercase().to_string());
+        }
+        let mut content = if self.chance(self.markdown_percent) {
+            match self.below(3) {
+                0 => {
+                    let i = self.below(words.len());
+                    words[i] = format!("**{}**", words[i]);
+                    words.join(" ")
+                }
+                1 => {
+                    let i = self.below(words.len());
+                    words[i] = format!("`{}`", words[i]);
+                    words.join(" ")
+                }
+                _ => words.chunks(4).map(|chunk| format!("- {}", chunk.join(" "))).collect::<Vec<_>>().join("\n"),
+            }
+        } else {
+            words.join(" ")
+        };
+        if self.chance(self.code_percent) {
+            content.push('\n');
+            content.push_str(self.pick(SNIPPETS));
+        }
+        content
+    }
+
+    /// Returns a new message if one is due at `now`, at roughly the configured interval.
+    ///
+    /// The first call only schedules the first arrival.
+    pub fn poll(&mut self, now: Instant) -> Option<ChatMessage> {
+        match self.next_arrival {
+            Some(due) if now >= due => {
+                self.next_arrival = Some(now + self.gap());
+                self.clock = SystemTime::now();
+                self.next()
+            }
+            Some(_) => None,
+            None => {
+                self.next_arrival = Some(now + self.gap());
+                None
+            }
+        }
+    }
+}
+
+impl Iterator for DemoConversation {
+    type Item = ChatMessage;
+
+    fn next(&mut self) -> Option<ChatMessage> {
+        let i = self.below(self.senders.len());
+        let sender = self.senders[i].clone();
+        let content = self.content();
+        let msg = ChatMessage::new(sender, content).with_timestamp(self.clock);
+        let gap = self.gap();
+        self.clock += gap;
+        Some(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_is_seeded_and_configurable() {
+        let start = SystemTime::UNIX_EPOCH;
+        let demo = || {
+            DemoConversation::new(7)
+                .with_senders(["ann"])
+                .with_length(2..=2)
+                .with_markdown_percent(0)
+                .with_code_percent(100)
+                .with_start(start)
+        };
+        let a: Vec<ChatMessage> = demo().take(5).collect();
+        let b: Vec<ChatMessage> = demo().take(5).collect();
+        assert!(a.iter().zip(&b).all(|(a, b)| a.content == b.content && a.timestamp == b.timestamp));
+        assert!(a.iter().all(|m| m.sender == "ann" && m.content.contains("\n```")));
+        assert_eq!(a[0].content.lines().next().unwrap().split(' ').count(), 2);
+        assert!(a.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+
+        let mut live = demo().with_interval(Duration::from_secs(1));
+        let now = Instant::now();
+        assert!(live.poll(now).is_none());
+        assert!(live.poll(now + Duration::from_millis(100)).is_none());
+        assert!(live.poll(now + Duration::from_secs(2)).is_some());
+    }
+}
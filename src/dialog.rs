@@ -0,0 +1,117 @@
+//! Modal confirmation dialog.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::Strings;
+
+/// A yes/no popup that captures key input while open.
+///
+/// Feed keys to [`ConfirmDialog::on_key`] until it returns the user's choice.
+#[derive(Clone, Debug)]
+pub struct ConfirmDialog {
+    // Falls back to the localized default title when not set.
+    title: Option<String>,
+    message: String,
+    yes_selected: bool,
+    strings: Strings,
+}
+
+impl ConfirmDialog {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            title: None,
+            message: message.into(),
+            yes_selected: false,
+            strings: Strings::default(),
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Sets the default title and button labels.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Handles a key, returning `Some(choice)` once the user has decided.
+    ///
+    /// `y`/`n` answer directly, Esc cancels, Left/Right/Tab move between the buttons and
+    /// Enter confirms the highlighted one. The default button is "No".
+    pub fn on_key(&mut self, key: KeyEvent) -> Option<bool> {
+        match key.code {
+            KeyCode::Char('y' | 'Y') => Some(true),
+            KeyCode::Char('n' | 'N') | KeyCode::Esc => Some(false),
+            KeyCode::Enter => Some(self.yes_selected),
+            KeyCode::Left | KeyCode::Right | KeyCode::Tab | KeyCode::BackTab => {
+                self.yes_selected = !self.yes_selected;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Dims everything in `area` and draws the dialog centered on top of it.
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        frame.buffer_mut().set_style(area, Style::default().add_modifier(Modifier::DIM));
+
+        let width = (self.message.len() as u16 + 4).clamp(24, 60).min(area.width);
+        let mut text: Vec<Line> = textwrap::wrap(&self.message, width.saturating_sub(2).max(1) as usize)
+            .into_iter()
+            .map(|l| Line::from(l.into_owned()).centered())
+            .collect();
+        let height = (text.len() as u16 + 4).min(area.height);
+        let [popup] = Layout::horizontal([Constraint::Length(width)]).flex(Flex::Center).areas(area);
+        let [popup] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(popup);
+
+        let button = |label: &str, selected: bool| {
+            let style = if selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            Span::styled(format!("[ {label} ]"), style)
+        };
+        let buttons = Line::from(vec![
+            button(&self.strings.yes, self.yes_selected),
+            Span::raw("  "),
+            button(&self.strings.no, !self.yes_selected),
+        ])
+        .centered();
+        text.push(Line::default());
+        text.push(buttons);
+
+        let title = self.title.as_deref().unwrap_or(&self.strings.confirm_title);
+        let block = Block::default().borders(Borders::ALL).title(title);
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }).block(block), popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dialog_choice_keys() {
+        let mut dialog = ConfirmDialog::new("Clear conversation?");
+        assert_eq!(dialog.on_key(KeyEvent::from(KeyCode::Char('x'))), None);
+        assert_eq!(dialog.on_key(KeyEvent::from(KeyCode::Enter)), Some(false));
+        assert_eq!(dialog.on_key(KeyEvent::from(KeyCode::Left)), None);
+        assert_eq!(dialog.on_key(KeyEvent::from(KeyCode::Enter)), Some(true));
+        assert_eq!(dialog.on_key(KeyEvent::from(KeyCode::Esc)), Some(false));
+    }
+}
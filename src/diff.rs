@@ -0,0 +1,77 @@
+//! Coloring unified diffs, whether a whole message is a patch or it sits in a ```diff block.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+
+/// Whether `content` reads as a unified diff: a hunk header plus a file header.
+fn is_patch(content: &str) -> bool {
+    let mut hunk = false;
+    let mut header = false;
+    for line in content.lines() {
+        hunk |= line.starts_with("@@ ");
+        header |= line.starts_with("--- ") || line.starts_with("+++ ") || line.starts_with("diff --git ");
+    }
+    hunk && header
+}
+
+fn style(line: &str) -> Style {
+    if line.starts_with("diff ") || line.starts_with("index ") || line.starts_with("--- ") || line.starts_with("+++ ") {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else if line.starts_with("@@") {
+        Style::default().fg(Color::Cyan)
+    } else if line.starts_with('+') {
+        Style::default().fg(Color::Green)
+    } else if line.starts_with('-') {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default()
+    }
+}
+
+/// Styled spans for `content` with diff lines colored: additions green, removals red, hunk
+/// headers cyan and file headers bold. Returns `None` if there is nothing to color.
+pub(crate) fn spans(content: &str) -> Option<Vec<Span<'static>>> {
+    let whole = is_patch(content);
+    if !whole && !content.contains("```diff") && !content.contains("```patch") {
+        return None;
+    }
+    let mut spans = Vec::new();
+    let mut in_block = false;
+    for (i, line) in content.split('\n').enumerate() {
+        if i > 0 {
+            spans.push(Span::raw("\n"));
+        }
+        let fence = line.trim_start().strip_prefix("```");
+        if let Some(lang) = fence {
+            in_block = !in_block && matches!(lang.trim(), "diff" | "patch");
+        }
+        let styled = (whole || in_block) && fence.is_none();
+        spans.push(Span::styled(line.to_string(), if styled { style(line) } else { Style::default() }));
+    }
+    Some(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_patches_and_diff_blocks() {
+        let patch = "--- a/x.rs\n+++ b/x.rs\n@@ -1 +1 @@\n-old\n+new\n same";
+        let patch = spans(patch).unwrap();
+        let styles: Vec<Style> = patch.iter().filter(|s| s.content != "\n").map(|s| s.style).collect();
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        assert_eq!(styles[..2], [bold, bold]);
+        assert_eq!(styles[2].fg, Some(Color::Cyan));
+        assert_eq!(styles[3].fg, Some(Color::Red));
+        assert_eq!(styles[4].fg, Some(Color::Green));
+        assert_eq!(styles[5], Style::default());
+
+        let block = spans("- a list item\n```diff\n-x\n+y\n```\n- another").unwrap();
+        let red: Vec<&str> = block.iter().filter(|s| s.style.fg == Some(Color::Red)).map(|s| s.content.as_ref()).collect();
+        assert_eq!(red, ["-x"]);
+        assert!(spans("- just a list").is_none());
+    }
+}
@@ -0,0 +1,48 @@
+//! Folding fenced code blocks and quoted sections of a message to one summary line each.
+
+use crate::{Strings, strings};
+
+/// Replaces each fenced code block in `content`, and each run of `>` quoted lines if
+/// `quotes` is set, with a one-line summary such as `▸ ```rust … (200 lines)`.
+///
+/// An unclosed fence folds everything after it, as it would be shown as code anyway.
+pub(crate) fn fold(content: &str, quotes: bool, strings: &Strings) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut lines = content.lines().peekable();
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+        if let Some(lang) = trimmed.strip_prefix("```") {
+            let mut count = 0;
+            for line in lines.by_ref() {
+                if line.trim_start().starts_with("```") {
+                    break;
+                }
+                count += 1;
+            }
+            out.push(strings::fill(&strings.folded_code, &[("lang", &lang.trim()), ("count", &count)]));
+        } else if quotes && trimmed.starts_with('>') {
+            let mut count = 1;
+            while lines.next_if(|line| line.trim_start().starts_with('>')).is_some() {
+                count += 1;
+            }
+            out.push(strings::fill(&strings.folded_quote, &[("count", &count)]));
+        } else {
+            out.push(line.to_string());
+        }
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folds_code_blocks_and_quotes() {
+        let strings = Strings::default();
+        let content = "Here:\n```rust\nfn a() {}\nfn b() {}\n```\n> quoted\n> more\nbye";
+        assert_eq!(fold(content, false, &strings), "Here:\n▸ ```rust … (2 lines)\n> quoted\n> more\nbye");
+        assert_eq!(fold(content, true, &strings), "Here:\n▸ ```rust … (2 lines)\n▸ > … (2 lines)\nbye");
+        assert_eq!(fold("```\nopen", false, &strings), "▸ ``` … (1 lines)");
+    }
+}
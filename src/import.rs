@@ -0,0 +1,416 @@
+//! Reading chat histories exported from other frontends: the OpenAI (ChatGPT)
+//! `conversations.json` export and plain arrays of `{"role", "content"}` messages.
+
+use std::{
+    collections::HashMap,
+    io,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::ChatMessage;
+
+/// A conversation read from an export, see [`import_chat_export`].
+#[derive(Clone, Debug)]
+pub struct ImportedConversation {
+    /// The export's title for the conversation, if it has one.
+    pub title: Option<String>,
+    pub messages: Vec<ChatMessage>,
+}
+
+/// Reads the conversations in a chat export. Recognized layouts are:
+///
+/// - the OpenAI `conversations.json` export, an array of conversations whose messages form
+///   a tree under `mapping`; the branch ending at `current_node` is read,
+/// - a single conversation from that export,
+/// - an array of messages with a `role` and `content`, as sent to chat completion APIs,
+/// - an object with such an array under `messages` and an optional `title`.
+///
+/// Roles become the senders `User`, `AI` and `System`; assistant tool calls and `tool`
+/// messages become [`ChatMessage::tool_call`] and [`ChatMessage::tool_result`]. Content may
+/// be a string or a list of text parts. `create_time`, `timestamp` or `created_at`, in
+/// seconds since the Unix epoch, become timestamps. Messages with no text are skipped.
+///
+/// Documents nested deeper than 128 arrays and objects are rejected as invalid.
+pub fn import_chat_export(json: &str) -> io::Result<Vec<ImportedConversation>> {
+    let value = Parser::new(json).parse().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid or too deeply nested JSON"))?;
+    let unrecognized = || io::Error::new(io::ErrorKind::InvalidData, "unrecognized chat export");
+    match &value {
+        Json::Array(items) if items.iter().all(|item| item.get("mapping").is_some()) && !items.is_empty() => {
+            Ok(items.iter().map(openai_conversation).collect())
+        }
+        Json::Array(items) => Ok(vec![ImportedConversation { title: None, messages: messages(items) }]),
+        Json::Object(_) if value.get("mapping").is_some() => Ok(vec![openai_conversation(&value)]),
+        Json::Object(_) => {
+            let Some(Json::Array(items)) = value.get("messages") else {
+                return Err(unrecognized());
+            };
+            let title = value.get("title").or_else(|| value.get("name")).and_then(Json::as_str).map(str::to_string);
+            Ok(vec![ImportedConversation { title, messages: messages(items) }])
+        }
+        _ => Err(unrecognized()),
+    }
+}
+
+/// The messages on the branch of an OpenAI export's message tree that ends at
+/// `current_node`, oldest first, or every message by time if there is no current node.
+fn openai_conversation(conversation: &Json) -> ImportedConversation {
+    let title = conversation.get("title").and_then(Json::as_str).map(str::to_string);
+    let Some(Json::Object(mapping)) = conversation.get("mapping") else {
+        return ImportedConversation { title, messages: Vec::new() };
+    };
+    let nodes: HashMap<&str, &Json> = mapping.iter().map(|(key, node)| (key.as_str(), node)).collect();
+    let node = |id: &str| nodes.get(id).copied();
+    let mut branch = Vec::new();
+    match conversation.get("current_node").and_then(Json::as_str) {
+        Some(current) => {
+            let mut next = node(current);
+            // Parents are followed at most once per node, so a malformed cycle still ends.
+            while let Some(current) = next
+                && branch.len() < mapping.len()
+            {
+                branch.push(current);
+                next = current.get("parent").and_then(Json::as_str).and_then(node);
+            }
+            branch.reverse();
+        }
+        None => {
+            branch = mapping.iter().map(|(_, node)| node).collect();
+            branch.sort_by(|a, b| {
+                let time = |node: &Json| node.get("message").and_then(|msg| msg.get("create_time")).and_then(Json::as_f64).unwrap_or(0.0);
+                time(a).total_cmp(&time(b))
+            });
+        }
+    }
+    let nodes: Vec<Json> = branch.into_iter().filter_map(|node| node.get("message").cloned()).collect();
+    ImportedConversation { title, messages: messages(&nodes) }
+}
+
+fn messages(items: &[Json]) -> Vec<ChatMessage> {
+    items.iter().flat_map(message).collect()
+}
+
+fn message(item: &Json) -> Vec<ChatMessage> {
+    let author = item.get("author");
+    let role = item.get("role").or_else(|| author.and_then(|author| author.get("role"))).and_then(Json::as_str).unwrap_or("user");
+    let text = item.get("content").map(content_text).unwrap_or_default();
+    let timestamp = ["create_time", "timestamp", "created_at"]
+        .iter()
+        .find_map(|key| item.get(key).and_then(Json::as_f64))
+        // Out-of-range times are dropped rather than trusted.
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .and_then(|since| UNIX_EPOCH.checked_add(since));
+    let stamp = |msg: ChatMessage| match timestamp {
+        Some(time) => msg.with_timestamp(time),
+        None => msg,
+    };
+    let mut out = Vec::new();
+    if role == "tool" {
+        let name = item.get("name").or_else(|| author.and_then(|author| author.get("name"))).and_then(Json::as_str).unwrap_or("tool");
+        if !text.trim().is_empty() {
+            out.push(stamp(ChatMessage::tool_result("Tool", name, text)));
+        }
+        return out;
+    }
+    if !text.trim().is_empty() {
+        out.push(stamp(ChatMessage::new(sender(role), text)));
+    }
+    if let Some(Json::Array(calls)) = item.get("tool_calls") {
+        for call in calls {
+            let function = call.get("function");
+            let field = |key| function.and_then(|function| function.get(key)).and_then(Json::as_str);
+            out.push(stamp(ChatMessage::tool_call(sender(role), field("name").unwrap_or("tool"), field("arguments").unwrap_or(""))));
+        }
+    }
+    out
+}
+
+fn sender(role: &str) -> String {
+    match role {
+        "user" => "User",
+        "assistant" => "AI",
+        "system" | "developer" => "System",
+        role => role,
+    }
+    .to_string()
+}
+
+/// The text of a message's content: a string, a list of strings and `{"text"}` parts, or
+/// an OpenAI export's `{"parts"}`. Other parts, such as images, are left out.
+fn content_text(content: &Json) -> String {
+    match content {
+        Json::String(text) => text.clone(),
+        Json::Array(parts) => parts.iter().map(content_text).filter(|text| !text.is_empty()).collect::<Vec<_>>().join("\n"),
+        Json::Object(_) => match (content.get("parts"), content.get("text")) {
+            (Some(parts), _) => content_text(parts),
+            (None, Some(Json::String(text))) => text.clone(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in the order they appear.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+}
+
+/// Deepest nesting of arrays and objects the parser follows, so a hostile file cannot
+/// overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A recursive descent JSON parser, enough for chat exports without pulling in a JSON crate.
+struct Parser<'a> {
+    rest: &'a str,
+    // Arrays and objects open around the current value.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { rest: text.trim_start_matches('\u{feff}'), depth: 0 }
+    }
+
+    /// The document's value, or `None` if it is not valid JSON.
+    fn parse(mut self) -> Option<Json> {
+        let value = self.value()?;
+        self.skip_space();
+        self.rest.is_empty().then_some(value)
+    }
+
+    fn skip_space(&mut self) {
+        self.rest = self.rest.trim_start_matches([' ', '\t', '\n', '\r']);
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_space();
+        let first = self.rest.chars().next()?;
+        if matches!(first, '{' | '[') {
+            if self.depth == MAX_DEPTH {
+                return None;
+            }
+            self.depth += 1;
+            let value = self.container(first);
+            self.depth -= 1;
+            return value;
+        }
+        match first {
+            '"' => self.string().map(Json::String),
+            _ if self.eat("null") => Some(Json::Null),
+            _ if self.eat("true") => Some(Json::Bool(true)),
+            _ if self.eat("false") => Some(Json::Bool(false)),
+            _ => {
+                let end = self.rest.find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E')).unwrap_or(self.rest.len());
+                let number = self.rest[..end].parse().ok()?;
+                self.rest = &self.rest[end..];
+                Some(Json::Number(number))
+            }
+        }
+    }
+
+    /// The object, or else the array, starting at `open`.
+    fn container(&mut self, open: char) -> Option<Json> {
+        match open {
+            '{' => {
+                self.eat("{");
+                let mut members = Vec::new();
+                if !self.eat("}") {
+                    loop {
+                        self.skip_space();
+                        let key = self.string()?;
+                        if !self.eat(":") {
+                            return None;
+                        }
+                        members.push((key, self.value()?));
+                        if self.eat("}") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return None;
+                        }
+                    }
+                }
+                Some(Json::Object(members))
+            }
+            _ => {
+                self.eat("[");
+                let mut items = Vec::new();
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.value()?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        if !self.eat(",") {
+                            return None;
+                        }
+                    }
+                }
+                Some(Json::Array(items))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.rest = self.rest.strip_prefix('"')?;
+        let mut out = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[i + 1..];
+                    return Some(out);
+                }
+                '\\' => {
+                    let escaped = match chars.next()?.1 {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => {
+                            let high = hex4(&mut chars)?;
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                // A surrogate pair spells one character outside the basic plane.
+                                let (Some((_, '\\')), Some((_, 'u'))) = (chars.next(), chars.next()) else {
+                                    return None;
+                                };
+                                let low = hex4(&mut chars)?;
+                                0x10000 + ((high - 0xD800) << 10) + low.checked_sub(0xDC00)?
+                            } else {
+                                high
+                            };
+                            char::from_u32(code).unwrap_or('\u{fffd}')
+                        }
+                        c => c,
+                    };
+                    out.push(escaped);
+                }
+                c => out.push(c),
+            }
+        }
+        None
+    }
+}
+
+/// The code unit spelled by the four hex digits after a `\u` escape.
+fn hex4(chars: &mut impl Iterator<Item = (usize, char)>) -> Option<u32> {
+    let digits: String = chars.take(4).map(|(_, c)| c).collect();
+    if digits.len() != 4 {
+        return None;
+    }
+    u32::from_str_radix(&digits, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageKind;
+
+    #[test]
+    fn test_openai_export_follows_current_branch() {
+        let export = r#"[{
+            "title": "Rust help",
+            "current_node": "c",
+            "mapping": {
+                "root": {"id": "root", "message": null, "parent": null, "children": ["a"]},
+                "a": {"message": {"author": {"role": "user"}, "create_time": 1700000000.5,
+                      "content": {"content_type": "text", "parts": ["How do I \"borrow\"? 🦀"]}}, "parent": "root"},
+                "old": {"message": {"author": {"role": "assistant"}, "content": {"parts": ["regenerated away"]}}, "parent": "a"},
+                "c": {"message": {"author": {"role": "assistant"}, "content": {"parts": ["Use &."]}}, "parent": "a"}
+            }
+        }]"#;
+        let conversations = import_chat_export(export).unwrap();
+        assert_eq!(conversations[0].title.as_deref(), Some("Rust help"));
+        let messages = &conversations[0].messages;
+        assert_eq!(messages.iter().map(|msg| (msg.sender.as_str(), msg.content.as_str())).collect::<Vec<_>>(), [
+            ("User", "How do I \"borrow\"? 🦀"),
+            ("AI", "Use &.")
+        ]);
+        assert_eq!(messages[0].timestamp, Some(UNIX_EPOCH + Duration::from_millis(1_700_000_000_500)));
+    }
+
+    #[test]
+    fn test_role_content_arrays() {
+        let export = r#"{"title": "Weather", "messages": [
+            {"role": "system", "content": "Be brief."},
+            {"role": "user", "content": [{"type": "text", "text": "Weather?"}, {"type": "image_url"}]},
+            {"role": "assistant", "content": null, "tool_calls": [{"function": {"name": "weather", "arguments": "{\"city\": \"Oslo\"}"}}]},
+            {"role": "tool", "name": "weather", "content": "{\"temp\": -3}"},
+            {"role": "assistant", "content": "It is -3°C."}
+        ]}"#;
+        let conversation = import_chat_export(export).unwrap().remove(0);
+        assert_eq!(conversation.title.as_deref(), Some("Weather"));
+        let senders: Vec<&str> = conversation.messages.iter().map(|msg| msg.sender.as_str()).collect();
+        assert_eq!(senders, ["System", "User", "AI", "Tool", "AI"]);
+        assert_eq!(conversation.messages[1].content, "Weather?");
+        assert_eq!(conversation.messages[2].kind, MessageKind::ToolCall { name: "weather".to_string() });
+        assert_eq!(conversation.messages[2].content, r#"{"city": "Oslo"}"#);
+
+        assert_eq!(import_chat_export(r#"[{"role": "user", "content": "hi"}]"#).unwrap()[0].messages.len(), 1);
+        assert_eq!(import_chat_export("[1, ").unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(import_chat_export(r#"{"foo": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_deep_nesting_is_rejected() {
+        let error = import_chat_export(&"[".repeat(200_000)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert!(import_chat_export(&nested).is_ok());
+        let too_deep = format!("[{nested}]");
+        assert!(import_chat_export(&too_deep).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_timestamps_are_dropped() {
+        for timestamp in ["1e30", "1e19", "-5"] {
+            let export = format!(r#"[{{"role": "user", "content": "hi", "timestamp": {timestamp}}}]"#);
+            let messages = import_chat_export(&export).unwrap().remove(0).messages;
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].timestamp, None);
+        }
+        let export = r#"[{"role": "user", "content": "hi", "timestamp": 60.5}]"#;
+        let messages = import_chat_export(export).unwrap().remove(0).messages;
+        assert_eq!(messages[0].timestamp, Some(UNIX_EPOCH + Duration::from_millis(60_500)));
+    }
+}
@@ -0,0 +1,355 @@
+//! Key bindings for [`ChatApp`](crate::ChatApp).
+
+use std::fmt;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// An operation [`ChatApp`](crate::ChatApp) can perform in response to a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Submit,
+    Newline,
+    Paste,
+    Backspace,
+    CursorLeft,
+    CursorRight,
+    CursorUp,
+    CursorDown,
+    ScrollUp,
+    ScrollDown,
+    OpenSettings,
+    /// Selects the newest message; Up/Down then move the selection and Enter expands it.
+    SelectMessages,
+    /// Starts typing a filter that hides non-matching messages.
+    Filter,
+    /// Opens a popup to jump to a date and time in the chat.
+    JumpToDate,
+    /// Moves the splitter between the chat and the input up, giving the input more room.
+    GrowInput,
+    /// Moves the splitter between the chat and the input down.
+    ShrinkInput,
+    /// Stops the active conversation's streaming messages, see
+    /// [`ChatApp::set_on_cancel_streaming`](crate::ChatApp::set_on_cancel_streaming).
+    CancelStreaming,
+    /// Cycles the markdown preview of the input: off, side by side, preview only.
+    TogglePreview,
+    /// Replaces the misspelled word at the cursor with the next spelling suggestion.
+    NextSuggestion,
+    /// Copies fenced code block N, counting from 1, of the selected message or else of the
+    /// newest message with code, see [`ChatApp::copy_code_block`](crate::ChatApp::copy_code_block).
+    CopyCodeBlock(u8),
+    /// Copies the text selected with the mouse, see
+    /// [`ChatApp::copy_selection`](crate::ChatApp::copy_selection).
+    CopySelection,
+    /// Shows the active conversation side by side with the next one, or closes the split,
+    /// see [`ChatApp::open_split`](crate::ChatApp::open_split).
+    ToggleSplit,
+    /// Moves the focus to the other pane of a split view.
+    SwitchPane,
+    /// Shows the selected message, or else the newest one, in a scrollable popup, see
+    /// [`ChatApp::open_message_detail`](crate::ChatApp::open_message_detail).
+    OpenMessageDetail,
+    /// Discards the draft in the input.
+    ClearInput,
+    /// Moves the input line under the cursor up past the line above it.
+    MoveLineUp,
+    /// Moves the input line under the cursor down past the line below it.
+    MoveLineDown,
+    /// Inserts a copy of the input line under the cursor below it.
+    DuplicateLine,
+    /// Selects the whole draft in the input, so what is typed next replaces it.
+    SelectAll,
+    /// Asks the host to suspend to the shell, see
+    /// [`ChatApp::take_suspend_request`](crate::ChatApp::take_suspend_request).
+    Suspend,
+    Quit,
+}
+
+impl Action {
+    /// Short human-readable description, used for key hints.
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Submit => "send",
+            Action::Newline => "newline",
+            Action::Paste => "paste",
+            Action::Backspace => "delete",
+            Action::CursorLeft => "left",
+            Action::CursorRight => "right",
+            Action::CursorUp => "up",
+            Action::CursorDown => "down",
+            Action::ScrollUp => "scroll up",
+            Action::ScrollDown => "scroll down",
+            Action::OpenSettings => "settings",
+            Action::SelectMessages => "select",
+            Action::Filter => "filter",
+            Action::JumpToDate => "jump to date",
+            Action::GrowInput => "taller input",
+            Action::ShrinkInput => "shorter input",
+            Action::CancelStreaming => "stop",
+            Action::TogglePreview => "preview",
+            Action::NextSuggestion => "next spelling",
+            Action::CopyCodeBlock(_) => "copy code",
+            Action::CopySelection => "copy",
+            Action::ToggleSplit => "split",
+            Action::SwitchPane => "other pane",
+            Action::OpenMessageDetail => "details",
+            Action::ClearInput => "clear",
+            Action::MoveLineUp => "line up",
+            Action::MoveLineDown => "line down",
+            Action::DuplicateLine => "duplicate line",
+            Action::SelectAll => "select all",
+            Action::Suspend => "suspend",
+            Action::Quit => "quit",
+        }
+    }
+}
+
+/// A key together with the modifiers that must be held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub const fn plain(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    pub const fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    /// Whether `key` triggers this binding: same key, with at least these modifiers held.
+    fn matches(&self, key: &KeyEvent) -> bool {
+        self.code == key.code && key.modifiers.contains(self.modifiers)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl"),
+            (KeyModifiers::ALT, "Alt"),
+            (KeyModifiers::SHIFT, "Shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+/// Whether `key` types its character rather than being a shortcut: no modifier other than
+/// Shift, or both Ctrl and Alt, which is how AltGr arrives on Windows.
+pub(crate) fn is_text(key: &KeyEvent) -> bool {
+    let shortcut = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+    shortcut.is_empty() || shortcut == KeyModifiers::CONTROL | KeyModifiers::ALT
+}
+
+/// Maps key bindings to [`Action`]s.
+///
+/// When several bindings match a key, the one requiring the most modifiers wins, so
+/// `Shift+Enter` can mean something different from `Enter`.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use KeyCode::*;
+        let bindings = vec![
+            (KeyBinding::plain(Enter), Action::Submit),
+            (KeyBinding::new(Enter, KeyModifiers::SHIFT), Action::Newline),
+            (KeyBinding::ctrl('j'), Action::Newline),
+            (KeyBinding::ctrl('v'), Action::Paste),
+            (KeyBinding::plain(Backspace), Action::Backspace),
+            (KeyBinding::plain(Left), Action::CursorLeft),
+            (KeyBinding::plain(Right), Action::CursorRight),
+            (KeyBinding::plain(Up), Action::CursorUp),
+            (KeyBinding::plain(Down), Action::CursorDown),
+            (KeyBinding::plain(PageUp), Action::ScrollUp),
+            (KeyBinding::plain(PageDown), Action::ScrollDown),
+            (KeyBinding::plain(F(2)), Action::OpenSettings),
+            (KeyBinding::new(Up, KeyModifiers::CONTROL), Action::SelectMessages),
+            (KeyBinding::ctrl('f'), Action::Filter),
+            (KeyBinding::ctrl('g'), Action::JumpToDate),
+            (KeyBinding::ctrl('p'), Action::TogglePreview),
+            (KeyBinding::ctrl('y'), Action::CopyCodeBlock(1)),
+            (KeyBinding::new(Char('c'), KeyModifiers::ALT), Action::CopySelection),
+            (KeyBinding::new(Char('s'), KeyModifiers::ALT), Action::ToggleSplit),
+            (KeyBinding::plain(F(6)), Action::SwitchPane),
+            (KeyBinding::plain(F(3)), Action::OpenMessageDetail),
+            // Alt+Up/Down move lines as in most editors, so the splitter takes Shift as well.
+            (KeyBinding::new(Up, KeyModifiers::ALT | KeyModifiers::SHIFT), Action::GrowInput),
+            (KeyBinding::new(Down, KeyModifiers::ALT | KeyModifiers::SHIFT), Action::ShrinkInput),
+            (KeyBinding::ctrl('l'), Action::ClearInput),
+            (KeyBinding::new(Up, KeyModifiers::ALT), Action::MoveLineUp),
+            (KeyBinding::new(Down, KeyModifiers::ALT), Action::MoveLineDown),
+            (KeyBinding::ctrl('d'), Action::DuplicateLine),
+            (KeyBinding::ctrl('a'), Action::SelectAll),
+            // Esc stops a streaming reply, and quits once nothing is streaming.
+            (KeyBinding::plain(Esc), Action::CancelStreaming),
+            (KeyBinding::ctrl('c'), Action::Quit),
+            (KeyBinding::plain(Esc), Action::Quit),
+        ];
+        #[cfg(feature = "spellcheck")]
+        let bindings = [bindings, vec![(KeyBinding::plain(F(7)), Action::NextSuggestion)]].concat();
+        #[cfg(all(unix, feature = "suspend"))]
+        let bindings = [bindings, vec![(KeyBinding::ctrl('z'), Action::Suspend)]].concat();
+        Self { bindings }
+    }
+}
+
+/// Built-in keymaps, selectable by name from the settings panel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum KeymapPreset {
+    /// Enter sends, Shift+Enter or Ctrl+J inserts a newline.
+    #[default]
+    Standard,
+    /// Enter inserts a newline, Alt+Enter or Ctrl+S sends. Suited to composing long messages.
+    Multiline,
+}
+
+impl KeymapPreset {
+    pub const ALL: [KeymapPreset; 2] = [KeymapPreset::Standard, KeymapPreset::Multiline];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            KeymapPreset::Standard => "Standard",
+            KeymapPreset::Multiline => "Multiline",
+        }
+    }
+
+    pub fn keymap(self) -> Keymap {
+        let mut keymap = Keymap::default();
+        if self == KeymapPreset::Multiline {
+            keymap.unbind_action(Action::Submit);
+            keymap.unbind(KeyBinding::new(KeyCode::Enter, KeyModifiers::SHIFT));
+            keymap.bind(KeyBinding::plain(KeyCode::Enter), Action::Newline);
+            keymap.bind(KeyBinding::new(KeyCode::Enter, KeyModifiers::ALT), Action::Submit);
+            keymap.bind(KeyBinding::ctrl('s'), Action::Submit);
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /// A keymap with no bindings.
+    pub fn empty() -> Self {
+        Self { bindings: Vec::new() }
+    }
+
+    /// Binds a key to an action, replacing any existing bindings for exactly that key.
+    pub fn bind(&mut self, binding: KeyBinding, action: Action) {
+        self.unbind(binding);
+        self.bindings.push((binding, action));
+    }
+
+    /// Removes the binding for exactly this key, if any.
+    pub fn unbind(&mut self, binding: KeyBinding) {
+        self.bindings.retain(|(b, _)| *b != binding);
+    }
+
+    /// Removes every binding for an action.
+    pub fn unbind_action(&mut self, action: Action) {
+        self.bindings.retain(|(_, a)| *a != action);
+    }
+
+    /// Binds exactly `keys` to an action, replacing the keys it had, for example to choose
+    /// which keys quit.
+    pub fn set_keys(&mut self, action: Action, keys: &[KeyBinding]) {
+        self.unbind_action(action);
+        for &key in keys {
+            self.bind(key, action);
+        }
+    }
+
+    /// Looks up the action for a key event.
+    pub fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .filter(|(b, _)| b.matches(key))
+            .max_by_key(|(b, _)| b.modifiers.bits().count_ones())
+            .map(|(_, a)| *a)
+    }
+
+    /// Every action for a key event, in binding order. More than one is returned when the
+    /// most specific matching key is bound several times, like Esc in the default keymap.
+    pub fn actions_for(&self, key: &KeyEvent) -> Vec<Action> {
+        let matching = self.bindings.iter().filter(|(b, _)| b.matches(key));
+        let Some(most) = matching.clone().map(|(b, _)| b.modifiers.bits().count_ones()).max() else {
+            return Vec::new();
+        };
+        matching.filter(|(b, _)| b.modifiers.bits().count_ones() == most).map(|(_, a)| *a).collect()
+    }
+
+    /// Carries the changes made to `base` that turned it into this keymap over to `onto`, so
+    /// rebinding survives a switch of [`KeymapPreset`].
+    pub(crate) fn rebased(&self, base: &Keymap, mut onto: Keymap) -> Keymap {
+        for binding in &base.bindings {
+            if !self.bindings.contains(binding) {
+                onto.bindings.retain(|b| b != binding);
+            }
+        }
+        for &(key, action) in &self.bindings {
+            if !base.bindings.contains(&(key, action)) && !onto.bindings.contains(&(key, action)) {
+                // Like `bind`, an added key displaces what the other preset gave it.
+                onto.bindings.retain(|&(b, a)| b != key || self.bindings.contains(&(b, a)));
+                onto.bindings.push((key, action));
+            }
+        }
+        onto
+    }
+
+    /// All keys bound to an action, in binding order.
+    pub fn keys_for(&self, action: Action) -> Vec<KeyBinding> {
+        self.bindings.iter().filter(|(_, a)| *a == action).map(|(b, _)| *b).collect()
+    }
+
+    pub fn bindings(&self) -> &[(KeyBinding, Action)] {
+        &self.bindings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_most_specific_binding_wins() {
+        let keymap = Keymap::default();
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        let shift_enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT);
+        let ctrl_enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::CONTROL);
+        assert_eq!(keymap.action_for(&enter), Some(Action::Submit));
+        assert_eq!(keymap.action_for(&shift_enter), Some(Action::Newline));
+        assert_eq!(keymap.action_for(&ctrl_enter), Some(Action::Submit));
+        assert_eq!(keymap.action_for(&KeyEvent::from(KeyCode::Char('c'))), None);
+        assert_eq!(KeyBinding::new(KeyCode::Enter, KeyModifiers::SHIFT).to_string(), "Shift+Enter");
+    }
+
+    #[test]
+    fn test_set_keys_replaces_an_actions_bindings() {
+        let mut keymap = Keymap::default();
+        keymap.set_keys(Action::Quit, &[KeyBinding::ctrl('q'), KeyBinding::ctrl('d')]);
+        assert_eq!(keymap.keys_for(Action::Quit), [KeyBinding::ctrl('q'), KeyBinding::ctrl('d')]);
+        assert_eq!(keymap.action_for(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)), None);
+    }
+}
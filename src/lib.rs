@@ -3,21 +3,353 @@
 //! This crate provides reusable widgets for building chat interfaces in terminal applications
 //! using the ratatui TUI framework.
 
+use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
 use arboard::Clipboard;
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
 };
-use crossterm::event::{MouseEvent, MouseEventKind};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ropey::Rope;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+mod backend;
+mod bidi;
+mod channel_list;
+mod chat_header;
+mod conversations;
+mod date_picker;
+mod demo;
+mod dialog;
+mod diff;
+mod fold;
+mod import;
+mod keymap;
+mod markdown;
+mod math;
+mod message_detail;
+#[cfg(feature = "tracing")]
+mod log_layer;
+mod member_list;
+mod middleware;
+#[cfg(feature = "notifications")]
+mod notifications;
+mod reasoning;
+mod rules;
+mod run_loop;
+mod search;
+mod search_index;
+mod session;
+mod settings;
+#[cfg(feature = "spellcheck")]
+mod spellcheck;
+mod spoiler;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+mod status_bar;
+mod store;
+mod strings;
+#[cfg(all(unix, feature = "suspend"))]
+mod suspend;
+mod table;
+pub mod testing;
+mod theme;
+mod timestamp;
+mod toast;
+mod tokens;
+mod tool_call;
+mod wrap;
+
+pub use backend::{BackendEvent, ChatBackend};
+pub use channel_list::{ChannelEntry, ChannelList};
+pub use chat_header::{ChatHeader, TypingPosition};
+pub use conversations::{Conversation, ConversationManager};
+pub use date_picker::DatePicker;
+pub use demo::DemoConversation;
+pub use dialog::ConfirmDialog;
+pub use import::{ImportedConversation, import_chat_export};
+pub use keymap::{Action, KeyBinding, Keymap, KeymapPreset};
+#[cfg(feature = "tracing")]
+pub use log_layer::{ChatLayer, LogReceiver, level_prefix};
+pub use member_list::{Member, MemberList, Presence};
+pub use message_detail::{DetailAction, MessageDetail};
+pub use middleware::MessageMiddleware;
+#[cfg(feature = "notifications")]
+pub use notifications::{DesktopNotifier, NotifyRule};
+pub use rules::{NotifyActions, NotifyLevel, NotifyRules};
+pub use search_index::{InvertedIndex, SearchIndex};
+pub use session::{ConversationState, SessionState};
+pub use settings::{Settings, SettingsPanel};
+#[cfg(feature = "spellcheck")]
+pub use spellcheck::SpellChecker;
+#[cfg(feature = "sqlite")]
+pub use sqlite_store::SqliteStore;
+pub use status_bar::{ConnectionStatus, StatusBar};
+pub use store::MessageStore;
+pub use strings::Strings;
+pub use theme::{SENDER_PALETTE, Theme, ThemePreset};
+#[cfg(feature = "chrono")]
+pub use timestamp::TimeFormat;
+pub use toast::{Toast, ToastLevel, Toasts};
+pub use tokens::{HeuristicCounter, TokenCounter};
+pub use wrap::WrapPolicy;
 
 /// Represents a single chat message.
-#[derive(Clone, Debug)]
+///
+/// Build one with [`ChatMessage::new`] or another constructor and the `with_` methods;
+/// fields may be added in later versions.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct ChatMessage {
     /// The sender of the message (e.g., "User", "AI")
     pub sender: String,
     /// The content of the message
     pub content: String,
+    /// When the message was sent. [`ChatArea::add_message`] fills this in with the
+    /// current time if it is `None`.
+    pub timestamp: Option<SystemTime>,
+    /// Generation details shown in a footer when [`ChatArea::set_show_metadata`] is on.
+    pub metadata: Option<MessageMetadata>,
+    /// Whether this is plain text or part of a tool call.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub kind: MessageKind,
+    /// What an assistant thought before answering, shown collapsed to one dim row above the
+    /// content. A `<think>…</think>` section at the start of the content is shown the same way.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reasoning: Option<String>,
+}
+
+impl ChatMessage {
+    pub fn new(sender: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            sender: sender.into(),
+            content: content.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Sets the timestamp from a date and time in any timezone, such as a UTC time parsed
+    /// from an export.
+    #[cfg(feature = "chrono")]
+    pub fn with_date_time<Tz: chrono::TimeZone>(self, time: chrono::DateTime<Tz>) -> Self {
+        self.with_timestamp(time.into())
+    }
+
+    pub fn with_metadata(mut self, metadata: MessageMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    pub fn with_reasoning(mut self, reasoning: impl Into<String>) -> Self {
+        self.reasoning = Some(reasoning.into());
+        self
+    }
+
+    /// Replaces the content, such as to caption a [`ChatMessage::voice`] message.
+    pub fn with_content(mut self, content: impl Into<String>) -> Self {
+        self.content = content.into();
+        self
+    }
+
+    /// A request to run the tool `name` with `arguments`, usually a JSON object.
+    pub fn tool_call(sender: impl Into<String>, name: impl Into<String>, arguments: impl Into<String>) -> Self {
+        Self {
+            kind: MessageKind::ToolCall { name: name.into() },
+            ..Self::new(sender, arguments)
+        }
+    }
+
+    /// What the tool `name` returned, usually JSON.
+    pub fn tool_result(sender: impl Into<String>, name: impl Into<String>, result: impl Into<String>) -> Self {
+        Self {
+            kind: MessageKind::ToolResult { name: name.into() },
+            ..Self::new(sender, result)
+        }
+    }
+
+    /// A voice or audio message at `source`, such as a file path or URL, lasting `duration`
+    /// if known. The content starts empty and may be set to a caption or transcript.
+    pub fn voice(sender: impl Into<String>, source: impl Into<String>, duration: Option<Duration>) -> Self {
+        Self {
+            kind: MessageKind::Voice { source: source.into(), duration },
+            ..Self::new(sender, "")
+        }
+    }
+}
+
+/// What a [`ChatMessage`]'s content is.
+///
+/// A tool message shows the tool's name, then its content on one line, cut short. Expanding
+/// it ([`ChatArea::toggle_expanded`], or Enter in selection mode) pretty-prints JSON content
+/// with its syntax colored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum MessageKind {
+    #[default]
+    Text,
+    /// An LLM asking for a tool to run, with the arguments as the content.
+    ToolCall { name: String },
+    /// A tool's output, as the content.
+    ToolResult { name: String },
+    /// A voice or audio message, shown as a `▶ Voice message (0:42)` placeholder above any
+    /// caption in the content. [`ChatApp::set_on_play_voice`] lets the host play it.
+    Voice { source: String, duration: Option<Duration> },
+}
+
+/// Details about how a message was generated, such as an LLM reply's model and token usage.
+///
+/// Every field is optional; only those that are set appear in the footer. Start from
+/// [`MessageMetadata::default`] and use the `with_` methods, as fields may be added.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct MessageMetadata {
+    pub model: Option<String>,
+    /// Time taken to produce the message.
+    pub elapsed: Option<std::time::Duration>,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    /// Cost in US dollars.
+    pub cost: Option<f64>,
+}
+
+impl MessageMetadata {
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = Some(elapsed);
+        self
+    }
+
+    pub fn with_tokens(mut self, prompt: u64, completion: u64) -> Self {
+        self.prompt_tokens = Some(prompt);
+        self.completion_tokens = Some(completion);
+        self
+    }
+
+    pub fn with_cost(mut self, cost: f64) -> Self {
+        self.cost = Some(cost);
+        self
+    }
+}
+
+/// Stable identifier assigned to a message when it is added to a [`ChatArea`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MessageId(pub u64);
+
+/// Where [`ChatArea::scroll_to_message`] places the message in the view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScrollAlignment {
+    /// The message's first row at the top of the view.
+    #[default]
+    Top,
+    /// The message centered vertically.
+    Center,
+    /// The message's last row at the bottom of the view.
+    Bottom,
+}
+
+/// How [`ChatApp`] shows the markdown preview of the input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PreviewMode {
+    #[default]
+    Off,
+    /// The preview next to the input.
+    SideBySide,
+    /// The preview in place of the input, which keeps receiving keys.
+    Only,
+}
+
+impl PreviewMode {
+    /// The mode after this one, for toggling with a single key.
+    pub fn next(self) -> Self {
+        match self {
+            PreviewMode::Off => PreviewMode::SideBySide,
+            PreviewMode::SideBySide => PreviewMode::Only,
+            PreviewMode::Only => PreviewMode::Off,
+        }
+    }
+}
+
+/// How many lines of text an [`InputArea`] shows, and so how much vertical space it claims.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputHeight {
+    /// Grows upward with the text from `min` to `max` lines, then scrolls internally.
+    Grow { min: usize, max: usize },
+    /// Always this many lines, scrolling internally once the text is longer.
+    Fixed(usize),
+}
+
+impl Default for InputHeight {
+    fn default() -> Self {
+        InputHeight::Grow { min: 1, max: 10 }
+    }
+}
+
+impl InputHeight {
+    /// The smallest and largest number of lines, at least one each.
+    fn limits(self) -> (usize, usize) {
+        let (min, max) = match self {
+            InputHeight::Grow { min, max } => (min, max),
+            InputHeight::Fixed(lines) => (lines, lines),
+        };
+        (min.max(1), max.max(min).max(1))
+    }
+}
+
+/// Controls how tightly messages are laid out in a [`ChatArea`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Density {
+    /// One message after another with the sender inline (`"sender: content"`).
+    #[default]
+    Compact,
+    /// Sender on its own line above the content, with a blank line between messages.
+    Cozy,
+    /// Like `Cozy`, with the content additionally padded away from the left edge.
+    Comfortable,
+}
+
+impl Density {
+    /// Whether the sender prefix is rendered on its own line.
+    fn header_line(self) -> bool {
+        !matches!(self, Density::Compact)
+    }
+
+    /// Number of blank lines inserted between consecutive messages.
+    fn gap(self) -> usize {
+        match self {
+            Density::Compact => 0,
+            Density::Cozy | Density::Comfortable => 1,
+        }
+    }
+
+    /// Left padding applied to message content, in columns.
+    fn padding(self) -> usize {
+        match self {
+            Density::Compact | Density::Cozy => 0,
+            Density::Comfortable => 2,
+        }
+    }
 }
 
 /// A widget for displaying and scrolling through chat messages.
@@ -26,12 +358,149 @@ pub struct ChatMessage {
 /// scrolling through message history.
 pub struct ChatArea {
     messages: Vec<ChatMessage>,
+    // Ids of `messages`, in the same order: those of the first `older` messages, which were
+    // prepended, then the rest in ascending order.
+    ids: Vec<MessageId>,
+    older: usize,
+    // The prepended batches as their first id and length, the latest first, each a run of
+    // consecutive ids.
+    older_runs: Vec<(u64, usize)>,
+    // Messages prepended since the last render, to keep the view on the same rows.
+    prepended: usize,
+    next_id: u64,
     // Each message can be multi-line, so we need to track the lines.
     // This is a list of (message_index, line_index) tuples.
     message_lines: Vec<(usize, usize)>,
+    // Styled display rows, parallel to `message_lines`.
+    lines: Vec<Line<'static>>,
     offset: usize,
+    // Number of message rows visible at the last render.
+    viewport_height: usize,
+    // Width messages were wrapped to at the last render.
+    rendered_width: usize,
+    // Where the rows were drawn and the index of the top one, as of the last render.
+    text_area: Rect,
+    top_row: usize,
+    // Row and column where a mouse selection started, and the selected cells between its
+    // first and last, inclusive.
+    selection_anchor: Option<(usize, usize)>,
+    text_selection: Option<((usize, usize), (usize, usize))>,
+    // In a split view, the conversation name drawn instead of the chat title and whether
+    // the pane has focus.
+    pane: Option<(String, bool)>,
     scrollbar_state: ScrollbarState,
     auto_scroll: bool,
+    #[cfg(feature = "ansi-to-tui")]
+    parse_ansi: bool,
+    prefix_formatter: PrefixFormatter,
+    // Colors senders are assigned from by name; empty to use the theme's sender style.
+    sender_colors: Vec<Color>,
+    // Presence of senders the host has reported, shown as a dot before their names, and
+    // that of the conversation's members, for senders the host has not reported.
+    presence: HashMap<String, Presence>,
+    member_presence: HashMap<String, Presence>,
+    notify_rules: NotifyRules,
+    hanging_indent: bool,
+    wrap_policy: WrapPolicy,
+    single_line: bool,
+    code_no_wrap: bool,
+    label_code_blocks: bool,
+    // Columns each message's unwrapped code is scrolled to the right.
+    code_offsets: HashMap<MessageId, usize>,
+    rtl_alignment: bool,
+    density: Density,
+    gutter: Option<(usize, GutterFormatter)>,
+    show_position: bool,
+    position_formatter: Option<PositionFormatter>,
+    sticky_header: Option<LabelFormatter>,
+    pinned: Vec<MessageId>,
+    last_read: Option<MessageId>,
+    // The newest message each participant has read, in the order they were first set.
+    read_markers: Vec<(String, MessageId)>,
+    pin_cursor: Option<usize>,
+    // Messages still receiving content, in the order they started.
+    streaming: Vec<MessageId>,
+    // The content streamed into those messages so far, as it arrived, when the shown
+    // message is what the message filter made of it. See `ChatApp::append_to_message`.
+    streamed: HashMap<MessageId, ChatMessage>,
+    selected: Option<MessageId>,
+    selection_style: Style,
+    // Whether messages the filter rejects are dimmed instead of hidden.
+    dim_unmatched: bool,
+    // Messages taller than this many rows are cut short with a "show more" footer.
+    collapse_after: Option<usize>,
+    expanded: HashSet<MessageId>,
+    // Messages whose code blocks, and quotes if `fold_quotes`, are folded to summaries.
+    folded: HashSet<MessageId>,
+    fold_quotes: bool,
+    parse_spoilers: bool,
+    render_tables: bool,
+    render_diffs: bool,
+    render_inline_code: bool,
+    render_math: bool,
+    revealed: HashSet<MessageId>,
+    show_pinned: bool,
+    show_timestamps: bool,
+    // Messages up to this old show a relative time instead of the clock time.
+    relative_timestamps: Option<Duration>,
+    // The time relative timestamps are measured from, as of the last `tick`.
+    now: Option<SystemTime>,
+    #[cfg(feature = "chrono")]
+    time_format: TimeFormat,
+    show_metadata: bool,
+    smooth_scroll: bool,
+    filter: String,
+    search_index: Option<Box<dyn SearchIndex>>,
+    filter_regex: bool,
+    // The compiled filter, or why it failed to compile. `None` while no filter is set.
+    matcher: Option<Result<search::Matcher, String>>,
+    // Offset currently drawn while a smooth scroll animates towards `offset`.
+    shown_offset: Option<usize>,
+    theme: Theme,
+    strings: Strings,
+    // Whether anything shown has changed since the last render.
+    dirty: bool,
+}
+
+/// Callback that renders the sender prefix shown before a message's content.
+pub type PrefixFormatter = Box<dyn Fn(&ChatMessage) -> Span<'static>>;
+
+/// Callback that renders the gutter cell shown on the first row of a message.
+pub type GutterFormatter = Box<dyn Fn(&ChatMessage) -> Span<'static>>;
+
+/// Callback that renders the scroll position shown after the chat title, given the bottom
+/// visible row and the number of rows, both counted from 1.
+pub type PositionFormatter = Box<dyn Fn(usize, usize) -> String>;
+
+/// Callback that maps a message to a text label, such as its day or group.
+pub type LabelFormatter = Box<dyn Fn(&ChatMessage) -> String>;
+
+/// Callback told which conversation's streaming message the user cancelled, see
+/// [`ChatApp::set_on_cancel_streaming`].
+pub type CancelHandler = Box<dyn FnMut(usize, MessageId)>;
+
+/// Callback asked to play a voice message, with its conversation index, id and source, see
+/// [`ChatApp::set_on_play_voice`].
+pub type VoiceHandler = Box<dyn FnMut(usize, MessageId, &str)>;
+
+/// Callback that may rewrite a message, given its conversation index, before it is stored,
+/// or reject it by returning `false`. See [`ChatApp::set_message_filter`].
+pub type MessageFilter = Box<dyn FnMut(usize, &mut ChatMessage) -> bool>;
+
+/// Callback told about a message stored in a conversation, see [`ChatApp::set_on_message_added`].
+pub type MessageHandler = Box<dyn FnMut(usize, MessageId, &ChatMessage)>;
+
+/// Callback told which conversation's view scrolled, see [`ChatApp::set_on_scrolled_away_from_bottom`].
+pub type ScrollHandler = Box<dyn FnMut(usize)>;
+
+/// Callback receiving the text the user sent in a conversation, see [`ChatApp::set_on_submit`].
+pub type SubmitHandler = Box<dyn FnMut(usize, &str)>;
+
+/// Callback receiving new messages as plain text lines, see [`ChatApp::set_announcer`].
+pub type Announcer = Box<dyn FnMut(&str)>;
+
+fn default_prefix(msg: &ChatMessage) -> Span<'static> {
+    Span::raw(format!("{}: ", msg.sender))
 }
 
 impl Default for ChatArea {
@@ -44,562 +513,6694 @@ impl ChatArea {
     pub fn new() -> Self {
         Self {
             messages: Vec::new(),
+            ids: Vec::new(),
+            older: 0,
+            older_runs: Vec::new(),
+            prepended: 0,
+            next_id: 0,
             message_lines: Vec::new(),
+            lines: Vec::new(),
             offset: 0,
+            viewport_height: 0,
+            rendered_width: 0,
+            text_area: Rect::default(),
+            top_row: 0,
+            selection_anchor: None,
+            text_selection: None,
+            pane: None,
             scrollbar_state: ScrollbarState::default(),
             auto_scroll: true,
+            #[cfg(feature = "ansi-to-tui")]
+            parse_ansi: false,
+            prefix_formatter: Box::new(default_prefix),
+            sender_colors: Vec::new(),
+            presence: HashMap::new(),
+            member_presence: HashMap::new(),
+            notify_rules: NotifyRules::new(),
+            hanging_indent: false,
+            wrap_policy: WrapPolicy::default(),
+            single_line: false,
+            code_no_wrap: false,
+            label_code_blocks: false,
+            code_offsets: HashMap::new(),
+            rtl_alignment: false,
+            density: Density::default(),
+            gutter: None,
+            show_position: false,
+            position_formatter: None,
+            sticky_header: None,
+            pinned: Vec::new(),
+            last_read: None,
+            read_markers: Vec::new(),
+            pin_cursor: None,
+            streaming: Vec::new(),
+            streamed: HashMap::new(),
+            selected: None,
+            selection_style: Style::default().add_modifier(Modifier::REVERSED),
+            dim_unmatched: false,
+            collapse_after: None,
+            expanded: HashSet::new(),
+            folded: HashSet::new(),
+            fold_quotes: false,
+            parse_spoilers: false,
+            render_tables: false,
+            render_diffs: false,
+            render_inline_code: false,
+            render_math: false,
+            revealed: HashSet::new(),
+            show_pinned: false,
+            show_timestamps: false,
+            relative_timestamps: None,
+            now: None,
+            #[cfg(feature = "chrono")]
+            time_format: TimeFormat::default(),
+            show_metadata: false,
+            smooth_scroll: false,
+            filter: String::new(),
+            search_index: None,
+            filter_regex: false,
+            matcher: None,
+            shown_offset: None,
+            theme: ThemePreset::default().theme(),
+            strings: Strings::default(),
+            dirty: true,
         }
     }
 
-    pub fn add_message(&mut self, msg: ChatMessage) {
-        self.messages.push(msg);
-        self.auto_scroll = true;
+    /// Shows each message's time (`HH:MM`, UTC unless set with `set_time_format`) before the sender prefix.
+    pub fn set_show_timestamps(&mut self, show: bool) {
+        self.dirty |= self.show_timestamps != show;
+        self.show_timestamps = show;
     }
 
-    pub fn scroll_up(&mut self, lines: usize) {
-        self.offset = self.offset.saturating_sub(lines);
-        self.auto_scroll = false;
+    /// Sets the timezone and patterns timestamps are shown with, for example
+    /// [`TimeFormat::local`] for the user's local time.
+    #[cfg(feature = "chrono")]
+    pub fn set_time_format(&mut self, time_format: TimeFormat) {
+        self.dirty |= self.time_format != time_format;
+        self.time_format = time_format;
     }
 
-    pub fn scroll_down(&mut self, lines: usize) {
-        let content_length = self.message_lines.len();
-        let max_scroll = content_length.saturating_sub(1);
-        self.offset = (self.offset + lines).min(max_scroll);
-        if self.offset == max_scroll {
-            self.auto_scroll = true;
-        }
+    /// Shows timestamps of messages up to `max_age` old as relative times such as `5m ago`,
+    /// and older ones as the clock time. `None` always shows the clock time.
+    ///
+    /// Relative times are measured from the time last given to [`ChatArea::tick`].
+    pub fn set_relative_timestamps(&mut self, max_age: Option<Duration>) {
+        self.dirty |= self.relative_timestamps != max_age;
+        self.relative_timestamps = max_age;
     }
 
-    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
-        let visible_width = area.width.saturating_sub(2) as usize; // account for borders
-        let visible_height = area.height.saturating_sub(2) as usize;
-
-        // If width is zero, we can't render anything.
-        if visible_width == 0 {
-            return;
-        }
+    /// Shows a dim, right-aligned footer under messages that carry [`MessageMetadata`].
+    pub fn set_show_metadata(&mut self, show: bool) {
+        self.dirty |= self.show_metadata != show;
+        self.show_metadata = show;
+    }
 
-        // Re-calculate message_lines whenever we render
-        self.message_lines.clear();
-        for (i, msg) in self.messages.iter().enumerate() {
-            let content = format!("{}: {}", msg.sender, msg.content);
-            let lines = textwrap::wrap(&content, visible_width);
-            for j in 0..lines.len() {
-                self.message_lines.push((i, j));
-            }
-        }
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.dirty |= self.theme != theme;
+        self.theme = theme;
+    }
 
-        let total_lines = self.message_lines.len();
+    /// Sets the titles drawn on the chat and pinned panel borders.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.dirty |= self.strings != strings;
+        self.strings = strings;
+    }
 
-        let max_offset = total_lines.saturating_sub(visible_height);
-        if self.auto_scroll {
-            self.offset = max_offset;
-        }
-        self.offset = self.offset.min(max_offset);
+    /// Titles the chat with a conversation name and mutes its border unless `focused`, for
+    /// the panes of [`ChatApp`]'s split view. `None` restores the usual title.
+    pub(crate) fn set_pane(&mut self, pane: Option<(String, bool)>) {
+        self.pane = pane;
+    }
 
+    fn block<'a>(&self, title: &'a str) -> Block<'a> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(self.theme.border)
+            .border_set(self.theme.border_set())
+            .title(title)
+            .title_style(self.theme.title)
+    }
 
-        // Slice the lines to show only visible ones
-        let items: Vec<ListItem> = self.message_lines.iter().skip(self.offset).take(visible_height).map(|(msg_idx, line_idx)| {
-            let msg = &self.messages[*msg_idx];
-            let content = format!("{}: {}", msg.sender, msg.content);
-            let lines = textwrap::wrap(&content, visible_width);
-            ListItem::new(lines[*line_idx].to_string())
-        }).collect();
+    /// Pins a section label for the topmost visible message onto the top border.
+    ///
+    /// `label` maps a message to the group it belongs to, such as its day or sender, so
+    /// the current group stays visible while scrolling through long transcripts.
+    pub fn set_sticky_header(&mut self, label: impl Fn(&ChatMessage) -> String + 'static) {
+        self.dirty = true;
+        self.sticky_header = Some(Box::new(label));
+    }
 
-        let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Chat"));
+    /// Removes the sticky header label.
+    pub fn clear_sticky_header(&mut self) {
+        self.dirty = true;
+        self.sticky_header = None;
+    }
 
-        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(Some("↑"))
-            .end_symbol(Some("↓"));
+    /// Returns the sticky header label for the topmost visible row, as of the last render.
+    pub fn sticky_header(&self) -> Option<String> {
+        let label = self.sticky_header.as_ref()?;
+        let (msg_idx, _) = self.message_lines.get(self.offset)?;
+        Some(label(&self.messages[*msg_idx]))
+    }
 
-        // Update scrollbar state
-        self.scrollbar_state = self.scrollbar_state.content_length(total_lines.saturating_sub(visible_height));
-        self.scrollbar_state = self.scrollbar_state.position(self.offset);
+    /// Adds a fixed-width column to the left of the message content.
+    ///
+    /// The formatter's output is shown on the first row of each message, padded or
+    /// truncated to `width` columns; continuation rows leave the gutter blank. Content is
+    /// wrapped to the remaining width, so it stays aligned regardless of gutter contents.
+    pub fn set_gutter(&mut self, width: u16, formatter: impl Fn(&ChatMessage) -> Span<'static> + 'static) {
+        self.dirty = true;
+        self.gutter = Some((width as usize, Box::new(formatter)));
+    }
 
-        let split = Layout::horizontal([Constraint::Min(1), Constraint::Length(1)]).split(area);
-        frame.render_widget(list, split[0]);
-        frame.render_stateful_widget(scrollbar, split[1], &mut self.scrollbar_state);
+    /// Removes the gutter column.
+    pub fn clear_gutter(&mut self) {
+        self.dirty = true;
+        self.gutter = None;
     }
 
-}
+    /// Shows how far through the history the view is after the chat title, such as
+    /// `Chat — 120/3400 (96%)`, while the messages do not fit. Rows are wrapped rows, counted
+    /// to the bottom of the view. Off by default.
+    pub fn set_show_scroll_position(&mut self, show: bool) {
+        self.dirty |= self.show_position != show;
+        self.show_position = show;
+    }
 
-/// A widget for multiline text input with cursor navigation.
-///
-/// Supports typing, backspace, cursor movement (arrows, up/down for lines),
-/// and handles text wrapping and scrolling for long inputs.
-pub struct InputArea {
-    buffer: String,      // current typed text
-    cursor: usize,       // cursor position in buffer
-    offset: usize,       // scroll offset for display
-}
+    pub fn show_scroll_position(&self) -> bool {
+        self.show_position
+    }
 
-impl Default for InputArea {
-    fn default() -> Self {
-        Self::new()
+    /// Sets the callback that renders the scroll position, given the bottom visible row and
+    /// the number of rows. The default fills [`Strings::title_position`].
+    pub fn set_position_formatter(&mut self, formatter: impl Fn(usize, usize) -> String + 'static) {
+        self.dirty = true;
+        self.position_formatter = Some(Box::new(formatter));
     }
-}
 
-impl InputArea {
-    const MAX_DISPLAY_LINES: usize = 10;
-    pub fn new() -> Self {
-        Self {
-            buffer: String::new(),
-            cursor: 0,
-            offset: 0,
+    /// The scroll position text after the chat title, if shown.
+    fn position_title(&self, shown_offset: usize, visible_height: usize) -> Option<String> {
+        let total = self.message_lines.len();
+        if !self.show_position || total <= visible_height {
+            return None;
         }
+        let line = (shown_offset + visible_height).min(total);
+        Some(match &self.position_formatter {
+            Some(formatter) => formatter(line, total),
+            None => {
+                let percent = line * 100 / total;
+                strings::fill(&self.strings.title_position, &[("line", &line), ("total", &total), ("percent", &percent)])
+            }
+        })
     }
 
-    pub fn calculate_display_lines(&self, width: u16) -> u16 {
-        let effective_width = width.saturating_sub(4); // 2 for borders, 2 for "> "
-        if effective_width == 0 {
-            return 2;
-        }
-        let logical_lines: Vec<&str> = self.buffer.split('\n').collect();
-        let mut total_lines = 0;
-        for line in logical_lines {
-            let line_len = line.len() as f32;
-            let wrapped = (line_len / effective_width as f32).ceil() as usize;
-            total_lines += wrapped.max(1);
-        }
-        let visible_lines = total_lines.min(Self::MAX_DISPLAY_LINES);
-        (visible_lines as u16) + 2 // +2 for top and bottom borders
+    /// Sets the layout density used when rendering messages.
+    pub fn set_density(&mut self, density: Density) {
+        self.dirty |= self.density != density;
+        self.density = density;
     }
 
-    pub fn insert_char(&mut self, ch: char) {
-        let ch = if ch == '\r' { '\n' } else { ch };
-        if self.cursor > self.buffer.len() {
-            self.cursor = self.buffer.len();
-        }
-        self.buffer.insert(self.cursor, ch);
-        self.cursor += ch.len_utf8();
+    pub fn density(&self) -> Density {
+        self.density
     }
 
-    /// Inserts a string into the buffer at the cursor position, normalizing line endings to \n.
-    pub fn insert_str(&mut self, s: &str) {
-        let normalized = s.replace("\r\n", "\n").replace('\r', "\n");
-        for ch in normalized.chars() {
-            self.insert_char(ch);
-        }
+    /// When enabled, wrapped continuation lines are indented to align under the message
+    /// body instead of starting at column 0 beneath the sender prefix.
+    pub fn set_hanging_indent(&mut self, enabled: bool) {
+        self.dirty |= self.hanging_indent != enabled;
+        self.hanging_indent = enabled;
     }
 
-    pub fn backspace(&mut self) {
-        if self.cursor > 0 {
-            // Find the start of the char before cursor
-            let mut prev_start = 0;
-            for (i, _) in self.buffer.char_indices() {
-                if i >= self.cursor {
-                    break;
-                }
-                prev_start = i;
-            }
-            self.buffer.remove(prev_start);
-            self.cursor = prev_start;
-        }
+    /// Sets how text that does not fit a row is wrapped, such as whether long URLs are
+    /// broken at the edge or kept whole.
+    pub fn set_wrap_policy(&mut self, policy: WrapPolicy) {
+        self.dirty |= self.wrap_policy != policy;
+        self.wrap_policy = policy;
     }
 
-    pub fn cursor_left(&mut self) {
-        if self.cursor > 0 {
-            // Find the previous char boundary
-            let mut prev = 0;
-            for (i, _) in self.buffer.char_indices() {
-                if i >= self.cursor {
-                    break;
-                }
-                prev = i;
-            }
-            self.cursor = prev;
-        }
+    pub fn wrap_policy(&self) -> WrapPolicy {
+        self.wrap_policy
     }
 
-    pub fn cursor_right(&mut self) {
-        if self.cursor < self.buffer.len() {
-            if let Some((i, _)) = self.buffer.char_indices().find(|(i, _)| *i > self.cursor) {
-                self.cursor = i;
-            } else {
-                // at the last char, move to end
-                self.cursor = self.buffer.len();
-            }
-        }
+    /// Shows each message on exactly one row, with line breaks as spaces and anything that
+    /// does not fit cut short with an ellipsis, for dense log or console panes. The selected
+    /// message is shown in full. Pair it with [`Density::Compact`] to drop the gaps between
+    /// messages.
+    pub fn set_single_line(&mut self, enabled: bool) {
+        self.dirty |= self.single_line != enabled;
+        self.single_line = enabled;
     }
 
-    fn find_current_line_col(&self) -> (usize, usize) {
-        let lines: Vec<&str> = self.buffer.split('\n').collect();
-        let mut pos = 0; // byte position
-        let mut current_line = 0;
-        let mut current_col = 0;
-        for (i, line) in lines.iter().enumerate() {
-            let line_bytes = line.len();
-            if pos + line_bytes >= self.cursor {
-                current_line = i;
-                current_col = self.buffer[pos..self.cursor].chars().count();
-                break;
-            }
-            pos += line_bytes + 1; // +1 for \n
-        }
-        (current_line, current_col)
+    pub fn single_line(&self) -> bool {
+        self.single_line
     }
 
-    pub fn cursor_up(&mut self) {
-        let lines: Vec<&str> = self.buffer.split('\n').collect();
-        if lines.is_empty() {
-            return;
-        }
-        let (current_line, current_col) = self.find_current_line_col();
-        if current_line > 0 {
-            let prev_line = lines[current_line - 1];
-            let prev_line_chars: Vec<char> = prev_line.chars().collect();
-            let new_col = current_col.min(prev_line_chars.len());
-            // Calculate byte position of prev line start
-            let mut prev_line_start = 0;
-            for line in lines.iter().take(current_line - 1) {
-                prev_line_start += line.len() + 1;
-            }
-            // Add byte offset for new_col chars
-            let mut byte_offset = 0;
-            for ch in prev_line.chars().take(new_col) {
-                byte_offset += ch.len_utf8();
-            }
-            self.cursor = prev_line_start + byte_offset;
-        }
+    /// Shows each line of a fenced code block on one row instead of wrapping it, cut short
+    /// with `›` (`>` with plain glyphs) where it does not fit. [`ChatArea::scroll_code_right`] scrolls a message's
+    /// code sideways, and the chat's bottom border shows the visible columns of the
+    /// selected message's code.
+    pub fn set_code_no_wrap(&mut self, enabled: bool) {
+        self.dirty |= self.code_no_wrap != enabled;
+        self.code_no_wrap = enabled;
     }
 
-    pub fn cursor_down(&mut self) {
-        let lines: Vec<&str> = self.buffer.split('\n').collect();
-        if lines.is_empty() {
-            return;
-        }
-        let (current_line, current_col) = self.find_current_line_col();
-        if current_line < lines.len() - 1 {
-            let next_line = lines[current_line + 1];
-            let next_line_chars: Vec<char> = next_line.chars().collect();
-            let new_col = current_col.min(next_line_chars.len());
-            // Calculate byte position of next line start
-            let mut next_line_start = 0;
-            for line in lines.iter().take(current_line + 1) {
-                next_line_start += line.len() + 1;
-            }
-            // Add byte offset for new_col chars
-            let mut byte_offset = 0;
-            for ch in next_line.chars().take(new_col) {
-                byte_offset += ch.len_utf8();
-            }
-            self.cursor = next_line_start + byte_offset;
-        }
+    pub fn code_no_wrap(&self) -> bool {
+        self.code_no_wrap
     }
 
-    pub fn newline(&mut self) {
-        self.insert_char('\n');
+    /// Scrolls a message's unwrapped code blocks `columns` to the right, as far as its
+    /// widest line allows.
+    pub fn scroll_code_right(&mut self, id: MessageId, columns: usize) {
+        self.dirty = true;
+        *self.code_offsets.entry(id).or_default() += columns;
     }
 
-    pub fn submit(&mut self) -> String {
-        let input = self.buffer.clone();
-        self.buffer.clear();
-        self.cursor = 0;
-        self.offset = 0;
-        input
+    pub fn scroll_code_left(&mut self, id: MessageId, columns: usize) {
+        self.dirty = true;
+        if let Some(offset) = self.code_offsets.get_mut(&id) {
+            *offset = offset.saturating_sub(columns);
+        }
     }
 
-    pub fn scroll_up(&mut self, lines: usize) {
-        self.offset = self.offset.saturating_sub(lines);
+    /// Numbers each message's fenced code blocks, as in ```` ```rust [1] ````, to go with
+    /// [`ChatArea::code_blocks`].
+    pub fn set_label_code_blocks(&mut self, enabled: bool) {
+        self.dirty |= self.label_code_blocks != enabled;
+        self.label_code_blocks = enabled;
     }
 
-    pub fn scroll_down(&mut self, lines: usize) {
-        self.offset += lines;
+    pub fn label_code_blocks(&self) -> bool {
+        self.label_code_blocks
     }
 
-    pub fn get_offset(&self) -> usize {
-        self.offset
+    /// The contents of a message's fenced code blocks, in order and without the fences.
+    /// Empty if the id is unknown.
+    pub fn code_blocks(&self, id: MessageId) -> Vec<String> {
+        self.index_of(id).map_or_else(Vec::new, |i| markdown::code_blocks(&self.messages[i].content))
     }
 
-    fn calculate_display_index(&self) -> usize {
-        let prefix = "> ";
-        let count_nl = self.buffer[..self.cursor].chars().filter(|&ch| ch == '\n').count();
-        prefix.len() + self.cursor + count_nl * prefix.len()
+    /// Columns a message's unwrapped code is scrolled to the right, as of the last render.
+    pub fn code_offset(&self, id: MessageId) -> usize {
+        self.code_offsets.get(&id).copied().unwrap_or(0)
     }
 
-    fn calculate_cursor_line(&self, display: &str) -> usize {
-        let display_index = self.calculate_display_index();
-        let mut current_line = 0;
-        let mut byte_index = 0;
-        for ch in display.chars() {
-            if byte_index >= display_index {
-                return current_line;
-            }
-            if ch == '\n' {
-                current_line += 1;
-            }
-            byte_index += ch.len_utf8();
-        }
-        current_line
+    /// When enabled, messages whose content is predominantly right-to-left (Arabic, Hebrew, ...)
+    /// are aligned to the right edge. Mixed-direction text is reordered for display either way.
+    pub fn set_rtl_alignment(&mut self, enabled: bool) {
+        self.dirty |= self.rtl_alignment != enabled;
+        self.rtl_alignment = enabled;
     }
 
-    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
-        let full_display = format!("> {}", self.buffer.replace('\n', "\n> "));
-        let lines: Vec<&str> = full_display.lines().collect();
-        let total_lines = lines.len();
-        let cursor_line = self.calculate_cursor_line(&full_display);
-        let max_offset = total_lines.saturating_sub(Self::MAX_DISPLAY_LINES);
-
-        // Auto-scroll to keep cursor visible
-        if cursor_line < self.offset {
-            self.offset = cursor_line;
-        } else if cursor_line >= self.offset + Self::MAX_DISPLAY_LINES {
-            self.offset = cursor_line.saturating_sub(Self::MAX_DISPLAY_LINES - 1);
+    /// Colors each sender's prefix with a color from `palette`, chosen by a hash of the name
+    /// so a sender keeps the same color across messages and runs. [`SENDER_PALETTE`] is a
+    /// good default; an empty palette goes back to the theme's sender style.
+    ///
+    /// Colors set by a custom prefix formatter take precedence.
+    pub fn set_sender_colors(&mut self, palette: &[Color]) {
+        self.dirty |= self.sender_colors != palette;
+        self.sender_colors = palette.to_vec();
+    }
+
+    /// Sets the presence of `sender`, shown as a status dot before the sender's name.
+    /// Messages from offline senders are dimmed with the theme's muted style. Senders
+    /// without a presence get no dot. Returns whether it changed.
+    ///
+    /// This takes precedence over the presence [`ChatApp::set_presence_in_chat`] copies
+    /// from the member list.
+    pub fn set_presence(&mut self, sender: &str, presence: Presence) -> bool {
+        if self.presence.get(sender) == Some(&presence) {
+            return false;
         }
-        self.offset = self.offset.min(max_offset);
+        self.dirty = true;
+        self.presence.insert(sender.to_string(), presence);
+        true
+    }
 
-        // Slice visible lines
-        let end = (self.offset + Self::MAX_DISPLAY_LINES).min(total_lines);
-        let visible_lines = &lines[self.offset..end];
-        let display = visible_lines.join("\n");
+    pub fn presence(&self, sender: &str) -> Option<Presence> {
+        self.presence.get(sender).or_else(|| self.member_presence.get(sender)).copied()
+    }
 
-        let paragraph = Paragraph::new(display)
-            .wrap(Wrap { trim: false })
-            .block(Block::default().borders(Borders::ALL).title("Input"));
-        frame.render_widget(paragraph, area);
+    /// Forgets the presence set with [`ChatArea::set_presence`], removing those dots.
+    pub fn clear_presence(&mut self) {
+        self.dirty = true;
+        self.presence.clear();
     }
 
+    /// Sets the presence copied from the conversation's member list.
+    fn set_member_presence(&mut self, presence: HashMap<String, Presence>) {
+        if self.member_presence != presence {
+            self.member_presence = presence;
+            self.dirty = true;
+        }
+    }
 
-}
+    /// Highlights messages that `rules` classify at [`NotifyLevel::Low`] or above with the
+    /// theme's highlight style. Like the filter, the rules do not see hidden spoilers.
+    pub fn set_notify_rules(&mut self, rules: NotifyRules) {
+        self.dirty |= self.notify_rules != rules;
+        self.notify_rules = rules;
+    }
 
-/// A complete chat application coordinator.
-///
-/// Combines ChatArea and InputArea into a full chat interface.
-/// Handles key events and rendering. Useful for quick prototyping or as a reference
-/// for integrating the individual widgets.
-pub struct ChatApp {
-    chat_area: ChatArea,
-    input_area: InputArea,
-    should_quit: bool,
-    cursor_pos: Option<(u16, u16)>,
-    chat_rect: Rect,
-}
+    pub fn notify_rules(&self) -> &NotifyRules {
+        &self.notify_rules
+    }
 
-impl Default for ChatApp {
-    fn default() -> Self {
-        Self::new()
+    /// Sets the callback used to render the prefix before each message.
+    ///
+    /// The default renders `"sender: "`. Return an empty span to hide the prefix entirely;
+    /// wrapping always accounts for the width of whatever the formatter returns.
+    pub fn set_prefix_formatter(&mut self, formatter: impl Fn(&ChatMessage) -> Span<'static> + 'static) {
+        self.dirty = true;
+        self.prefix_formatter = Box::new(formatter);
     }
-}
 
-impl ChatApp {
-    pub fn new() -> Self {
-        Self {
-            chat_area: ChatArea::new(),
-            input_area: InputArea::new(),
-            should_quit: false,
-            cursor_pos: None,
-            chat_rect: Rect::default(),
-        }
+    /// Enables or disables parsing of ANSI SGR escape sequences in message content.
+    ///
+    /// When enabled, colors and text attributes from sequences such as `\x1b[31m` are
+    /// rendered as styled spans instead of showing the raw escape bytes.
+    #[cfg(feature = "ansi-to-tui")]
+    pub fn set_parse_ansi(&mut self, enabled: bool) {
+        self.dirty |= self.parse_ansi != enabled;
+        self.parse_ansi = enabled;
     }
 
-    pub fn on_key(&mut self, key: crossterm::event::KeyEvent) {
-        use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
-        if key.kind != KeyEventKind::Press {
+    /// Hides text marked `||like this||` behind `▒` blocks until the message is revealed
+    /// with [`ChatArea::toggle_revealed`]. Off by default, since `||` also appears in code.
+    /// Hidden spoilers do not match the filter or searches, and stay hidden in announcements
+    /// and notifications.
+    pub fn set_parse_spoilers(&mut self, enabled: bool) {
+        if self.parse_spoilers == enabled {
             return;
         }
-        match key.code {
-            KeyCode::Enter => {
-                if key.modifiers.contains(KeyModifiers::SHIFT) {
-                    self.input_area.newline();
-                } else {
-                    let input = self.input_area.submit();
-                    if !input.trim().is_empty() {
-                        self.chat_area.add_message(ChatMessage {
-                            sender: "User".to_string(),
-                            content: input,
-                        });
-                        // Simulate AI response
-                        self.chat_area.add_message(ChatMessage {
-                            sender: "AI".to_string(),
-                            content: "Hello! This is a simulated response.".to_string(),
-                        });
-                    }
-                }
+        self.dirty = true;
+        self.parse_spoilers = enabled;
+        self.rebuild_search_index();
+    }
+
+    /// Draws markdown pipe tables in message content as aligned, box-drawn tables. Columns
+    /// are narrowed and their cells wrapped when a table is wider than the chat.
+    pub fn set_render_tables(&mut self, enabled: bool) {
+        self.dirty |= self.render_tables != enabled;
+        self.render_tables = enabled;
+    }
+
+    /// Colors unified diffs, whether a whole message is a patch or it sits in a ```` ```diff ````
+    /// block: added lines green, removed lines red, hunk headers cyan and file headers bold.
+    pub fn set_render_diffs(&mut self, enabled: bool) {
+        self.dirty |= self.render_diffs != enabled;
+        self.render_diffs = enabled;
+    }
+
+    /// Shows `inline code` in an accent color without its backticks, without rendering any
+    /// other markdown. Fenced code blocks are left as written.
+    pub fn set_render_inline_code(&mut self, enabled: bool) {
+        self.dirty |= self.render_inline_code != enabled;
+        self.render_inline_code = enabled;
+    }
+
+    /// Rewrites simple inline math such as `$x^2 + \alpha$` in unicode, as `x² + α`. Spans
+    /// using anything beyond Greek letters, common operators, scripts, `\frac` and `\sqrt`
+    /// are shown as written.
+    pub fn set_render_math(&mut self, enabled: bool) {
+        self.dirty |= self.render_math != enabled;
+        self.render_math = enabled;
+    }
+
+    /// Reveals a message's spoilers, or hides them again. Returns `false` if there is no
+    /// such message.
+    pub fn toggle_revealed(&mut self, id: MessageId) -> bool {
+        if self.index_of(id).is_none() {
+            return false;
+        }
+        self.dirty = true;
+        if !self.revealed.remove(&id) {
+            self.revealed.insert(id);
+        }
+        true
+    }
+
+    pub fn is_revealed(&self, id: MessageId) -> bool {
+        self.revealed.contains(&id)
+    }
+
+    /// The glyph hiding spoilers, if they are parsed.
+    fn spoiler_glyph(&self) -> Option<char> {
+        self.parse_spoilers.then(|| self.theme.glyph("▒", "#").chars().next().unwrap_or('#'))
+    }
+
+    /// The content to display for a message after folding, rewriting math and hiding spoilers, or `None`
+    /// if it is shown as is.
+    fn shown_content(&self, msg: &ChatMessage, id: MessageId) -> Option<String> {
+        if matches!(msg.kind, MessageKind::ToolCall { .. } | MessageKind::ToolResult { .. }) {
+            let expanded = self.expanded.contains(&id);
+            return Some(if expanded { tool_call::pretty(&msg.content) } else { tool_call::compact(&msg.content) });
+        }
+        let mut content = reasoning::split(&msg.content).map(|(_, answer)| answer.to_string());
+        if self.label_code_blocks && msg.content.contains("```") {
+            content = Some(markdown::label_code_blocks(content.as_deref().unwrap_or(&msg.content)));
+        }
+        if self.folded.contains(&id) {
+            content = Some(fold::fold(content.as_deref().unwrap_or(&msg.content), self.fold_quotes, &self.strings));
+        }
+        if self.render_math && msg.content.contains('$') {
+            content = Some(math::render(content.as_deref().unwrap_or(&msg.content)));
+        }
+        if let Some(glyph) = self.spoiler_glyph()
+            && msg.content.contains("||")
+        {
+            let text = content.as_deref().unwrap_or(&msg.content);
+            content = Some(spoiler::hide(text, self.revealed.contains(&id), glyph));
+        }
+        content
+    }
+
+    /// Builds the styled spans for a message, before wrapping.
+    #[cfg(test)]
+    fn message_spans(&self, msg: &ChatMessage) -> Vec<Span<'static>> {
+        let mut spans = self.prefix_spans(msg);
+        spans.extend(self.content_spans(msg));
+        spans
+    }
+
+    /// Spans shown before the content: the optional timestamp and the sender prefix.
+    fn prefix_spans(&self, msg: &ChatMessage) -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        if self.show_timestamps
+            && let Some(timestamp) = msg.timestamp
+        {
+            let relative = self.relative_timestamps.and_then(|max_age| {
+                let now = self.now.unwrap_or_else(SystemTime::now);
+                timestamp::format_relative(timestamp, now, max_age.as_secs(), &self.strings)
+            });
+            #[cfg(feature = "chrono")]
+            let label = relative.unwrap_or_else(|| self.time_format.format_clock(timestamp));
+            #[cfg(not(feature = "chrono"))]
+            let label = relative.unwrap_or_else(|| timestamp::format_clock(timestamp));
+            spans.push(Span::styled(format!("{label} "), self.theme.muted));
+        }
+        if let Some(presence) = self.presence(&msg.sender) {
+            let dot = if self.theme.plain_glyphs { presence.plain_symbol() } else { presence.symbol() };
+            spans.push(dot);
+            spans.push(Span::raw(" "));
+        }
+        let prefix = (self.prefix_formatter)(msg);
+        let mut style = self.theme.sender;
+        if let Some(color) = theme::sender_color(&msg.sender, &self.sender_colors) {
+            style = style.fg(color);
+        }
+        let style = style.patch(prefix.style);
+        spans.push(prefix.style(style));
+        spans
+    }
+
+    /// The reasoning row of a message as shown: a summary, or the whole reasoning under a
+    /// heading once expanded.
+    fn reasoning_row(&self, msg: &ChatMessage, id: MessageId) -> Option<String> {
+        let thought = reasoning::split(&msg.content).map(|(thought, _)| thought);
+        let parts: Vec<&str> = [msg.reasoning.as_deref(), thought].into_iter().flatten().filter(|text| !text.trim().is_empty()).collect();
+        let text = parts.join("\n");
+        if text.is_empty() {
+            return None;
+        }
+        if self.expanded.contains(&id) {
+            return Some(format!("{}\n{text}", self.strings.reasoning_expanded));
+        }
+        let count = reasoning::format_count(text.chars().count());
+        Some(strings::fill(&self.strings.reasoning_folded, &[("count", &count)]))
+    }
+
+    /// The content's spans below the shown message's reasoning row, which `render` puts in
+    /// the shown copy's [`ChatMessage::reasoning`].
+    fn content_spans(&self, msg: &ChatMessage) -> Vec<Span<'static>> {
+        let mut spans = self.body_spans(msg);
+        if let Some(reasoning) = &msg.reasoning {
+            if !msg.content.is_empty() {
+                spans.insert(0, Span::raw("\n"));
             }
-            KeyCode::PageUp => self.chat_area.scroll_up(5),
-            KeyCode::PageDown => self.chat_area.scroll_down(5),
-            KeyCode::Esc | KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.should_quit = true;
+            spans.insert(0, Span::styled(reasoning.clone(), self.theme.muted));
+        }
+        spans
+    }
+
+    fn body_spans(&self, msg: &ChatMessage) -> Vec<Span<'static>> {
+        let (glyph, name) = match &msg.kind {
+            MessageKind::Text => return self.text_spans(&msg.content),
+            MessageKind::ToolCall { name } => (self.theme.glyph("⚙", "*"), name),
+            MessageKind::ToolResult { name } => (self.theme.glyph("↳", "<-"), name),
+            MessageKind::Voice { duration, .. } => {
+                let mut label = format!("{} {}", self.theme.glyph("▶", ">"), self.strings.voice_message);
+                if let Some(duration) = duration {
+                    let seconds = duration.as_secs();
+                    label.push_str(&format!(" ({}:{:02})", seconds / 60, seconds % 60));
+                }
+                let mut spans = vec![Span::styled(label, self.theme.sender.add_modifier(Modifier::BOLD))];
+                if !msg.content.is_empty() {
+                    spans.push(Span::raw("\n"));
+                    spans.extend(self.text_spans(&msg.content));
+                }
+                return spans;
             }
-            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if let Ok(mut clipboard) = Clipboard::new()
-                    && let Ok(text) = clipboard.get_text() {
-                    self.on_paste(text);
+        };
+        let header = Span::styled(format!("{glyph} {name}"), self.theme.sender.add_modifier(Modifier::BOLD));
+        tool_call::spans(header, &msg.content, &self.theme)
+    }
+
+    /// Spans for text content, such as a text message or a voice message's caption.
+    fn text_spans(&self, content: &str) -> Vec<Span<'static>> {
+        #[cfg(feature = "ansi-to-tui")]
+        if self.parse_ansi {
+            use ansi_to_tui::IntoText;
+            if let Ok(text) = content.as_bytes().into_text() {
+                let mut spans = Vec::new();
+                for (i, line) in text.lines.into_iter().enumerate() {
+                    if i > 0 {
+                        spans.push(Span::raw("\n"));
+                    }
+                    let line_style = line.style;
+                    spans.extend(line.spans.into_iter().map(|span| span.patch_style(line_style)));
                 }
+                return spans;
             }
-            KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => self.input_area.newline(),
-            KeyCode::Char(c) => self.input_area.insert_char(c),
-            KeyCode::Backspace => self.input_area.backspace(),
-            KeyCode::Left => self.input_area.cursor_left(),
-            KeyCode::Right => self.input_area.cursor_right(),
-            KeyCode::Up => self.input_area.cursor_up(),
-            KeyCode::Down => self.input_area.cursor_down(),
-            _ => {}
         }
+        if self.render_diffs
+            && let Some(spans) = diff::spans(content)
+        {
+            return spans;
+        }
+        if self.render_inline_code
+            && let Some(spans) = markdown::code_spans(content, self.theme.code)
+        {
+            return spans;
+        }
+        vec![Span::raw(content.to_string())]
     }
 
-    pub fn on_mouse(&mut self, mouse: MouseEvent) {
-        // Check if mouse is within chat area
-        if mouse.column >= self.chat_rect.x
-            && mouse.column < self.chat_rect.x + self.chat_rect.width
-            && mouse.row >= self.chat_rect.y
-            && mouse.row < self.chat_rect.y + self.chat_rect.height
+    /// Columns available to a message's content rows after the gutter and any indent.
+    fn content_width(&self, msg: &ChatMessage, width: usize) -> usize {
+        let gutter = self.gutter.as_ref().map_or(0, |(gutter_width, _)| (*gutter_width).min(width.saturating_sub(1)));
+        let indent = if self.density.header_line() {
+            self.density.padding()
+        } else if self.hanging_indent {
+            self.prefix_spans(msg).iter().map(Span::width).sum()
+        } else {
+            0
+        };
+        width.saturating_sub(gutter + indent)
+    }
+
+    /// Wraps a message into styled display rows of at most `width` columns, including the gutter.
+    fn wrap_message(&self, msg: &ChatMessage, width: usize) -> Vec<Line<'static>> {
+        self.wrap_message_scrolled(msg, width, 0)
+    }
+
+    /// Like [`ChatArea::wrap_message`], with unwrapped code scrolled `code_offset` columns.
+    fn wrap_message_scrolled(&self, msg: &ChatMessage, width: usize, code_offset: usize) -> Vec<Line<'static>> {
+        let Some((gutter_width, formatter)) = &self.gutter else {
+            return self.wrap_content(msg, width, code_offset);
+        };
+        let gutter_width = (*gutter_width).min(width.saturating_sub(1));
+        let mut rows = self.wrap_content(msg, width - gutter_width, code_offset);
+        for (i, row) in rows.iter_mut().enumerate() {
+            let cell = if i == 0 { formatter(msg) } else { Span::raw("") };
+            row.spans.insert(0, wrap::fit_span(cell, gutter_width));
+        }
+        rows
+    }
+
+    /// A message on one row of `width` columns, including the gutter, cut short with an
+    /// ellipsis if it does not fit.
+    fn single_row(&self, msg: &ChatMessage, width: usize) -> Line<'static> {
+        let gutter_width = self.gutter.as_ref().map_or(0, |(gutter_width, _)| (*gutter_width).min(width.saturating_sub(1)));
+        let mut spans = self.prefix_spans(msg);
+        spans.extend(self.highlight_matches(self.content_spans(msg)));
+        let mut row = bidi::reorder_line(wrap::truncate_spans(&spans, width - gutter_width, self.theme.glyph("…", "...")));
+        if let Some((_, formatter)) = &self.gutter {
+            row.spans.insert(0, wrap::fit_span(formatter(msg), gutter_width));
+        }
+        row
+    }
+
+    /// Wraps the prefix and content of a message, without the gutter, in display order.
+    fn wrap_content(&self, msg: &ChatMessage, width: usize, code_offset: usize) -> Vec<Line<'static>> {
+        let align_right = self.rtl_alignment && bidi::is_predominantly_rtl(&msg.content);
+        let mut rows: Vec<Line<'static>> = self
+            .wrap_logical(msg, width, code_offset)
+            .into_iter()
+            .map(|row| {
+                let mut row = bidi::reorder_line(row);
+                if align_right {
+                    // Pad rather than set the line alignment, so a gutter stays in place.
+                    let pad = width.saturating_sub(row.width());
+                    row.spans.insert(0, Span::raw(" ".repeat(pad)));
+                }
+                row
+            })
+            .collect();
+        if self.show_metadata
+            && let Some(metadata) = &msg.metadata
         {
-            match mouse.kind {
-                MouseEventKind::ScrollUp => self.chat_area.scroll_up(3),
-                MouseEventKind::ScrollDown => self.chat_area.scroll_down(3),
-                _ => {}
+            let fitted = wrap::fit_span(Span::raw(metadata_summary(metadata, &self.strings)), width);
+            let footer = Span::styled(fitted.content.trim_end().to_string(), self.theme.muted);
+            let pad = width.saturating_sub(footer.width());
+            rows.push(Line::from(vec![Span::raw(" ".repeat(pad)), footer]));
+        }
+        rows
+    }
+
+    /// Wraps the prefix and content of a message in logical order.
+    fn wrap_logical(&self, msg: &ChatMessage, width: usize, code_offset: usize) -> Vec<Line<'static>> {
+        let mut prefix = self.prefix_spans(msg);
+        let content = self.highlight_matches(self.content_spans(msg));
+        let keep_whitespace = self.wrap_policy.keep_trailing_whitespace;
+        let no_wrap = wrap::NoWrap {
+            paragraphs: if self.code_no_wrap {
+                // Code lines count from the content, below any reasoning rows.
+                let skip = msg.reasoning.as_ref().map_or(0, |reasoning| reasoning.split('\n').count());
+                markdown::code_lines(&msg.content).into_iter().map(|line| line + skip).collect()
+            } else {
+                Vec::new()
+            },
+            offset: code_offset,
+            marker: self.theme.glyph("›", ">"),
+        };
+        if self.density.header_line() {
+            if let Some(last) = prefix.last_mut() {
+                last.content = last.content.trim_end().to_string().into();
+            }
+            let mut rows = Vec::new();
+            if prefix.iter().any(|span| !span.content.is_empty()) {
+                rows.extend(wrap::wrap_spans(&prefix, &self.wrap_policy.options(width), keep_whitespace));
             }
+            let padding = " ".repeat(self.density.padding().min(width.saturating_sub(1)));
+            let options = self.wrap_policy.options(width).initial_indent(&padding).subsequent_indent(&padding);
+            rows.extend(wrap::wrap_spans_with(&content, &options, keep_whitespace, &no_wrap));
+            return rows;
         }
+        let indent = if self.hanging_indent {
+            let prefix_width: usize = prefix.iter().map(Span::width).sum();
+            " ".repeat(prefix_width.min(width.saturating_sub(1)))
+        } else {
+            String::new()
+        };
+        prefix.extend(content);
+        let options = self.wrap_policy.options(width).subsequent_indent(&indent);
+        wrap::wrap_spans_with(&prefix, &options, keep_whitespace, &no_wrap)
     }
 
-    /// Handles pasted content by inserting it into the input area.
-    pub fn on_paste(&mut self, content: String) {
-        self.input_area.insert_str(&content);
+    /// Appends a message and returns the id assigned to it.
+    pub fn add_message(&mut self, mut msg: ChatMessage) -> MessageId {
+        self.dirty = true;
+        msg.timestamp.get_or_insert_with(SystemTime::now);
+        let id = MessageId(self.next_id);
+        self.next_id += 1;
+        let glyph = self.spoiler_glyph();
+        if let Some(index) = self.search_index.as_mut() {
+            index.insert(id, &spoiler::hide_message(&msg, glyph));
+        }
+        self.messages.push(msg);
+        self.ids.push(id);
+        self.auto_scroll = true;
+        id
     }
 
-    pub fn render(&mut self, frame: &mut Frame) {
-        let size = frame.area();
-        let input_height = self.input_area.calculate_display_lines(size.width);
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Min(1),
-                Constraint::Length(input_height),
-            ].as_ref())
-            .split(size);
-        self.chat_rect = chunks[0];
-        self.chat_area.render(frame, chunks[0]);
-        self.input_area.render(frame, chunks[1]);
+    /// Inserts older messages, oldest first, before every other message, as when loading
+    /// history while the user scrolls back, and returns their ids. Unlike
+    /// [`ChatArea::add_message`], this keeps the rows in view where they are.
+    pub fn prepend_messages(&mut self, messages: Vec<ChatMessage>) -> Vec<MessageId> {
+        self.dirty = true;
+        let ids: Vec<MessageId> = (self.next_id..self.next_id + messages.len() as u64).map(MessageId).collect();
+        self.next_id += messages.len() as u64;
+        let glyph = self.spoiler_glyph();
+        if let Some(index) = self.search_index.as_mut() {
+            for (id, msg) in ids.iter().zip(&messages) {
+                index.insert(*id, &spoiler::hide_message(msg, glyph));
+            }
+        }
+        self.older += messages.len();
+        if !messages.is_empty() {
+            self.older_runs.insert(0, (ids[0].0, messages.len()));
+        }
+        self.prepended += messages.len();
+        self.ids.splice(..0, ids.iter().copied());
+        self.messages.splice(..0, messages);
+        ids
+    }
 
-        // Calculate cursor position
-        let input_area = chunks[1];
-        let full_display = format!("> {}", self.input_area.buffer.replace('\n', "\n> "));
-        let lines: Vec<&str> = full_display.lines().collect();
-        let total_lines = lines.len();
-        let max_offset = total_lines.saturating_sub(10);
-        let offset = self.input_area.get_offset().min(max_offset);
-        let end = (offset + 10).min(total_lines);
-        let visible_lines = &lines[offset..end];
-        let display = visible_lines.join("\n");
-        let display_index = self.input_area.calculate_display_index();
-        // Calculate start_byte of visible display
-        let mut start_byte = 0;
-        for i in 0..offset {
-            if i < lines.len() {
-                start_byte += lines[i].len() + 1; // +1 for \n
-            }
-        }
-        let adjusted_display_index = display_index.saturating_sub(start_byte);
-        let cursor_pos = self.calculate_cursor_pos(&display, adjusted_display_index);
-        if let Some((line, col)) = cursor_pos {
-            let absolute_x = input_area.x + 1 + col;
-            let absolute_y = input_area.y + 1 + line;
-            self.cursor_pos = Some((absolute_x, absolute_y));
-        } else {
-            self.cursor_pos = None;
+    /// Replaces every message with saved ones under the ids they had, and pins `pinned`
+    /// again. Without one id per message, as in a session saved by an older version, the
+    /// messages get new ids and no pins.
+    fn restore_messages(&mut self, messages: Vec<ChatMessage>, ids: Vec<MessageId>, pinned: &[MessageId]) {
+        self.clear();
+        let unique: HashSet<_> = ids.iter().collect();
+        if ids.len() != messages.len() || unique.len() != ids.len() {
+            for msg in messages {
+                self.add_message(msg);
+            }
+            return;
+        }
+        // Ids ascend except where history was prepended: those runs come first, each run
+        // numbered consecutively, which is what `index_of` expects.
+        let mut older = ids.len().saturating_sub(1);
+        while older > 0 && ids[older - 1] < ids[older] {
+            older -= 1;
+        }
+        self.older_runs.clear();
+        for &id in &ids[..older] {
+            match self.older_runs.last_mut() {
+                Some((first, len)) if *first + *len as u64 == id.0 => *len += 1,
+                _ => self.older_runs.push((id.0, 1)),
+            }
+        }
+        self.older = older;
+        self.next_id = self.next_id.max(ids.iter().map(|id| id.0 + 1).max().unwrap_or(0));
+        let glyph = self.spoiler_glyph();
+        if let Some(index) = self.search_index.as_mut() {
+            for (id, msg) in ids.iter().zip(&messages) {
+                index.insert(*id, &spoiler::hide_message(msg, glyph));
+            }
         }
+        self.messages = messages;
+        self.ids = ids;
+        self.pinned = pinned.iter().copied().filter(|&id| self.index_of(id).is_some()).collect();
+        self.auto_scroll = true;
     }
 
+    /// Whether the view reached the oldest message at the last render, so older history
+    /// could be loaded with [`ChatArea::prepend_messages`].
+    pub fn is_at_top(&self) -> bool {
+        self.offset == 0 && self.prepended == 0
+    }
 
+    /// Keeps search results for [`ChatArea::search`] in `index`, such as an [`InvertedIndex`],
+    /// instead of scanning every message. The messages already in the chat are indexed
+    /// now, and later changes as they happen. With [`ChatArea::set_parse_spoilers`] on, the
+    /// index sees spoilers hidden, revealed or not.
+    pub fn set_search_index(&mut self, index: impl SearchIndex + 'static) {
+        self.search_index = Some(Box::new(index));
+        self.rebuild_search_index();
+    }
 
-    fn calculate_cursor_pos(&self, display: &str, display_index: usize) -> Option<(u16, u16)> {
-        let mut current_line = 0;
-        let mut current_col = 0;
-        let mut byte_index = 0;
-        for ch in display.chars() {
-            if byte_index == display_index {
-                return Some((current_line as u16, current_col as u16));
+    fn rebuild_search_index(&mut self) {
+        let glyph = self.spoiler_glyph();
+        if let Some(index) = self.search_index.as_mut() {
+            index.clear();
+            for (id, msg) in self.ids.iter().zip(&self.messages) {
+                index.insert(*id, &spoiler::hide_message(msg, glyph));
             }
-            if ch == '\n' {
-                current_line += 1;
-                current_col = 0;
-            } else {
-                current_col += 1;
-            }
-            byte_index += ch.len_utf8();
         }
-        if byte_index == display_index {
-            Some((current_line as u16, current_col as u16))
-        } else {
-            None
+    }
+
+    pub fn clear_search_index(&mut self) {
+        self.search_index = None;
+    }
+
+    /// Up to `limit` messages matching `query`, best match first. Without a search index,
+    /// this is every message whose sender or content contains `query`
+    /// (case-insensitively), newest first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<MessageId> {
+        if let Some(index) = &self.search_index {
+            return index.search(query, limit);
         }
+        let Ok(matcher) = search::Matcher::new(query, false) else {
+            return Vec::new();
+        };
+        self.ids
+            .iter()
+            .zip(&self.messages)
+            .rev()
+            .filter(|(id, msg)| {
+                let msg = self.shown_for_matching(msg, **id);
+                matcher.is_match(&msg.content) || matcher.is_match(&msg.sender)
+            })
+            .take(limit)
+            .map(|(id, _)| *id)
+            .collect()
     }
 
-    pub fn should_quit(&self) -> bool {
-        self.should_quit
+    /// Re-indexes a message whose content changed.
+    fn reindex(&mut self, position: usize) {
+        let glyph = self.spoiler_glyph();
+        if let Some(index) = self.search_index.as_mut() {
+            index.insert(self.ids[position], &spoiler::hide_message(&self.messages[position], glyph));
+        }
     }
 
-    pub fn get_cursor_pos(&self) -> Option<(u16, u16)> {
-        self.cursor_pos
+    /// Hides messages whose sender and content do not contain `query` (case-insensitively).
+    /// The messages are kept; an empty query shows them all again.
+    ///
+    /// Matches are highlighted in the messages that remain visible.
+    pub fn set_filter(&mut self, query: impl Into<String>) {
+        self.dirty = true;
+        self.filter = query.into();
+        self.matcher = (!self.filter.is_empty()).then(|| search::Matcher::new(&self.filter, self.filter_regex));
+        self.offset = 0;
+        self.shown_offset = None;
+        self.auto_scroll = true;
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crossterm::event::KeyModifiers;
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
 
-    #[test]
-    fn test_insert_str_normalization() {
-        let mut input = InputArea::new();
-        input.insert_str("hello\r\nworld\r");
-        assert_eq!(input.buffer, "hello\nworld\n");
-        assert_eq!(input.cursor, 12);
+    /// Shows the messages the filter rejects dimmed with [`Theme::muted`] instead of hiding
+    /// them, so matches keep their context. Selecting messages still steps from match to
+    /// match.
+    pub fn set_dim_unmatched(&mut self, dim: bool) {
+        self.dirty |= self.dim_unmatched != dim;
+        self.dim_unmatched = dim;
     }
 
-    #[test]
-    fn test_insert_str_cursor_position() {
-        let mut input = InputArea::new();
-        input.insert_char('a');
-        input.insert_char('b');
-        input.cursor_left();
-        input.insert_str("test");
-        assert_eq!(input.buffer, "atestb");
-        assert_eq!(input.cursor, 5); // 'a' 't' 'e' 's' 't' 'b' cursor after 't'
+    pub fn dim_unmatched(&self) -> bool {
+        self.dim_unmatched
     }
 
-    #[test]
-    fn test_mouse_scroll() {
-        let mut app = ChatApp::new();
-        app.chat_area.add_message(ChatMessage {
-            sender: "Test".to_string(),
-            content: "This is a long message that will wrap into multiple lines when displayed in the chat area.".to_string(),
-        });
-        // Manually calculate message_lines as in render
-        let visible_width = 10;
-        app.chat_area.message_lines.clear();
-        for (i, msg) in app.chat_area.messages.iter().enumerate() {
-            let content = format!("{}: {}", msg.sender, msg.content);
-            let lines = textwrap::wrap(&content, visible_width);
-            for j in 0..lines.len() {
-                app.chat_area.message_lines.push((i, j));
+    /// Interprets the filter as a regular expression instead of a case-insensitive substring.
+    #[cfg(feature = "regex")]
+    pub fn set_filter_regex(&mut self, enabled: bool) {
+        self.dirty = true;
+        self.filter_regex = enabled;
+        let filter = std::mem::take(&mut self.filter);
+        self.set_filter(filter);
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn filter_regex(&self) -> bool {
+        self.filter_regex
+    }
+
+    /// Why the current filter pattern is invalid, if it is. An invalid filter hides nothing.
+    pub fn filter_error(&self) -> Option<&str> {
+        self.matcher.as_ref()?.as_ref().err().map(String::as_str)
+    }
+
+    fn matches_filter(&self, msg: &ChatMessage, id: MessageId) -> bool {
+        match &self.matcher {
+            Some(Ok(matcher)) => {
+                let msg = self.shown_for_matching(msg, id);
+                matcher.is_match(&msg.content) || matcher.is_match(&msg.sender)
             }
+            _ => true,
         }
-        // Set offset to 10
-        app.chat_area.offset = 10;
-        // Set chat_rect
-        app.chat_rect = Rect::new(0, 0, 20, 20);
-        // Mouse event inside rect
-        let mouse = MouseEvent {
-            kind: MouseEventKind::ScrollUp,
-            column: 5,
-            row: 5,
-            modifiers: KeyModifiers::NONE,
+    }
+
+    /// A message as the filter and search see it, with the spoilers it hides hidden.
+    fn shown_for_matching<'a>(&self, msg: &'a ChatMessage, id: MessageId) -> Cow<'a, ChatMessage> {
+        spoiler::hide_message(msg, self.spoiler_glyph().filter(|_| !self.revealed.contains(&id)))
+    }
+
+    /// Highlights filter matches in a message's content spans.
+    fn highlight_matches(&self, spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+        let Some(Ok(matcher)) = &self.matcher else {
+            return spans;
         };
-        app.on_mouse(mouse);
-        assert_eq!(app.chat_area.offset, 7); // 10 - 3
-        // Reset offset
-        app.chat_area.offset = 10;
-        // Mouse event outside rect
-        let mouse_out = MouseEvent {
-            kind: MouseEventKind::ScrollUp,
-            column: 25,
-            row: 5,
-            modifiers: KeyModifiers::NONE,
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        let style = Style::default().add_modifier(Modifier::REVERSED);
+        search::highlight(spans, &matcher.find_iter(&text), style)
+    }
+
+    /// Number of messages shown under the current filter.
+    pub fn filter_matches(&self) -> usize {
+        self.messages.iter().zip(&self.ids).filter(|(msg, id)| self.matches_filter(msg, **id)).count()
+    }
+
+    /// Scrolls so the first message sent at or after `time` is at the top of the view, and
+    /// returns its id. Past the last message, the view follows the newest messages again.
+    ///
+    /// Like the other jumps, this uses the layout of the last render.
+    pub fn scroll_to_time(&mut self, time: SystemTime) -> Option<MessageId> {
+        self.dirty = true;
+        let Some(idx) = self.messages.iter().position(|m| m.timestamp.is_some_and(|t| t >= time)) else {
+            self.auto_scroll = true;
+            return None;
         };
-        app.on_mouse(mouse_out);
-        assert_eq!(app.chat_area.offset, 10); // unchanged
+        let id = self.ids[idx];
+        self.scroll_to_message(id, ScrollAlignment::Top);
+        Some(id)
+    }
+
+    /// Removes every message and pin. Ids of removed messages are not reused.
+    pub fn clear(&mut self) {
+        self.dirty = true;
+        self.messages.clear();
+        self.ids.clear();
+        self.older = 0;
+        self.older_runs.clear();
+        self.prepended = 0;
+        self.pinned.clear();
+        self.pin_cursor = None;
+        self.last_read = None;
+        self.read_markers.clear();
+        self.streaming.clear();
+        self.streamed.clear();
+        self.selected = None;
+        self.expanded.clear();
+        self.folded.clear();
+        self.revealed.clear();
+        self.selection_anchor = None;
+        self.text_selection = None;
+        if let Some(index) = self.search_index.as_mut() {
+            index.clear();
+        }
+        self.shown_offset = None;
+        self.offset = 0;
+        self.auto_scroll = true;
+    }
+
+    /// Appends a message whose content will arrive in pieces, such as a reply being
+    /// generated. Extend it with [`ChatArea::append_to_message`] and end it with
+    /// [`ChatArea::finish_streaming`] or [`ChatArea::cancel_streaming`].
+    pub fn start_streaming(&mut self, msg: ChatMessage) -> MessageId {
+        self.dirty = true;
+        let id = self.add_message(msg);
+        self.streaming.push(id);
+        id
+    }
+
+    /// Appends `chunk` to a message's content. Returns `false` if there is no such message.
+    pub fn append_to_message(&mut self, id: MessageId, chunk: &str) -> bool {
+        self.dirty = true;
+        let Some(index) = self.index_of(id) else {
+            return false;
+        };
+        self.messages[index].content.push_str(chunk);
+        self.reindex(index);
+        true
+    }
+
+    /// Marks a streaming message as complete. Returns `false` if it was not streaming.
+    pub fn finish_streaming(&mut self, id: MessageId) -> bool {
+        self.dirty = true;
+        let Some(pos) = self.streaming.iter().position(|&s| s == id) else {
+            return false;
+        };
+        self.streaming.remove(pos);
+        self.streamed.remove(&id);
+        true
+    }
+
+    /// Drops a message added after any prepended history, as when the message filter
+    /// rejects a streamed message once it is complete. Read markers on it move to the
+    /// message before. Returns `false` if there is no such message.
+    fn remove_message(&mut self, id: MessageId) -> bool {
+        let Some(index) = self.index_of(id).filter(|&i| i >= self.older) else {
+            return false;
+        };
+        self.dirty = true;
+        self.messages.remove(index);
+        self.ids.remove(index);
+        let previous = index.checked_sub(1).map(|i| self.ids[i]);
+        if self.last_read == Some(id) {
+            self.last_read = previous;
+        }
+        self.read_markers.retain_mut(|(_, marker)| {
+            if *marker == id {
+                let Some(previous) = previous else {
+                    return false;
+                };
+                *marker = previous;
+            }
+            true
+        });
+        if self.pinned.contains(&id) {
+            self.pinned.retain(|&p| p != id);
+            self.pin_cursor = None;
+        }
+        self.streaming.retain(|&s| s != id);
+        self.streamed.remove(&id);
+        if self.selected == Some(id) {
+            self.selected = None;
+        }
+        self.expanded.remove(&id);
+        self.folded.remove(&id);
+        self.revealed.remove(&id);
+        self.code_offsets.remove(&id);
+        self.selection_anchor = None;
+        self.text_selection = None;
+        if let Some(index) = self.search_index.as_mut() {
+            index.remove(id);
+        }
+        true
+    }
+
+    /// Stops a streaming message and marks its content as cancelled. The host is still
+    /// responsible for aborting the generation. Returns `false` if it was not streaming.
+    pub fn cancel_streaming(&mut self, id: MessageId) -> bool {
+        self.dirty = true;
+        if !self.finish_streaming(id) {
+            return false;
+        }
+        let Some(index) = self.index_of(id) else {
+            return false;
+        };
+        let content = &mut self.messages[index].content;
+        if !content.is_empty() && !content.ends_with(char::is_whitespace) {
+            content.push(' ');
+        }
+        content.push_str(&self.strings.cancelled);
+        self.reindex(index);
+        true
+    }
+
+    /// Messages still receiving content, oldest first.
+    pub fn streaming(&self) -> &[MessageId] {
+        &self.streaming
+    }
+
+    pub fn is_streaming(&self, id: MessageId) -> bool {
+        self.streaming.contains(&id)
     }
-}
\ No newline at end of file
+
+    /// Cuts messages taller than `max_rows` short, ending them with a footer such as
+    /// `… show more (312 lines)`, so one giant paste does not dominate the scrollback.
+    /// Expand them one by one with [`ChatArea::toggle_expanded`]. `None` shows every
+    /// message in full.
+    pub fn set_collapse_long_messages(&mut self, max_rows: Option<usize>) {
+        let collapse_after = max_rows.map(|rows| rows.max(1));
+        self.dirty |= self.collapse_after != collapse_after;
+        self.collapse_after = collapse_after;
+    }
+
+    /// Shows a collapsed message in full, or collapses it again, along with its reasoning and
+    /// the JSON of a tool message. Returns `false` if there is no such message.
+    pub fn toggle_expanded(&mut self, id: MessageId) -> bool {
+        if self.index_of(id).is_none() {
+            return false;
+        }
+        self.dirty = true;
+        if !self.expanded.remove(&id) {
+            self.expanded.insert(id);
+        }
+        true
+    }
+
+    pub fn is_expanded(&self, id: MessageId) -> bool {
+        self.expanded.contains(&id)
+    }
+
+    /// Folds each fenced code block of a message to a single summary line, or unfolds them
+    /// again. Returns `false` if there is no such message.
+    pub fn toggle_folded(&mut self, id: MessageId) -> bool {
+        if self.index_of(id).is_none() {
+            return false;
+        }
+        self.dirty = true;
+        if !self.folded.remove(&id) {
+            self.folded.insert(id);
+        }
+        true
+    }
+
+    pub fn is_folded(&self, id: MessageId) -> bool {
+        self.folded.contains(&id)
+    }
+
+    /// Also folds runs of `>` quoted lines in folded messages.
+    pub fn set_fold_quotes(&mut self, enabled: bool) {
+        self.dirty |= self.fold_quotes != enabled;
+        self.fold_quotes = enabled;
+    }
+
+    /// Replaces the hidden rows of a long message with a "show more" footer, keeping the
+    /// metadata footer if there is one.
+    fn collapse(&self, rows: &mut Vec<Line<'static>>, msg: &ChatMessage, id: MessageId) {
+        let Some(max) = self.collapse_after.filter(|_| !self.expanded.contains(&id)) else {
+            return;
+        };
+        let content = rows.len() - usize::from(self.show_metadata && msg.metadata.is_some());
+        if content <= max {
+            return;
+        }
+        let footer = strings::fill(&self.strings.show_more, &[("count", &(content - max))]);
+        rows.splice(max..content, [Line::styled(footer, self.theme.muted)]);
+    }
+
+    /// Highlights a message, for acting on it with the keyboard. The selection is cleared
+    /// with `None`, and when the message is removed.
+    pub fn select_message(&mut self, id: Option<MessageId>) {
+        self.dirty = true;
+        self.selected = id.filter(|&id| self.index_of(id).is_some());
+        if let Some(id) = self.selected {
+            self.reveal(id);
+        }
+    }
+
+    pub fn selected_message(&self) -> Option<MessageId> {
+        self.selected
+    }
+
+    /// Sets the style patched onto every row of the selected message, across the full
+    /// width of the chat. Reversed video by default.
+    pub fn set_selection_style(&mut self, style: Style) {
+        self.dirty |= self.selection_style != style;
+        self.selection_style = style;
+    }
+
+    pub fn selection_style(&self) -> Style {
+        self.selection_style
+    }
+
+    /// Selects the previous message the filter lets through, or the newest one if none is
+    /// selected. Returns the new selection.
+    pub fn select_prev(&mut self) -> Option<MessageId> {
+        self.dirty = true;
+        let shown = self.shown_ids();
+        let pos = self.selected.and_then(|id| shown.iter().position(|&s| s == id));
+        let id = match pos {
+            Some(pos) => shown[pos.saturating_sub(1)],
+            None => *shown.last()?,
+        };
+        self.select_message(Some(id));
+        self.selected
+    }
+
+    /// Selects the next message the filter lets through. Returns the new selection.
+    pub fn select_next(&mut self) -> Option<MessageId> {
+        self.dirty = true;
+        let shown = self.shown_ids();
+        let pos = self.selected.and_then(|id| shown.iter().position(|&s| s == id));
+        let id = match pos {
+            Some(pos) => shown[(pos + 1).min(shown.len() - 1)],
+            None => *shown.last()?,
+        };
+        self.select_message(Some(id));
+        self.selected
+    }
+
+    /// Ids of the messages that pass the filter, oldest first.
+    fn shown_ids(&self) -> Vec<MessageId> {
+        self.messages.iter().zip(&self.ids).filter(|(msg, id)| self.matches_filter(msg, **id)).map(|(_, &id)| id).collect()
+    }
+
+    /// Scrolls just enough to bring a message into view, if it is not already.
+    fn reveal(&mut self, id: MessageId) {
+        let Some(idx) = self.index_of(id) else {
+            return;
+        };
+        let Some(first) = self.message_lines.iter().position(|(m, _)| *m == idx) else {
+            return;
+        };
+        let rows = self.message_lines[first..].iter().take_while(|(m, _)| *m == idx).count();
+        if first < self.offset {
+            self.scroll_to_message(id, ScrollAlignment::Top);
+        } else if first + rows > self.offset + self.viewport_height {
+            // Show the top of a message taller than the view.
+            let alignment = if rows > self.viewport_height { ScrollAlignment::Top } else { ScrollAlignment::Bottom };
+            self.scroll_to_message(id, alignment);
+        }
+    }
+
+    /// Returns the message with the given id, if it exists.
+    pub fn message(&self, id: MessageId) -> Option<&ChatMessage> {
+        self.index_of(id).map(|i| &self.messages[i])
+    }
+
+    fn index_of(&self, id: MessageId) -> Option<usize> {
+        if let Ok(i) = self.ids[self.older..].binary_search(&id) {
+            return Some(self.older + i);
+        }
+        let mut start = 0;
+        for &(first, len) in &self.older_runs {
+            if (first..first + len as u64).contains(&id.0) {
+                return Some(start + (id.0 - first) as usize);
+            }
+            start += len;
+        }
+        None
+    }
+
+    /// The row and column of the text under a screen position as of the last render,
+    /// clamped to the drawn rows.
+    fn text_position(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        if self.lines.is_empty() || self.text_area.is_empty() {
+            return None;
+        }
+        let area = self.text_area;
+        let y = row.clamp(area.top(), area.bottom() - 1) - area.y;
+        let x = column.clamp(area.left(), area.right() - 1) - area.x;
+        Some(((self.top_row + y as usize).min(self.lines.len() - 1), x as usize))
+    }
+
+    /// Starts selecting text at a screen position, as when the left button goes down,
+    /// clearing any previous selection. Returns `false` if the position is not over the
+    /// messages as last rendered.
+    pub fn start_text_selection(&mut self, column: u16, row: u16) -> bool {
+        self.dirty = true;
+        self.text_selection = None;
+        self.selection_anchor = None;
+        if self.text_area.contains((column, row).into()) {
+            self.selection_anchor = self.text_position(column, row);
+        }
+        self.selection_anchor.is_some()
+    }
+
+    /// Selects the text from where the selection started to a screen position, as when
+    /// dragging. Positions outside the messages select up to the nearest edge.
+    pub fn extend_text_selection(&mut self, column: u16, row: u16) {
+        let (Some(anchor), Some(head)) = (self.selection_anchor, self.text_position(column, row)) else {
+            return;
+        };
+        self.dirty = true;
+        self.text_selection = Some((anchor.min(head), anchor.max(head)));
+    }
+
+    /// Selects the word under a screen position, as on a double click: the run of letters,
+    /// digits and underscores there, or the single character if it is something else.
+    /// Returns `false` if the position is not over text.
+    pub fn select_word_at(&mut self, column: u16, row: u16) -> bool {
+        self.dirty = true;
+        self.clear_text_selection();
+        let Some((line, column)) = self.text_area.contains((column, row).into()).then(|| self.text_position(column, row)).flatten() else {
+            return false;
+        };
+        let mut cells = Vec::new();
+        let mut start = 0;
+        for ch in wrap::plain(&self.lines[line]).chars() {
+            let width = ch.width().unwrap_or(0);
+            cells.push((start, width, ch));
+            start += width;
+        }
+        let Some(hit) = cells.iter().rposition(|&(start, width, _)| start <= column && column < start + width.max(1)) else {
+            return false;
+        };
+        if cells[hit].2.is_whitespace() {
+            return false;
+        }
+        let is_word_char = |ch: char| ch.is_alphanumeric() || ch == '_';
+        let (mut first, mut last) = (hit, hit);
+        if is_word_char(cells[hit].2) {
+            while first > 0 && is_word_char(cells[first - 1].2) {
+                first -= 1;
+            }
+            while last + 1 < cells.len() && is_word_char(cells[last + 1].2) {
+                last += 1;
+            }
+        }
+        let from = (line, cells[first].0);
+        self.selection_anchor = Some(from);
+        self.text_selection = Some((from, (line, cells[last].0 + cells[last].1.max(1) - 1)));
+        true
+    }
+
+    /// Selects every row of the message under a screen position, as on a triple click.
+    /// Returns `false` if the position is not over a message.
+    pub fn select_message_text_at(&mut self, column: u16, row: u16) -> bool {
+        self.dirty = true;
+        self.clear_text_selection();
+        let Some((line, _)) = self.text_area.contains((column, row).into()).then(|| self.text_position(column, row)).flatten() else {
+            return false;
+        };
+        let Some(&(message, _)) = self.message_lines.get(line) else {
+            return false;
+        };
+        // Skip the blank rows separating the message from the one before.
+        let rows: Vec<usize> = (0..self.message_lines.len())
+            .filter(|&k| self.message_lines[k].0 == message)
+            .skip_while(|&k| self.lines[k].width() == 0)
+            .collect();
+        let (Some(&first), Some(&last)) = (rows.first(), rows.last()) else {
+            return false;
+        };
+        self.selection_anchor = Some((first, 0));
+        self.text_selection = Some(((first, 0), (last, self.rendered_width.saturating_sub(1))));
+        true
+    }
+
+    /// Where a selection endpoint sits in the last layout: the message, the row within it,
+    /// the column and the row's text.
+    fn selection_point(&self, (line, column): (usize, usize)) -> Option<(MessageId, usize, usize, String)> {
+        let &(i, row) = self.message_lines.get(line)?;
+        Some((*self.ids.get(i + self.prepended)?, row, column, wrap::plain(&self.lines[line])))
+    }
+
+    /// The line a selection endpoint is on in the new layout, unless its row is gone or
+    /// now shows something else.
+    fn relocate(&self, (id, row, column, text): &(MessageId, usize, usize, String)) -> Option<(usize, usize)> {
+        let i = self.index_of(*id)?;
+        let line = self.message_lines.iter().position(|&position| position == (i, *row))?;
+        (wrap::plain(&self.lines[line]) == *text).then_some((line, *column))
+    }
+
+    pub fn clear_text_selection(&mut self) {
+        self.dirty = true;
+        self.selection_anchor = None;
+        self.text_selection = None;
+    }
+
+    /// The text selected with the mouse, as rendered, with a newline after each row and
+    /// trailing spaces dropped.
+    pub fn selected_text(&self) -> Option<String> {
+        let ((first, from), (last, to)) = self.text_selection?;
+        let rows: Vec<String> = (first..=last.min(self.lines.len().saturating_sub(1)))
+            .map(|i| {
+                let start = if i == first { from } else { 0 };
+                let end = if i == last { to + 1 } else { usize::MAX };
+                wrap::slice_columns(&wrap::plain(&self.lines[i]), start, end).trim_end().to_string()
+            })
+            .collect();
+        Some(rows.join("\n"))
+    }
+
+    /// Marks every message up to and including `id` as read, with a divider below it while
+    /// newer messages follow. `None` marks everything unread. Returns `false` if the id is
+    /// unknown.
+    pub fn set_last_read(&mut self, id: Option<MessageId>) -> bool {
+        if id.is_some_and(|id| self.index_of(id).is_none()) {
+            return false;
+        }
+        self.dirty |= self.last_read != id;
+        self.last_read = id;
+        true
+    }
+
+    pub fn last_read(&self) -> Option<MessageId> {
+        self.last_read
+    }
+
+    /// Marks the newest message as read.
+    pub fn mark_all_read(&mut self) {
+        self.set_last_read(self.ids.last().copied());
+    }
+
+    /// Number of messages after the one set with [`ChatArea::set_last_read`], or of all
+    /// messages if none is set.
+    pub fn unread_count(&self) -> usize {
+        match self.last_read.and_then(|id| self.index_of(id)) {
+            Some(i) => self.messages.len() - i - 1,
+            None => self.messages.len(),
+        }
+    }
+
+    /// Records that `participant` has read up to `id`, shown as `✓ participant` (`v
+    /// participant` with plain glyphs) at the right of that message. Returns `false` if the id is unknown.
+    pub fn set_read_marker(&mut self, participant: impl Into<String>, id: MessageId) -> bool {
+        if self.index_of(id).is_none() {
+            return false;
+        }
+        let participant = participant.into();
+        match self.read_markers.iter_mut().find(|(name, _)| *name == participant) {
+            Some((_, marker)) => {
+                self.dirty |= *marker != id;
+                *marker = id;
+            }
+            None => {
+                self.dirty = true;
+                self.read_markers.push((participant, id));
+            }
+        }
+        true
+    }
+
+    pub fn remove_read_marker(&mut self, participant: &str) {
+        let len = self.read_markers.len();
+        self.read_markers.retain(|(name, _)| name != participant);
+        self.dirty |= self.read_markers.len() != len;
+    }
+
+    /// Each participant with the newest message they have read.
+    pub fn read_markers(&self) -> &[(String, MessageId)] {
+        &self.read_markers
+    }
+
+    /// Puts the names of the participants who have read up to `id` at the right of the last
+    /// row, or on a row of their own if they do not fit.
+    fn add_read_markers(&self, rows: &mut Vec<Line<'static>>, id: MessageId, width: usize) {
+        let names: Vec<&str> = self.read_markers.iter().filter(|(_, marker)| *marker == id).map(|(name, _)| name.as_str()).collect();
+        if names.is_empty() {
+            return;
+        }
+        let label = strings::fill(&self.strings.read_by, &[("check", &self.theme.glyph("✓", "v")), ("names", &names.join(", "))]);
+        let label_width = label.width();
+        let label = Span::styled(label, self.theme.muted);
+        match rows.last_mut() {
+            Some(last) if last.width() + 1 + label_width <= width => {
+                let pad = width - last.width() - label_width;
+                last.spans.extend([Span::raw(" ".repeat(pad)), label]);
+            }
+            _ => rows.push(Line::from(vec![Span::raw(" ".repeat(width.saturating_sub(label_width))), label])),
+        }
+    }
+
+    /// A full-width rule with a label, separating read messages from unread ones.
+    fn unread_divider(&self, width: usize) -> Line<'static> {
+        let label = format!(" {} ", self.strings.unread_marker);
+        let rule = width.saturating_sub(label.width());
+        let glyph = self.theme.glyph("─", "-");
+        let text = format!("{}{label}{}", glyph.repeat(rule / 2), glyph.repeat(rule - rule / 2));
+        Line::styled(text, self.theme.muted)
+    }
+
+    /// Pins a message so it is listed in the pinned panel. Returns `false` if the id is unknown.
+    pub fn pin_message(&mut self, id: MessageId) -> bool {
+        if self.index_of(id).is_none() {
+            return false;
+        }
+        if !self.pinned.contains(&id) {
+            self.dirty = true;
+            self.pinned.push(id);
+        }
+        true
+    }
+
+    /// Unpins a message. Returns `false` if it was not pinned.
+    pub fn unpin_message(&mut self, id: MessageId) -> bool {
+        let Some(pos) = self.pinned.iter().position(|p| *p == id) else {
+            return false;
+        };
+        self.dirty = true;
+        self.pinned.remove(pos);
+        self.pin_cursor = None;
+        true
+    }
+
+    /// Returns the pinned message ids, in the order they were pinned.
+    pub fn pinned(&self) -> &[MessageId] {
+        &self.pinned
+    }
+
+    /// Shows or hides the pinned panel above the messages.
+    ///
+    /// While hidden, the number of pins is shown in the title instead.
+    pub fn set_show_pinned(&mut self, show: bool) {
+        self.dirty |= self.show_pinned != show;
+        self.show_pinned = show;
+    }
+
+    pub fn toggle_pinned(&mut self) {
+        self.dirty = true;
+        self.show_pinned = !self.show_pinned;
+    }
+
+    /// Scrolls to the next pinned message, wrapping around, and returns its id.
+    pub fn next_pin(&mut self) -> Option<MessageId> {
+        self.dirty = true;
+        if self.pinned.is_empty() {
+            return None;
+        }
+        let next = self.pin_cursor.map_or(0, |i| (i + 1) % self.pinned.len());
+        self.jump_to_pin(next)
+    }
+
+    /// Scrolls to the previous pinned message, wrapping around, and returns its id.
+    pub fn prev_pin(&mut self) -> Option<MessageId> {
+        self.dirty = true;
+        if self.pinned.is_empty() {
+            return None;
+        }
+        let len = self.pinned.len();
+        let prev = self.pin_cursor.map_or(len - 1, |i| (i + len - 1) % len);
+        self.jump_to_pin(prev)
+    }
+
+    fn jump_to_pin(&mut self, pin: usize) -> Option<MessageId> {
+        let id = self.pinned[pin];
+        self.index_of(id)?;
+        self.pin_cursor = Some(pin);
+        self.scroll_to_message(id, ScrollAlignment::Top);
+        Some(id)
+    }
+
+    /// Scrolls so the message is positioned in the view as `alignment` asks, and stops
+    /// following new messages. Returns `false` if the message is unknown or was not shown
+    /// in the last render, for example because it is hidden by the filter.
+    pub fn scroll_to_message(&mut self, id: MessageId, alignment: ScrollAlignment) -> bool {
+        let Some(idx) = self.index_of(id) else {
+            return false;
+        };
+        let Some(first) = self.message_lines.iter().position(|(m, _)| *m == idx) else {
+            return false;
+        };
+        let rows = self.message_lines[first..].iter().take_while(|(m, _)| *m == idx).count();
+        // Rows before the message's own content are the gap separating it from the previous one.
+        let gap = if first > 0 { self.density.gap().min(rows.saturating_sub(1)) } else { 0 };
+        let (start, end) = (first + gap, first + rows);
+        let offset = match alignment {
+            ScrollAlignment::Top => start,
+            ScrollAlignment::Center => ((start + end) / 2).saturating_sub(self.viewport_height / 2),
+            ScrollAlignment::Bottom => end.saturating_sub(self.viewport_height),
+        };
+        let offset = offset.min(self.message_lines.len().saturating_sub(self.viewport_height));
+        if offset == self.offset && !self.auto_scroll {
+            return true;
+        }
+        self.dirty = true;
+        self.animate_from(self.offset);
+        self.offset = offset;
+        self.auto_scroll = false;
+        true
+    }
+
+    fn render_pinned(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .pinned
+            .iter()
+            .enumerate()
+            .filter_map(|(i, id)| {
+                let msg = self.message(*id)?;
+                let content = self.shown_content(msg, *id).unwrap_or_else(|| msg.content.clone());
+                let first_line = content.lines().next().unwrap_or_default();
+                let marker = if self.pin_cursor == Some(i) { self.theme.glyph("▶ ", "> ") } else { "  " };
+                Some(ListItem::new(format!("{marker}{}: {first_line}", msg.sender)))
+            })
+            .collect();
+        let list = List::new(items).block(self.block(&self.strings.pinned_title));
+        frame.render_widget(list, area);
+    }
+
+    /// Animates [`ChatArea::scroll_up`] and [`ChatArea::scroll_down`] over a few frames
+    /// instead of jumping. The animation advances with each call to [`ChatArea::tick`].
+    pub fn set_smooth_scroll(&mut self, enabled: bool) {
+        self.dirty |= self.smooth_scroll != enabled;
+        self.smooth_scroll = enabled;
+        if !enabled {
+            self.shown_offset = None;
+        }
+    }
+
+    /// Advances a smooth scroll by one frame, covering half the remaining distance, and
+    /// updates the time relative timestamps are measured from to `now`. Call this from the
+    /// host loop; [`ChatApp::tick`] does so with the current time. Returns whether the
+    /// animation is still running.
+    ///
+    /// A new time only marks the chat for redrawing when the minute changes.
+    pub fn tick(&mut self, now: SystemTime) -> bool {
+        let minute = |time: SystemTime| time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 60);
+        if self.relative_timestamps.is_some() && self.now.map(minute) != Some(minute(now)) {
+            self.dirty = true;
+        }
+        self.now = Some(now);
+        if let Some(shown) = self.shown_offset {
+            self.dirty = true;
+            let step = shown.abs_diff(self.offset).div_ceil(2);
+            let shown = if shown < self.offset { shown + step } else { shown - step };
+            self.shown_offset = (shown != self.offset).then_some(shown);
+        }
+        self.is_animating()
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.shown_offset.is_some()
+    }
+
+    /// Starts animating from the current position, if smooth scrolling is enabled.
+    fn animate_from(&mut self, offset: usize) {
+        if self.smooth_scroll {
+            self.shown_offset.get_or_insert(offset);
+        }
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.dirty = true;
+        self.animate_from(self.offset);
+        self.offset = self.offset.saturating_sub(lines);
+        self.auto_scroll = false;
+    }
+
+    /// Whether the view is following the newest messages rather than scrolled back through history.
+    pub fn is_following(&self) -> bool {
+        self.auto_scroll
+    }
+
+    /// Whether anything shown has changed since the last render, so the host can skip
+    /// drawing when nothing did.
+    pub fn needs_redraw(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns `(line, total)`: the 1-based number of the last visible row and the total
+    /// number of rows, as of the last render.
+    pub fn scroll_position(&self) -> (usize, usize) {
+        let total = self.message_lines.len();
+        ((self.offset + self.viewport_height).min(total), total)
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.dirty = true;
+        self.animate_from(self.offset);
+        let content_length = self.message_lines.len();
+        let max_scroll = content_length.saturating_sub(1);
+        self.offset = (self.offset + lines).min(max_scroll);
+        if self.offset == max_scroll {
+            self.auto_scroll = true;
+        }
+    }
+
+    /// Renders into `buf` within `area` without a terminal, for example for snapshot tests.
+    pub fn render_to_buffer(&mut self, buf: &mut ratatui::buffer::Buffer, area: Rect) {
+        testing::render_headless(buf, area, |frame, area| self.render(frame, area));
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        self.dirty = false;
+        let area = if self.show_pinned && !self.pinned.is_empty() {
+            let panel_height = self.pinned.len().min(3) as u16 + 2;
+            let [panel, rest] = Layout::vertical([Constraint::Length(panel_height), Constraint::Min(1)]).areas(area);
+            self.render_pinned(frame, panel);
+            rest
+        } else {
+            area
+        };
+        // The bordered list sits left of a one-column scrollbar, so rows wrapped any wider
+        // would lose their last character under the border.
+        let visible_width = area.width.saturating_sub(3) as usize; // account for borders and the scrollbar
+        let visible_height = area.height.saturating_sub(2) as usize;
+        self.viewport_height = visible_height;
+
+        // If width is zero, we can't render anything.
+        if visible_width == 0 {
+            return;
+        }
+
+        // When the width changes or older messages are prepended while scrolled back, keep
+        // the top row's message in view as the messages rewrap or move down.
+        let anchor = ((visible_width != self.rendered_width || self.prepended > 0) && !self.auto_scroll)
+            .then(|| self.message_lines.get(self.offset).map(|&(i, row)| (i + self.prepended, row)))
+            .flatten();
+        if visible_width != self.rendered_width {
+            self.selection_anchor = None;
+            self.text_selection = None;
+        }
+        // A mouse selection is kept as rows of the layout, so carry it over to the rows its
+        // messages end up on as messages are added, prepended, filtered or folded.
+        let selection = self.text_selection.map(|(from, to)| (self.selection_point(from), self.selection_point(to)));
+        let selection_anchor = self.selection_anchor.map(|anchor| self.selection_point(anchor));
+        self.rendered_width = visible_width;
+        self.prepended = 0;
+
+        // Re-calculate message_lines whenever we render
+        self.message_lines.clear();
+        self.lines.clear();
+        let mut clamped_offsets = Vec::new();
+        let mut code_position = None;
+        for (i, msg) in self.messages.iter().enumerate() {
+            let matched = self.matches_filter(msg, self.ids[i]);
+            if !matched && !self.dim_unmatched {
+                continue;
+            }
+            let mut lines = Vec::new();
+            if !self.lines.is_empty() {
+                lines.resize(self.density.gap(), Line::default());
+            }
+            let mut content = self.shown_content(msg, self.ids[i]);
+            let single_line = self.single_line && self.selected != Some(self.ids[i]);
+            if self.render_tables && !single_line && msg.kind == MessageKind::Text && msg.content.contains('|') {
+                let text = content.as_deref().unwrap_or(&msg.content);
+                let width = self.content_width(msg, visible_width);
+                content = table::render(text, width, self.theme.plain_glyphs).or(content);
+            }
+            let reasoning = self.reasoning_row(msg, self.ids[i]);
+            let shown = (content.is_some() || reasoning.is_some() || msg.reasoning.is_some()).then(|| ChatMessage {
+                content: content.unwrap_or_else(|| msg.content.clone()),
+                reasoning,
+                ..msg.clone()
+            });
+            let shown = shown.as_ref().unwrap_or(msg);
+            let mut rows = if single_line {
+                vec![self.single_row(shown, visible_width)]
+            } else if self.code_no_wrap {
+                // Scroll no further than shows the end of the widest code line.
+                let code_lines = markdown::code_lines(&shown.content);
+                let code_width = shown.content.split('\n').enumerate()
+                    .filter(|(j, _)| code_lines.binary_search(j).is_ok())
+                    .map(|(_, line)| line.width())
+                    .max()
+                    .unwrap_or(0);
+                let available = self.content_width(shown, visible_width);
+                let code_offset = self.code_offset(self.ids[i]).min(code_width.saturating_sub(available));
+                clamped_offsets.push((self.ids[i], code_offset));
+                if self.selected == Some(self.ids[i]) && code_width > available {
+                    code_position = Some((code_offset + 1, (code_offset + available).min(code_width), code_width));
+                }
+                self.wrap_message_scrolled(shown, visible_width, code_offset)
+            } else {
+                self.wrap_message(shown, visible_width)
+            };
+            self.collapse(&mut rows, msg, self.ids[i]);
+            if !single_line {
+                self.add_read_markers(&mut rows, self.ids[i], visible_width);
+            }
+            if self.notify_rules.classify(&self.shown_for_matching(msg, self.ids[i])) > NotifyLevel::None {
+                for row in &mut rows {
+                    *row = row.clone().patch_style(self.theme.highlight);
+                }
+            }
+            if !matched || self.presence(&msg.sender) == Some(Presence::Offline) {
+                for row in &mut rows {
+                    *row = row.clone().patch_style(self.theme.muted);
+                }
+            }
+            if self.selected == Some(self.ids[i]) {
+                for row in &mut rows {
+                    *row = row.clone().patch_style(self.selection_style);
+                }
+            }
+            lines.extend(rows);
+            if self.last_read == Some(self.ids[i]) && i + 1 < self.messages.len() {
+                lines.push(self.unread_divider(visible_width));
+            }
+            for j in 0..lines.len() {
+                self.message_lines.push((i, j));
+            }
+            self.lines.extend(lines);
+        }
+
+        self.text_selection = selection.and_then(|(from, to)| Some((self.relocate(&from?)?, self.relocate(&to?)?)));
+        self.selection_anchor = selection_anchor.flatten().and_then(|anchor| self.relocate(&anchor));
+
+        for (id, offset) in clamped_offsets {
+            if offset == 0 {
+                self.code_offsets.remove(&id);
+            } else {
+                self.code_offsets.insert(id, offset);
+            }
+        }
+
+        if let Some((message, row)) = anchor
+            && let Some(start) = self.message_lines.iter().position(|&(i, _)| i == message)
+        {
+            let rows = self.message_lines[start..].iter().take_while(|&&(i, _)| i == message).count();
+            self.offset = start + row.min(rows - 1);
+            self.shown_offset = None;
+        }
+
+        let total_lines = self.message_lines.len();
+
+        let max_offset = total_lines.saturating_sub(visible_height);
+        if self.auto_scroll {
+            self.offset = max_offset;
+        }
+        self.offset = self.offset.min(max_offset);
+        let shown_offset = self.shown_offset.map_or(self.offset, |shown| shown.min(max_offset));
+
+        // Slice the lines to show only visible ones
+        let items: Vec<ListItem> = self.lines.iter().skip(shown_offset).take(visible_height).map(|line| {
+            ListItem::new(line.clone())
+        }).collect();
+
+        let mut title = self.pane.as_ref().map_or(&self.strings.chat_title, |(name, _)| name).clone();
+        if let Some(position) = self.position_title(shown_offset, visible_height) {
+            title = format!("{title} {position}");
+        }
+        let mut block = self.block(&title);
+        if let Some((_, false)) = self.pane {
+            block = block.border_style(self.theme.muted);
+        }
+        if !self.show_pinned && !self.pinned.is_empty() {
+            block = block.title(strings::fill(&self.strings.pinned_count, &[("count", &self.pinned.len())]));
+        }
+        match &self.matcher {
+            Some(Ok(_)) => {
+                let count = self.filter_matches();
+                let template = if self.filter_regex { &self.strings.regex_matches } else { &self.strings.filter_matches };
+                block = block.title(strings::fill(template, &[("query", &self.filter), ("count", &count)]));
+            }
+            Some(Err(error)) => {
+                let title = strings::fill(&self.strings.filter_invalid, &[("query", &self.filter), ("error", error)]);
+                block = block.title(Line::styled(title, Style::default().fg(ratatui::style::Color::Red)));
+            }
+            None => {}
+        }
+        if let Some(label) = self.sticky_header() {
+            block = block.title(Line::from(format!(" {label} ")).right_aligned());
+        }
+        if let Some((from, to, total)) = code_position {
+            let label = strings::fill(&self.strings.code_columns, &[("from", &from), ("to", &to), ("total", &total)]);
+            block = block.title_bottom(Line::from(format!(" {label} ")).right_aligned());
+        }
+        let list = List::new(items).block(block);
+
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(Some(self.theme.glyph("↑", "^")))
+            .end_symbol(Some(self.theme.glyph("↓", "v")));
+        let scrollbar = if self.theme.plain_glyphs {
+            scrollbar.track_symbol(Some("|")).thumb_symbol("#")
+        } else {
+            scrollbar
+        };
+
+        // Update scrollbar state
+        self.scrollbar_state = self.scrollbar_state.content_length(total_lines.saturating_sub(visible_height));
+        self.scrollbar_state = self.scrollbar_state.position(shown_offset);
+
+        let split = Layout::horizontal([Constraint::Min(1), Constraint::Length(1)]).split(area);
+        frame.render_widget(list, split[0]);
+        frame.render_stateful_widget(scrollbar, split[1], &mut self.scrollbar_state);
+
+        self.text_area = Rect::new(area.x + 1, area.y + 1, visible_width as u16, visible_height as u16);
+        self.top_row = shown_offset;
+        if let Some(((first, from), (last, to))) = self.text_selection {
+            for y in 0..visible_height.min(total_lines.saturating_sub(shown_offset)) {
+                let line = shown_offset + y;
+                if line < first || line > last {
+                    continue;
+                }
+                let start = if line == first { from } else { 0 };
+                let end = if line == last { to + 1 } else { visible_width };
+                let cells = Rect::new(self.text_area.x + start as u16, self.text_area.y + y as u16, (end - start) as u16, 1);
+                frame.buffer_mut().set_style(cells.intersection(self.text_area), Modifier::REVERSED);
+            }
+        }
+    }
+
+}
+
+/// The footer text for a message's metadata, such as `gpt-4o · 1.2s · 12 → 40 tokens · $0.0031`.
+pub(crate) fn metadata_summary(metadata: &MessageMetadata, strings: &Strings) -> String {
+    let mut parts = Vec::new();
+    if let Some(model) = &metadata.model {
+        parts.push(model.clone());
+    }
+    if let Some(elapsed) = metadata.elapsed {
+        parts.push(format!("{:.1}s", elapsed.as_secs_f64()));
+    }
+    if metadata.prompt_tokens.is_some() || metadata.completion_tokens.is_some() {
+        let count = |tokens: Option<u64>| tokens.map_or("?".to_string(), |n| n.to_string());
+        parts.push(strings::fill(
+            &strings.metadata_tokens,
+            &[("prompt", &count(metadata.prompt_tokens)), ("completion", &count(metadata.completion_tokens))],
+        ));
+    }
+    if let Some(cost) = metadata.cost {
+        parts.push(format!("${cost:.4}"));
+    }
+    parts.join(" · ")
+}
+
+/// Whole seconds in `duration`, rounded up, as a countdown shows them.
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// A rope line split into its text and the line break ending it, which is empty on the
+/// last line.
+fn split_line_break(line: &str) -> (&str, &str) {
+    let text = line.trim_end_matches(['\n', '\r', '\u{0B}', '\u{0C}', '\u{85}', '\u{2028}', '\u{2029}']);
+    (text, &line[text.len()..])
+}
+
+/// A widget for multiline text input with cursor navigation.
+///
+/// Supports typing, backspace, cursor movement (arrows, up/down for lines),
+/// and handles text wrapping and scrolling for long inputs.
+pub struct InputArea {
+    buffer: Rope,        // current typed text
+    cursor: usize,       // cursor position in buffer, in bytes
+    offset: (usize, usize), // first line shown and its wrapped rows scrolled past
+    preedit: String,     // in-progress IME composition, shown at the cursor
+    all_selected: bool,  // whether the whole draft is selected, to be replaced by the next edit
+    prompt: String,      // drawn before the first line
+    continuation_prompt: String, // drawn before every later line
+    theme: Theme,
+    strings: Strings,
+    dirty: bool,         // whether anything shown has changed since the last render
+    token_counter: Option<Box<dyn TokenCounter>>,
+    token_count: Cell<Option<usize>>, // counted at most once per edit
+    token_budget: Option<usize>,
+    enabled: bool,
+    disabled_reason: Option<String>,
+    height: InputHeight,
+    rows: usize,         // text rows shown as of the last render
+    width: usize,        // columns inside the borders as of the last render
+    cooldown: Option<Instant>, // submission is rejected until then
+    cooldown_shown: u64,       // seconds left as of the last render
+    on_cooldown_end: Option<Box<dyn FnMut()>>,
+    #[cfg(feature = "spellcheck")]
+    spelling: Option<spellcheck::Spelling>,
+    #[cfg(feature = "spellcheck")]
+    misspelled_style: Style,
+}
+
+impl Default for InputArea {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputArea {
+    pub fn new() -> Self {
+        Self {
+            buffer: Rope::new(),
+            cursor: 0,
+            offset: (0, 0),
+            preedit: String::new(),
+            all_selected: false,
+            prompt: "> ".to_string(),
+            continuation_prompt: "> ".to_string(),
+            theme: ThemePreset::default().theme(),
+            strings: Strings::default(),
+            dirty: true,
+            token_counter: None,
+            token_count: Cell::new(None),
+            token_budget: None,
+            enabled: true,
+            disabled_reason: None,
+            height: InputHeight::default(),
+            rows: InputHeight::default().limits().1,
+            width: 0,
+            cooldown: None,
+            cooldown_shown: 0,
+            on_cooldown_end: None,
+            #[cfg(feature = "spellcheck")]
+            spelling: None,
+            #[cfg(feature = "spellcheck")]
+            misspelled_style: spellcheck::MISSPELLED,
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.dirty |= self.theme != theme;
+        self.theme = theme;
+    }
+
+    /// Sets the prompt drawn before the first line of the input, such as `"❯ "`, and the
+    /// one drawn before each line after it, such as `"… "`. Both are `"> "` by default.
+    pub fn set_prompt(&mut self, first: impl Into<String>, continuation: impl Into<String>) {
+        let (first, continuation) = (first.into(), continuation.into());
+        self.dirty |= self.prompt != first || self.continuation_prompt != continuation;
+        self.prompt = first;
+        self.continuation_prompt = continuation;
+    }
+
+    /// The prompt drawn before buffer line `line`, counting from 0.
+    pub fn prompt(&self, line: usize) -> &str {
+        if line == 0 { &self.prompt } else { &self.continuation_prompt }
+    }
+
+    /// Columns taken by the prompt before buffer line `line`, which the text and cursor
+    /// are shifted right by.
+    pub fn prompt_width(&self, line: usize) -> u16 {
+        self.prompt(line).width() as u16
+    }
+
+    /// Underlines words the checker rejects and enables [`InputArea::next_suggestion`].
+    #[cfg(feature = "spellcheck")]
+    pub fn set_spellchecker(&mut self, checker: impl SpellChecker + 'static) {
+        self.dirty = true;
+        self.spelling = Some(spellcheck::Spelling::new(Box::new(checker)));
+    }
+
+    #[cfg(feature = "spellcheck")]
+    pub fn clear_spellchecker(&mut self) {
+        self.dirty = true;
+        self.spelling = None;
+    }
+
+    /// Sets the style of misspelled words. Defaults to red and underlined. The style is kept
+    /// when the spell checker is set or replaced later.
+    #[cfg(feature = "spellcheck")]
+    pub fn set_misspelled_style(&mut self, style: Style) {
+        self.dirty |= self.spelling.is_some() && self.misspelled_style != style;
+        self.misspelled_style = style;
+    }
+
+    /// Replaces the misspelled word at the cursor with the spell checker's next suggestion.
+    /// Repeated calls cycle through the suggestions and back to the original word.
+    ///
+    /// Returns `false` if there is no misspelled word at the cursor or no suggestion for it.
+    #[cfg(feature = "spellcheck")]
+    pub fn next_suggestion(&mut self) -> bool {
+        self.dirty = true;
+        let Some(spelling) = self.spelling.as_mut().filter(|_| self.enabled) else {
+            return false;
+        };
+        let line = self.buffer.byte_to_line(self.cursor);
+        let line_start = self.buffer.line_to_byte(line);
+        let text = self.buffer.line(line).to_string();
+        let Some((range, word)) = spelling.next_suggestion(&text, self.cursor - line_start) else {
+            return false;
+        };
+        let start = self.buffer.byte_to_char(line_start + range.start);
+        let end = self.buffer.byte_to_char(line_start + range.end);
+        self.buffer.remove(start..end);
+        self.buffer.insert(start, &word);
+        self.edited();
+        self.cursor = line_start + range.start + word.len();
+        true
+    }
+
+    /// Enables or disables the input. While disabled it is greyed out and ignores edits and
+    /// submission, for example to prevent sending twice while a request is in flight. The
+    /// cursor can still move and the draft is kept.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.dirty |= self.enabled != enabled;
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Sets text shown on the border while the input is disabled, such as "waiting for response…".
+    pub fn set_disabled_reason(&mut self, reason: Option<String>) {
+        self.dirty |= self.disabled_reason != reason;
+        self.disabled_reason = reason;
+    }
+
+    /// Sets how many lines the input shows, see [`InputHeight`]. Defaults to growing from 1 to 10 lines.
+    pub fn set_height(&mut self, height: InputHeight) {
+        self.dirty |= self.height != height;
+        self.height = height;
+    }
+
+    pub fn height(&self) -> InputHeight {
+        self.height
+    }
+
+    /// Shows an estimate of the buffer's token count on the input border, such as `~412 tokens`.
+    /// Use [`HeuristicCounter`] when no tokenizer is at hand.
+    pub fn set_token_counter(&mut self, counter: impl TokenCounter + 'static) {
+        self.dirty = true;
+        self.token_counter = Some(Box::new(counter));
+        self.token_count.set(None);
+    }
+
+    pub fn clear_token_counter(&mut self) {
+        self.dirty = true;
+        self.token_counter = None;
+    }
+
+    /// Shows the token count as a warning once it exceeds `budget`.
+    pub fn set_token_budget(&mut self, budget: Option<usize>) {
+        self.dirty |= self.token_budget != budget;
+        self.token_budget = budget;
+    }
+
+    /// The token count of the buffer, if a counter is set. The text is counted again only
+    /// after it changes.
+    pub fn token_count(&self) -> Option<usize> {
+        let counter = self.token_counter.as_ref()?;
+        let count = self.token_count.get().unwrap_or_else(|| counter.count(&self.buffer.to_string()));
+        self.token_count.set(Some(count));
+        Some(count)
+    }
+
+    /// Forgets what depends on the buffer's text, after an edit.
+    fn edited(&mut self) {
+        self.token_count.set(None);
+        self.all_selected = false;
+    }
+
+    /// Selects the whole draft: it is drawn highlighted, and typing, pasting or Backspace
+    /// next replaces it. Moving the cursor drops the selection.
+    pub fn select_all(&mut self) {
+        self.dirty = true;
+        self.all_selected = self.enabled && self.buffer.len_bytes() > 0;
+    }
+
+    pub fn is_all_selected(&self) -> bool {
+        self.all_selected
+    }
+
+    /// Discards the draft if it is all selected, so the edit under way replaces it.
+    /// Returns whether it did.
+    fn replace_selection(&mut self) -> bool {
+        if !self.all_selected {
+            return false;
+        }
+        self.buffer = Rope::new();
+        self.edited();
+        self.cursor = 0;
+        self.offset = (0, 0);
+        true
+    }
+
+    /// Rejects submission for `duration`, showing the remaining seconds on the input border,
+    /// for backends that enforce rate limits. Typing is still allowed.
+    pub fn set_cooldown(&mut self, duration: Duration) {
+        self.dirty = true;
+        self.cooldown = Some(Instant::now() + duration);
+    }
+
+    /// Ends the cooldown early, without calling the callback.
+    pub fn clear_cooldown(&mut self) {
+        self.dirty = true;
+        self.cooldown = None;
+    }
+
+    /// Time left before submission is accepted again, or `None` if there is no cooldown.
+    pub fn cooldown_remaining(&self) -> Option<Duration> {
+        self.cooldown.map(|until| until.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether a cooldown is running, so [`InputArea::submit`] returns nothing.
+    pub fn is_cooling_down(&self) -> bool {
+        self.cooldown_remaining().is_some_and(|left| !left.is_zero())
+    }
+
+    /// Sets a callback run from [`InputArea::tick`] once a cooldown runs out.
+    pub fn set_on_cooldown_end(&mut self, callback: impl FnMut() + 'static) {
+        self.on_cooldown_end = Some(Box::new(callback));
+    }
+
+    /// Updates the cooldown countdown. Call this periodically from the host loop.
+    ///
+    /// Returns whether the cooldown ended, after running the callback.
+    pub fn tick(&mut self) -> bool {
+        let Some(left) = self.cooldown_remaining() else {
+            return false;
+        };
+        // The border shows whole seconds, so only redraw when they change.
+        if ceil_secs(left) != self.cooldown_shown {
+            self.dirty = true;
+        }
+        if !left.is_zero() {
+            return false;
+        }
+        self.cooldown = None;
+        if let Some(callback) = self.on_cooldown_end.as_mut() {
+            callback();
+        }
+        true
+    }
+
+    /// Sets the title drawn on the input border.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.dirty |= self.strings != strings;
+        self.strings = strings;
+    }
+
+    /// Shows an in-progress input method composition, underlined at the cursor.
+    ///
+    /// The preedit text is not part of the buffer; once the input method commits, clear it
+    /// with an empty string and insert the committed text with [`InputArea::insert_str`].
+    pub fn set_preedit(&mut self, text: impl Into<String>) {
+        self.dirty = true;
+        if !self.enabled {
+            return;
+        }
+        self.preedit = text.into().replace(['\r', '\n'], "");
+    }
+
+    pub fn preedit(&self) -> &str {
+        &self.preedit
+    }
+
+    /// Text of a logical line without its line break, with the preedit inserted if the
+    /// cursor is on it, and the byte range the preedit occupies.
+    fn display_line(&self, line: usize) -> (String, std::ops::Range<usize>) {
+        let mut text = self.buffer.line(line).to_string();
+        if text.ends_with('\n') {
+            text.pop();
+        }
+        if self.buffer.byte_to_line(self.cursor) != line {
+            return (text, 0..0);
+        }
+        let at = self.cursor - self.buffer.line_to_byte(line);
+        text.insert_str(at, &self.preedit);
+        (text, at..at + self.preedit.len())
+    }
+
+    pub fn calculate_display_lines(&self, width: u16) -> u16 {
+        let (min, max) = self.height.limits();
+        let prompt_width = self.prompt_width(0).max(self.prompt_width(1));
+        if width.saturating_sub(2 + prompt_width) == 0 || min == max {
+            return min as u16 + 2;
+        }
+        let width = width.saturating_sub(2) as usize; // 2 for borders
+        let mut total_lines = 0;
+        // Only count as far as the display can grow, so huge drafts stay cheap to lay out.
+        for line in 0..self.buffer.len_lines() {
+            total_lines += self.row_starts(line, width).len();
+            if total_lines >= max {
+                break;
+            }
+        }
+        let visible_lines = total_lines.clamp(min, max);
+        (visible_lines as u16) + 2 // +2 for top and bottom borders
+    }
+
+    pub fn insert_char(&mut self, ch: char) {
+        self.dirty = true;
+        if !self.enabled {
+            return;
+        }
+        let ch = if ch == '\r' { '\n' } else { ch };
+        self.replace_selection();
+        self.cursor = self.cursor.min(self.buffer.len_bytes());
+        self.buffer.insert_char(self.buffer.byte_to_char(self.cursor), ch);
+        self.edited();
+        self.cursor += ch.len_utf8();
+    }
+
+    /// The text in the buffer.
+    pub fn text(&self) -> String {
+        self.buffer.to_string()
+    }
+
+    /// Replaces the buffer with `text`, for example to prefill a reply, and moves the cursor
+    /// to its end. Line endings are normalized to \n. Works while the input is disabled.
+    pub fn set_text(&mut self, text: &str) {
+        self.dirty = true;
+        let normalized = text.replace("\r\n", "\n").replace('\r', "\n");
+        self.buffer = Rope::from_str(&normalized);
+        self.edited();
+        self.cursor = normalized.len();
+        self.preedit.clear();
+        self.offset = (0, 0);
+    }
+
+    /// Inserts a string into the buffer at the cursor position, normalizing line endings to \n.
+    pub fn insert_str(&mut self, s: &str) {
+        self.dirty = true;
+        if !self.enabled {
+            return;
+        }
+        let normalized = s.replace("\r\n", "\n").replace('\r', "\n");
+        self.replace_selection();
+        self.cursor = self.cursor.min(self.buffer.len_bytes());
+        self.buffer.insert(self.buffer.byte_to_char(self.cursor), &normalized);
+        self.edited();
+        self.cursor += normalized.len();
+    }
+
+    pub fn backspace(&mut self) {
+        self.dirty = true;
+        if !self.enabled || self.replace_selection() {
+            return;
+        }
+        let idx = self.buffer.byte_to_char(self.cursor);
+        if idx > 0 {
+            self.buffer.remove(idx - 1..idx);
+            self.edited();
+            self.cursor = self.buffer.char_to_byte(idx - 1);
+        }
+    }
+
+    pub fn cursor_left(&mut self) {
+        self.dirty = true;
+        self.all_selected = false;
+        let idx = self.buffer.byte_to_char(self.cursor);
+        if idx > 0 {
+            self.cursor = self.buffer.char_to_byte(idx - 1);
+        }
+    }
+
+    pub fn cursor_right(&mut self) {
+        self.dirty = true;
+        self.all_selected = false;
+        let idx = self.buffer.byte_to_char(self.cursor);
+        if idx < self.buffer.len_chars() {
+            self.cursor = self.buffer.char_to_byte(idx + 1);
+        }
+    }
+
+    /// Number of characters in a logical line, excluding its line break.
+    fn line_len_chars(&self, line: usize) -> usize {
+        let slice = self.buffer.line(line);
+        slice.len_chars() - usize::from(slice.chars().last() == Some('\n'))
+    }
+
+    /// Moves the cursor to `line`, keeping its column where that line is long enough.
+    fn move_to_line(&mut self, line: usize) {
+        let idx = self.buffer.byte_to_char(self.cursor);
+        let current = self.buffer.char_to_line(idx);
+        let col = idx - self.buffer.line_to_char(current);
+        let col = col.min(self.line_len_chars(line));
+        self.cursor = self.buffer.char_to_byte(self.buffer.line_to_char(line) + col);
+    }
+
+    pub fn cursor_up(&mut self) {
+        self.dirty = true;
+        self.all_selected = false;
+        let line = self.buffer.byte_to_line(self.cursor);
+        if line > 0 {
+            self.move_to_line(line - 1);
+        }
+    }
+
+    pub fn cursor_down(&mut self) {
+        self.dirty = true;
+        self.all_selected = false;
+        let line = self.buffer.byte_to_line(self.cursor);
+        if line + 1 < self.buffer.len_lines() {
+            self.move_to_line(line + 1);
+        }
+    }
+
+    pub fn newline(&mut self) {
+        self.dirty = true;
+        self.insert_char('\n');
+    }
+
+    /// Discards the draft, unlike [`InputArea::submit`] which returns it.
+    pub fn clear(&mut self) {
+        self.dirty = true;
+        if !self.enabled {
+            return;
+        }
+        self.buffer = Rope::new();
+        self.edited();
+        self.preedit.clear();
+        self.cursor = 0;
+        self.offset = (0, 0);
+    }
+
+    /// Swaps the cursor's line with the one above it, keeping the cursor on the moved line.
+    pub fn move_line_up(&mut self) {
+        let line = self.buffer.byte_to_line(self.cursor);
+        if line > 0 {
+            self.swap_lines(line - 1, line - 1);
+        }
+    }
+
+    /// Swaps the cursor's line with the one below it, keeping the cursor on the moved line.
+    pub fn move_line_down(&mut self) {
+        let line = self.buffer.byte_to_line(self.cursor);
+        if line + 1 < self.buffer.len_lines() {
+            self.swap_lines(line, line + 1);
+        }
+    }
+
+    /// Inserts a copy of the cursor's line below it and moves the cursor onto the copy.
+    pub fn duplicate_line(&mut self) {
+        self.dirty = true;
+        if !self.enabled {
+            return;
+        }
+        let line = self.buffer.byte_to_line(self.cursor);
+        let column = self.cursor - self.buffer.line_to_byte(line);
+        let end = self.buffer.line_to_char(line + 1);
+        let text = self.buffer.line(line).to_string();
+        // The copy goes after the line's break, or after a new one on the last line.
+        let copy = match split_line_break(&text) {
+            (text, "") => format!("\n{text}"),
+            (text, line_break) => format!("{text}{line_break}"),
+        };
+        self.buffer.insert(end, &copy);
+        self.edited();
+        self.cursor = self.buffer.line_to_byte(line + 1) + column;
+        self.preedit.clear();
+    }
+
+    /// Swaps line `upper` with the line below it and puts the cursor on line `to`, at the
+    /// same column where the line is long enough. Only the two lines are rewritten.
+    fn swap_lines(&mut self, upper: usize, to: usize) {
+        self.dirty = true;
+        if !self.enabled {
+            return;
+        }
+        let column = self.cursor - self.buffer.line_to_byte(self.buffer.byte_to_line(self.cursor));
+        let (start, middle, end) = (
+            self.buffer.line_to_char(upper),
+            self.buffer.line_to_char(upper + 1),
+            self.buffer.line_to_char(upper + 2),
+        );
+        let first = self.buffer.slice(start..middle).to_string();
+        let second = self.buffer.slice(middle..end).to_string();
+        // Line breaks stay in place, so the last line still has none after the swap.
+        let (first, first_break) = split_line_break(&first);
+        let (second, second_break) = split_line_break(&second);
+        self.buffer.remove(start..end);
+        self.buffer.insert(start, &format!("{second}{first_break}{first}{second_break}"));
+        self.edited();
+        let moved = if to == upper { second } else { first };
+        let mut column = column.min(moved.len());
+        while !moved.is_char_boundary(column) {
+            column -= 1;
+        }
+        self.cursor = self.buffer.line_to_byte(to) + column;
+        self.preedit.clear();
+    }
+
+    /// Takes the buffer and clears the input. Returns an empty string, keeping the draft,
+    /// while disabled or cooling down.
+    pub fn submit(&mut self) -> String {
+        if !self.enabled || self.is_cooling_down() {
+            return String::new();
+        }
+        self.dirty = true;
+        let input = self.buffer.to_string();
+        self.buffer = Rope::new();
+        self.edited();
+        self.preedit.clear();
+        self.cursor = 0;
+        self.offset = (0, 0);
+        input
+    }
+
+    /// Scrolls up by `lines` rows, as wrapped at the last render.
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.dirty = true;
+        self.offset = self.rows_up(self.offset, lines, self.width);
+    }
+
+    /// Scrolls down by `lines` rows, as wrapped at the last render.
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.dirty = true;
+        self.offset = self.rows_down(self.offset, lines, self.width);
+    }
+
+    /// The first buffer line shown, which may be scrolled partway through its wrapped rows.
+    pub fn get_offset(&self) -> usize {
+        self.offset.0
+    }
+
+    /// Columns the text of buffer line `line` wraps to in an input `width` columns wide
+    /// inside its borders.
+    fn text_width(&self, line: usize, width: usize) -> usize {
+        width.saturating_sub(self.prompt_width(line) as usize).max(1)
+    }
+
+    /// Where each row of buffer line `line` starts in its displayed text, wrapped as
+    /// `render` draws it in an input `width` columns wide inside its borders.
+    fn row_starts(&self, line: usize, width: usize) -> Vec<usize> {
+        let (text, _) = self.display_line(line);
+        wrap::row_starts(&text, &textwrap::Options::new(self.text_width(line, width)))
+    }
+
+    /// The buffer line and wrapped row the cursor is on.
+    fn cursor_row(&self, width: usize) -> (usize, usize) {
+        let line = self.buffer.byte_to_line(self.cursor);
+        let (_, preedit) = self.display_line(line);
+        let starts = self.row_starts(line, width);
+        (line, starts.partition_point(|&start| start <= preedit.end) - 1)
+    }
+
+    /// The wrapped row `n` rows above `(line, row)`, or the first.
+    fn rows_up(&self, (mut line, mut row): (usize, usize), mut n: usize, width: usize) -> (usize, usize) {
+        while n > row {
+            if line == 0 {
+                return (0, 0);
+            }
+            n -= row + 1;
+            line -= 1;
+            row = self.row_starts(line, width).len() - 1;
+        }
+        (line, row - n)
+    }
+
+    /// The wrapped row `n` rows below `(line, row)`, or the last.
+    fn rows_down(&self, (mut line, mut row): (usize, usize), mut n: usize, width: usize) -> (usize, usize) {
+        loop {
+            let rows = self.row_starts(line, width).len();
+            if row + n < rows || line + 1 >= self.buffer.len_lines() {
+                return (line, (row + n).min(rows - 1));
+            }
+            n -= rows - row;
+            line += 1;
+            row = 0;
+        }
+    }
+
+    /// Wrapped rows from `from` down to `to`, counted no further than past `limit`.
+    fn rows_between(&self, from: (usize, usize), to: (usize, usize), limit: usize, width: usize) -> usize {
+        let (mut line, mut row) = from;
+        let mut rows = 0;
+        while line < to.0 && rows <= limit {
+            rows += self.row_starts(line, width).len() - row;
+            line += 1;
+            row = 0;
+        }
+        rows + to.1.saturating_sub(row)
+    }
+
+    /// Whether anything shown has changed since the last render.
+    pub fn needs_redraw(&self) -> bool {
+        self.dirty
+    }
+
+    /// Row and column of the cursor inside the input's borders, as of the last render, or
+    /// `None` if it is scrolled out of view.
+    fn cursor_cell(&self) -> Option<(u16, u16)> {
+        let (line, cursor_row) = self.cursor_row(self.width);
+        if (line, cursor_row) < self.offset {
+            return None;
+        }
+        let row = self.rows_between(self.offset, (line, cursor_row), self.rows, self.width);
+        if row >= self.rows {
+            return None;
+        }
+        let (text, preedit) = self.display_line(line);
+        let starts = self.row_starts(line, self.width);
+        let start = starts[cursor_row];
+        let end = starts.get(cursor_row + 1).copied().unwrap_or(text.len());
+        // The row as drawn, without the spaces it ends in.
+        let shown = text[start..end].trim_end_matches(' ');
+        let before = &text[start..preedit.end];
+        let width = if before.len() <= shown.len() {
+            // Right-to-left runs are displayed reordered, so move the cursor with its character.
+            let visual = bidi::visual_column(shown, before.chars().count());
+            // Wide characters, such as CJK in a preedit, take two cells.
+            bidi::reorder_text(shown).chars().take(visual).filter_map(UnicodeWidthChar::width).sum()
+        } else {
+            before.width()
+        };
+        Some((row as u16, self.prompt_width(line) + width as u16))
+    }
+
+    /// Renders into `buf` within `area` without a terminal, for example for snapshot tests.
+    pub fn render_to_buffer(&mut self, buf: &mut ratatui::buffer::Buffer, area: Rect) {
+        testing::render_headless(buf, area, |frame, area| self.render(frame, area));
+    }
+
+    /// Renders the buffer as formatted markdown, scrolled along with the input.
+    pub fn render_preview(&self, frame: &mut Frame, area: Rect) {
+        let lines = markdown::render(&self.buffer.to_string(), &self.theme);
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.offset.0 as u16, 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(self.theme.border)
+                    .border_set(self.theme.border_set())
+                    .title(self.strings.preview_title.as_str())
+                    .title_style(self.theme.title),
+            );
+        frame.render_widget(paragraph, area);
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        self.dirty = false;
+        // Fill whatever room the host gives, which may differ from the requested height.
+        self.rows = (area.height.saturating_sub(2) as usize).max(1);
+        self.width = (area.width.saturating_sub(2) as usize).max(1);
+        let width = self.width;
+        let last_line = self.buffer.len_lines() - 1;
+        let last_row = (last_line, self.row_starts(last_line, width).len() - 1);
+        let offset_line = self.offset.0.min(last_line);
+        let offset_rows = self.row_starts(offset_line, width).len();
+        self.offset = (offset_line, self.offset.1.min(offset_rows - 1));
+
+        // Auto-scroll to keep cursor visible, counting wrapped rows
+        let cursor = self.cursor_row(width);
+        if cursor < self.offset {
+            self.offset = cursor;
+        } else if self.rows_between(self.offset, cursor, self.rows, width) >= self.rows {
+            self.offset = self.rows_up(cursor, self.rows - 1, width);
+        }
+        self.offset = self.offset.min(self.rows_up(last_row, self.rows - 1, width));
+
+        // Lay out lines from the first visible row until the rows are filled
+        let mut display: Vec<Line> = Vec::new();
+        for index in self.offset.0..=last_line {
+            if display.len() >= self.rows {
+                break;
+            }
+            let (text, preedit) = self.display_line(index);
+            let spans = vec![
+                Span::raw(text[..preedit.start].to_string()),
+                Span::styled(text[preedit.clone()].to_string(), Style::default().add_modifier(Modifier::UNDERLINED)),
+                Span::raw(text[preedit.end..].to_string()),
+            ];
+            #[cfg(feature = "spellcheck")]
+            let spans = match &self.spelling {
+                Some(spelling) => search::highlight(spans, &spelling.misspelled(&text), self.misspelled_style),
+                None => spans,
+            };
+            // Wrap before reordering, so right-to-left runs are reversed within each row
+            // rather than across the whole line.
+            let prompt = self.prompt(index);
+            let indent = prompt.width();
+            let options = textwrap::Options::new(self.text_width(index, width));
+            let skip = if index == self.offset.0 { self.offset.1 } else { 0 };
+            let rows = wrap::wrap_spans(&spans, &options, false).into_iter().enumerate().skip(skip);
+            display.extend(rows.map(|(row, line)| {
+                let mut line = bidi::reorder_line(line);
+                if self.all_selected {
+                    for span in &mut line.spans {
+                        span.style = span.style.add_modifier(Modifier::REVERSED);
+                    }
+                }
+                let lead = if row == 0 { prompt.to_string() } else { " ".repeat(indent) };
+                line.spans.insert(0, Span::raw(lead));
+                line
+            }));
+        }
+        display.truncate(self.rows);
+
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(self.theme.border)
+            .border_set(self.theme.border_set())
+            .title(self.strings.input_title.as_str())
+            .title_style(self.theme.title);
+        if !self.enabled {
+            block = block.border_style(self.theme.muted).title_style(self.theme.muted);
+            if let Some(reason) = &self.disabled_reason {
+                block = block.title(Line::styled(format!("({reason})"), self.theme.muted.add_modifier(Modifier::ITALIC)));
+            }
+        }
+        self.cooldown_shown = self.cooldown_remaining().map_or(0, ceil_secs);
+        if self.cooldown_shown > 0 {
+            let seconds = self.cooldown_shown;
+            let title = strings::fill(&self.strings.cooldown, &[("seconds", &seconds)]);
+            block = block.title(Line::styled(title, Style::default().fg(ratatui::style::Color::Yellow)));
+        }
+        if let Some(count) = self.token_count() {
+            let title = match self.token_budget {
+                Some(budget) if count > budget => Line::styled(
+                    strings::fill(&self.strings.token_budget_exceeded, &[("count", &count), ("budget", &budget)]),
+                    Style::default().fg(ratatui::style::Color::Red),
+                ),
+                _ => Line::styled(strings::fill(&self.strings.token_count, &[("count", &count)]), self.theme.muted),
+            };
+            block = block.title(title.right_aligned());
+        }
+        let mut paragraph = Paragraph::new(display).wrap(Wrap { trim: false }).block(block);
+        if !self.enabled {
+            paragraph = paragraph.style(self.theme.muted);
+        }
+        frame.render_widget(paragraph, area);
+    }
+
+
+}
+
+/// Which part of [`ChatApp`] receives key input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Focus {
+    Input,
+    Channels,
+    // Typing edits the active chat's filter.
+    Filter,
+    // Up/Down move the message selection.
+    Messages,
+}
+
+/// Messages loaded from the message store at a time.
+const HISTORY_PAGE: usize = 50;
+
+/// Columns Left and Right scroll unwrapped code by.
+const CODE_SCROLL_STEP: usize = 8;
+
+/// Longest time between clicks on the same cell that still counts them as a double or
+/// triple click.
+const MULTI_CLICK: Duration = Duration::from_millis(400);
+
+/// Settings [`ChatApp`] applies to every conversation, kept for the conversations added
+/// later. `None` leaves the widget's own default.
+#[derive(Clone, Default)]
+struct ConversationDefaults {
+    label_code_blocks: bool,
+    typing_position: TypingPosition,
+    typing_timeout: Option<Duration>,
+    dim_unmatched: bool,
+    selection_style: Option<Style>,
+    show_scroll_position: bool,
+    input_prompt: Option<(String, String)>,
+    input_height: Option<InputHeight>,
+    relative_timestamps: Option<Duration>,
+    sender_colors: Vec<Color>,
+}
+
+/// A complete chat application coordinator.
+///
+/// Combines ChatArea and InputArea into a full chat interface.
+/// Handles key events and rendering. Useful for quick prototyping or as a reference
+/// for integrating the individual widgets.
+///
+/// Settings the app applies to every conversation also apply to conversations added later
+/// with [`ChatApp::add_conversation`], by backend events, imports and restored sessions.
+pub struct ChatApp {
+    conversations: ConversationManager,
+    channel_list: ChannelList,
+    show_channels: bool,
+    show_members: bool,
+    // Whether chats mirror their member list's presence before senders' names.
+    presence_in_chat: bool,
+    show_header: bool,
+    status_bar: Option<StatusBar>,
+    keymap: Keymap,
+    toasts: Toasts,
+    // Open dialog and the tag the host gave it.
+    dialog: Option<(String, ConfirmDialog)>,
+    confirmation: Option<(String, bool)>,
+    settings: Settings,
+    defaults: ConversationDefaults,
+    settings_panel: Option<SettingsPanel>,
+    date_picker: Option<DatePicker>,
+    // The popup and the conversation and id of the message it shows, to follow its updates.
+    message_detail: Option<(usize, MessageId, MessageDetail)>,
+    #[cfg(feature = "chrono")]
+    time_format: TimeFormat,
+    preview: PreviewMode,
+    strings: Strings,
+    announcer: Option<Announcer>,
+    on_cancel_streaming: Option<CancelHandler>,
+    on_play_voice: Option<VoiceHandler>,
+    message_filter: Option<MessageFilter>,
+    middleware: Vec<Box<dyn MessageMiddleware>>,
+    store: Option<Box<dyn MessageStore>>,
+    // Messages the user sent, by conversation name, waiting for a backend; `None` when no
+    // backend picks them up.
+    outbox: Option<Vec<(String, ChatMessage)>>,
+    // Whether sent messages without an outbox get a canned reply, for demos.
+    simulated_replies: bool,
+    on_message_added: Option<MessageHandler>,
+    on_scrolled_away: Option<ScrollHandler>,
+    on_returned_to_bottom: Option<ScrollHandler>,
+    on_submit: Option<SubmitHandler>,
+    // The active conversation and whether its view followed the newest messages at the last render.
+    followed: (usize, bool),
+    smooth_scroll: bool,
+    // Whether the terminal window has focus, as reported by focus events.
+    terminal_focused: bool,
+    // Messages that arrived in the active conversation while the user was away from the bottom.
+    unseen: usize,
+    bell_on_message: bool,
+    notify_rules: NotifyRules,
+    // Indexed by `NotifyLevel`.
+    notify_actions: [NotifyActions; 3],
+    pending_bell: bool,
+    window_title: Option<String>,
+    written_title: Option<String>,
+    // Whether a steady cursor shape has been written for reduced motion.
+    steady_cursor: bool,
+    #[cfg(feature = "notifications")]
+    notifier: DesktopNotifier,
+    focus: Focus,
+    should_quit: bool,
+    suspend_requested: bool,
+    cursor_pos: Option<(u16, u16)>,
+    chat_rect: Rect,
+    // The conversations in the left and right panes of a split view, which of the two has
+    // focus and shows the active conversation, and where the other pane was drawn.
+    split: Option<[usize; 2]>,
+    split_focus: usize,
+    other_pane_rect: Rect,
+    // The area shared by the chat and the input, and the input's part of it.
+    split_rect: Rect,
+    input_rect: Rect,
+    // Percentage of `split_rect` given to the input, or `None` to size it to its text.
+    input_split: Option<u16>,
+    dragging_split: bool,
+    // Whether the left button went down over the chat and is selecting text.
+    selecting_text: bool,
+    copy_on_select: bool,
+    // When and where the left button last went down over the chat, and how many clicks in
+    // a row that made.
+    last_click: Option<(Instant, u16, u16, u8)>,
+    members_rect: Rect,
+    channels_rect: Rect,
+    tabs_rect: Rect,
+    // Whether anything outside the active chat and input has changed since the last render.
+    dirty: bool,
+}
+
+impl Default for ChatApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChatApp {
+    pub fn new() -> Self {
+        Self {
+            conversations: ConversationManager::default(),
+            channel_list: ChannelList::new(),
+            show_channels: false,
+            show_members: false,
+            presence_in_chat: false,
+            show_header: false,
+            status_bar: None,
+            keymap: Keymap::default(),
+            toasts: Toasts::new(),
+            dialog: None,
+            confirmation: None,
+            settings: Settings::default(),
+            defaults: ConversationDefaults::default(),
+            settings_panel: None,
+            date_picker: None,
+            message_detail: None,
+            #[cfg(feature = "chrono")]
+            time_format: TimeFormat::default(),
+            preview: PreviewMode::Off,
+            strings: Strings::default(),
+            announcer: None,
+            on_cancel_streaming: None,
+            on_play_voice: None,
+            message_filter: None,
+            middleware: Vec::new(),
+            store: None,
+            outbox: None,
+            simulated_replies: false,
+            on_message_added: None,
+            on_scrolled_away: None,
+            on_returned_to_bottom: None,
+            on_submit: None,
+            followed: (0, true),
+            smooth_scroll: false,
+            terminal_focused: true,
+            unseen: 0,
+            bell_on_message: false,
+            notify_rules: NotifyRules::new(),
+            notify_actions: [
+                NotifyActions::default(),
+                NotifyActions { toast: true, ..NotifyActions::default() },
+                NotifyActions { bell: true, toast: true, desktop: true },
+            ],
+            pending_bell: false,
+            window_title: None,
+            written_title: None,
+            steady_cursor: false,
+            #[cfg(feature = "notifications")]
+            notifier: DesktopNotifier::default(),
+            focus: Focus::Input,
+            should_quit: false,
+            suspend_requested: false,
+            cursor_pos: None,
+            chat_rect: Rect::default(),
+            split: None,
+            split_focus: 0,
+            other_pane_rect: Rect::default(),
+            split_rect: Rect::default(),
+            input_rect: Rect::default(),
+            input_split: None,
+            dragging_split: false,
+            selecting_text: false,
+            copy_on_select: true,
+            last_click: None,
+            members_rect: Rect::default(),
+            channels_rect: Rect::default(),
+            tabs_rect: Rect::default(),
+            dirty: true,
+        }
+    }
+
+    /// The conversations shown by this app. A tab bar is rendered once there is more than one.
+    pub fn conversations(&self) -> &ConversationManager {
+        &self.conversations
+    }
+
+    /// The conversations, for changing them directly. Conversations added through the
+    /// manager start with the widgets' defaults; use [`ChatApp::add_conversation`] to give
+    /// them the app's settings.
+    pub fn conversations_mut(&mut self) -> &mut ConversationManager {
+        self.dirty = true;
+        &mut self.conversations
+    }
+
+    /// Adds a conversation with the app's current settings, strings and theme, returning
+    /// its index.
+    pub fn add_conversation(&mut self, name: impl Into<String>) -> usize {
+        self.dirty = true;
+        let index = self.conversations.add_conversation(name);
+        self.configure_conversation(index);
+        index
+    }
+
+    /// Gives conversation `index` everything the app's setters have applied to the others.
+    fn configure_conversation(&mut self, index: usize) {
+        let conversation = self.conversations.get_mut(index).expect("index in range");
+        Self::apply_settings_to(conversation, self.settings, self.smooth_scroll);
+        let defaults = &self.defaults;
+        let chat_area = conversation.chat_area_mut();
+        chat_area.set_strings(self.strings.clone());
+        chat_area.set_notify_rules(self.notify_rules.clone());
+        #[cfg(feature = "chrono")]
+        chat_area.set_time_format(self.time_format.clone());
+        chat_area.set_label_code_blocks(defaults.label_code_blocks);
+        chat_area.set_dim_unmatched(defaults.dim_unmatched);
+        if let Some(style) = defaults.selection_style {
+            chat_area.set_selection_style(style);
+        }
+        chat_area.set_show_scroll_position(defaults.show_scroll_position);
+        chat_area.set_relative_timestamps(defaults.relative_timestamps);
+        chat_area.set_sender_colors(&defaults.sender_colors);
+        let input = conversation.input_area_mut();
+        input.set_strings(self.strings.clone());
+        if let Some((first, continuation)) = &defaults.input_prompt {
+            input.set_prompt(first, continuation);
+        }
+        if let Some(height) = defaults.input_height {
+            input.set_height(height);
+        }
+        conversation.members_mut().set_strings(self.strings.clone());
+        let header = conversation.header_mut();
+        header.set_strings(self.strings.clone());
+        header.set_typing_position(defaults.typing_position);
+        header.set_typing_timeout(defaults.typing_timeout);
+    }
+
+    /// Shows or hides the channel sidebar to the left of the chat.
+    ///
+    /// While shown, Tab moves focus between the input and the sidebar. In the sidebar,
+    /// Up/Down and typing navigate and filter, and Enter switches to the selected conversation.
+    pub fn set_show_channels(&mut self, show: bool) {
+        self.dirty |= self.show_channels != show;
+        self.show_channels = show;
+        if !show {
+            self.focus = Focus::Input;
+        }
+    }
+
+    /// Shows each sender's presence from the conversation's [`MemberList`] as a dot before
+    /// their name in the chat, and dims messages from offline members, see
+    /// [`ChatArea::set_presence`]. The chat follows changes to the member list as it is
+    /// drawn. Presence set with [`ChatArea::set_presence`] still wins over the member list's.
+    pub fn set_presence_in_chat(&mut self, show: bool) {
+        self.dirty = true;
+        self.presence_in_chat = show;
+        for i in 0..self.conversations.len() {
+            if show {
+                self.sync_presence(i, true);
+            } else if let Some(conversation) = self.conversations.get_mut(i) {
+                conversation.chat_area_mut().set_member_presence(HashMap::new());
+            }
+        }
+    }
+
+    /// Copies conversation `index`'s member presence into its chat, if enabled and the
+    /// member list changed since the last copy, or if `force`d.
+    fn sync_presence(&mut self, index: usize, force: bool) {
+        if !self.presence_in_chat {
+            return;
+        }
+        let Some(conversation) = self.conversations.get_mut(index) else {
+            return;
+        };
+        if !conversation.members_mut().take_changed() && !force {
+            return;
+        }
+        let presence = conversation.members().members().iter().map(|member| (member.name.clone(), member.presence)).collect();
+        conversation.chat_area_mut().set_member_presence(presence);
+    }
+
+    /// Shows or hides the active conversation's member list to the right of the chat.
+    pub fn set_show_members(&mut self, show: bool) {
+        self.dirty |= self.show_members != show;
+        self.show_members = show;
+    }
+
+    /// Shows or hides the active conversation's [`ChatHeader`] above the chat.
+    pub fn set_show_header(&mut self, show: bool) {
+        self.dirty |= self.show_header != show;
+        self.show_header = show;
+    }
+
+    /// Shows conversation `other` beside the active one, each pane with its own scroll
+    /// position. The active conversation stays on the left with focus, and keys, the input
+    /// and the rest of the app act on whichever pane has focus. Switching conversations
+    /// replaces the focused pane's. Returns `false` if `other` is out of range or active.
+    pub fn open_split(&mut self, other: usize) -> bool {
+        let active = self.conversations.active_index();
+        if other == active || other >= self.conversations.len() {
+            return false;
+        }
+        self.close_split();
+        self.dirty = true;
+        self.split = Some([active, other]);
+        self.split_focus = 0;
+        true
+    }
+
+    /// Goes back to showing only the active conversation.
+    pub fn close_split(&mut self) {
+        self.dirty = true;
+        for index in self.split.take().into_iter().flatten() {
+            if let Some(conversation) = self.conversations.get_mut(index) {
+                conversation.chat_area_mut().set_pane(None);
+            }
+        }
+    }
+
+    /// The conversations in the left and right panes while the view is split.
+    pub fn split(&self) -> Option<[usize; 2]> {
+        self.split
+    }
+
+    /// Moves the focus to the other pane of a split view, making its conversation active.
+    pub fn focus_other_pane(&mut self) {
+        self.sync_split();
+        if let Some(panes) = self.split {
+            self.dirty = true;
+            self.split_focus = 1 - self.split_focus;
+            self.conversations.select(panes[self.split_focus]);
+        }
+    }
+
+    /// Catches the split view up with the active conversation, which may have been changed
+    /// by a tab, the channel list or the host: it takes over the focused pane unless it is
+    /// already in the other one.
+    fn sync_split(&mut self) {
+        let active = self.conversations.active_index();
+        let Some(panes) = self.split.as_mut() else {
+            return;
+        };
+        if let Some(pane) = panes.iter().position(|&index| index == active) {
+            self.split_focus = pane;
+            return;
+        }
+        let left = std::mem::replace(&mut panes[self.split_focus], active);
+        if let Some(conversation) = self.conversations.get_mut(left) {
+            conversation.chat_area_mut().set_pane(None);
+        }
+    }
+
+    /// Shows or hides the status bar below the input.
+    pub fn set_show_status_bar(&mut self, show: bool) {
+        self.dirty |= self.status_bar.is_some() != show;
+        self.status_bar = show.then(|| {
+            self.status_bar.take().unwrap_or_else(|| {
+                let mut status_bar = StatusBar::new();
+                status_bar.set_strings(self.strings.clone());
+                status_bar.set_theme(self.settings.theme.theme());
+                status_bar
+            })
+        });
+    }
+
+    /// The status bar, if shown. Use it to set the connection status; the mode, key hints
+    /// and scroll position are kept up to date by the app.
+    pub fn status_bar_mut(&mut self) -> Option<&mut StatusBar> {
+        self.dirty = true;
+        self.status_bar.as_mut()
+    }
+
+    /// Shows a transient notification in the top-right corner of the chat for `ticks` calls to [`ChatApp::tick`].
+    pub fn notify(&mut self, level: ToastLevel, text: impl Into<String>, ticks: u32) {
+        self.dirty = true;
+        self.toasts.push(level, text, ticks);
+    }
+
+    /// Opens a modal confirmation dialog. While it is open it receives all key input and
+    /// the rest of the interface is dimmed. Once answered, the choice is available from
+    /// [`ChatApp::take_confirmation`] together with `tag`.
+    pub fn confirm(&mut self, tag: impl Into<String>, mut dialog: ConfirmDialog) {
+        self.dirty = true;
+        dialog.set_strings(self.strings.clone());
+        self.dialog = Some((tag.into(), dialog));
+    }
+
+    pub fn is_dialog_open(&self) -> bool {
+        self.dialog.is_some()
+    }
+
+    /// Returns the tag and choice of the most recently answered dialog, if not yet taken.
+    pub fn take_confirmation(&mut self) -> Option<(String, bool)> {
+        self.confirmation.take()
+    }
+
+    /// Adds a message to a conversation, counting it as unread if that conversation is not
+    /// active and emitting any notifications configured for it.
+    ///
+    /// The message first passes through the message filter and middleware, and the id of the
+    /// last message they produce is returned. Returns `None` if there is no such conversation
+    /// or the message was dropped.
+    pub fn add_message(&mut self, conversation: usize, msg: ChatMessage) -> Option<MessageId> {
+        self.dirty = true;
+        self.conversations.get(conversation)?;
+        let mut id = None;
+        for msg in self.process_message(conversation, msg) {
+            id = self.store_message(conversation, msg);
+        }
+        id
+    }
+
+    fn store_message(&mut self, conversation: usize, msg: ChatMessage) -> Option<MessageId> {
+        self.conversations.get(conversation)?;
+        self.alert(conversation, &msg);
+        self.save_message(conversation, &msg);
+        let id = self.conversations.add_message(conversation, msg)?;
+        self.message_added(conversation, id);
+        Some(id)
+    }
+
+    /// Starts a message in a conversation whose content arrives in pieces, such as a reply
+    /// being generated, see [`ChatArea::start_streaming`]. Extend it with
+    /// [`ChatApp::append_to_message`] and end it with [`ChatApp::finish_streaming`], which
+    /// saves it to the message store.
+    ///
+    /// The message filter sees the message as it starts, and may rewrite or reject it. It
+    /// sees it again with every chunk, see [`ChatApp::append_to_message`]; middleware does
+    /// not run. Returns `None` if there is no such conversation or the filter rejected the
+    /// message.
+    pub fn start_streaming(&mut self, conversation: usize, msg: ChatMessage) -> Option<MessageId> {
+        self.dirty = true;
+        self.conversations.get(conversation)?;
+        let mut shown = msg.clone();
+        if let Some(filter) = self.message_filter.as_mut()
+            && !filter(conversation, &mut shown)
+        {
+            return None;
+        }
+        let id = self.conversations.add_message(conversation, shown)?;
+        let chat_area = self.conversations.get_mut(conversation)?.chat_area_mut();
+        chat_area.streaming.push(id);
+        chat_area.streamed.insert(id, msg);
+        self.message_added(conversation, id);
+        Some(id)
+    }
+
+    /// Appends `chunk` to a streaming message, see [`ChatArea::append_to_message`]. Returns
+    /// `false` if there is no such message.
+    ///
+    /// The message filter runs on the content streamed so far before it is shown, so a
+    /// redaction covers text split across chunks. While the filter rejects it, the message
+    /// is shown without content.
+    pub fn append_to_message(&mut self, conversation: usize, id: MessageId, chunk: &str) -> bool {
+        self.dirty = true;
+        let Some(chat_area) = self.conversations.get_mut(conversation).map(Conversation::chat_area_mut) else {
+            return false;
+        };
+        let Some(index) = chat_area.index_of(id) else {
+            return false;
+        };
+        let mut streamed = chat_area.streamed.remove(&id).unwrap_or_else(|| chat_area.messages[index].clone());
+        streamed.content.push_str(chunk);
+        let mut msg = streamed.clone();
+        if let Some(filter) = self.message_filter.as_mut()
+            && !filter(conversation, &mut msg)
+        {
+            msg.content.clear();
+        }
+        if chat_area.is_streaming(id) {
+            chat_area.streamed.insert(id, streamed);
+        }
+        chat_area.messages[index] = msg;
+        chat_area.reindex(index);
+        true
+    }
+
+    /// Completes a streaming message: it is announced, notified like a message from
+    /// [`ChatApp::add_message`] and saved to the message store. Returns `false` if it was not
+    /// streaming.
+    ///
+    /// The message filter runs again on the complete message as it was streamed. A message
+    /// it rejects now is removed from the chat and is not saved, announced or notified.
+    pub fn finish_streaming(&mut self, conversation: usize, id: MessageId) -> bool {
+        self.dirty = true;
+        let Some(chat_area) = self.conversations.get_mut(conversation).map(Conversation::chat_area_mut) else {
+            return false;
+        };
+        let streamed = chat_area.streamed.remove(&id);
+        if !chat_area.finish_streaming(id) {
+            return false;
+        }
+        let Some(index) = chat_area.index_of(id) else {
+            return false;
+        };
+        let mut msg = streamed.unwrap_or_else(|| chat_area.messages[index].clone());
+        if let Some(filter) = self.message_filter.as_mut() {
+            if !filter(conversation, &mut msg) {
+                chat_area.remove_message(id);
+                return true;
+            }
+            chat_area.messages[index] = msg.clone();
+            chat_area.reindex(index);
+        }
+        self.alert(conversation, &msg);
+        self.save_message(conversation, &msg);
+        true
+    }
+
+    /// Announces a new message and, if the user is away from it, runs the notifications
+    /// its level calls for.
+    fn alert(&mut self, conversation: usize, msg: &ChatMessage) {
+        let msg = &*self.without_spoilers(conversation, msg);
+        let active = conversation == self.conversations.active_index();
+        let away = !self.terminal_focused
+            || !active
+            || !self.conversations.get(conversation).is_some_and(|c| c.chat_area().is_following());
+        self.announce(msg);
+        if away {
+            if active {
+                self.unseen += 1;
+            }
+            let actions = self.notify_actions[self.notify_rules.classify(msg) as usize];
+            self.pending_bell |= self.bell_on_message || actions.bell;
+            if actions.toast {
+                let content = msg.content.lines().next().unwrap_or_default();
+                let text = strings::fill(&self.strings.notify_toast, &[("sender", &msg.sender), ("content", &content)]);
+                self.notify(ToastLevel::Info, text, 20);
+            }
+            #[cfg(feature = "notifications")]
+            if actions.desktop {
+                self.notifier.show(msg);
+            } else {
+                self.notifier.notify(msg);
+            }
+        }
+    }
+
+    /// Appends a message to the message store, if there is one.
+    fn save_message(&mut self, conversation: usize, msg: &ChatMessage) {
+        let (Some(store), Some(target)) = (self.store.as_mut(), self.conversations.get_mut(conversation)) else {
+            return;
+        };
+        match store.append(target.name(), msg) {
+            // History loaded later continues from before the first message saved now.
+            Ok(key) => {
+                target.history.before.get_or_insert(key);
+            }
+            Err(error) => self.history_failed(&error),
+        }
+    }
+
+    /// Loads the page of history before the active conversation's oldest message once its
+    /// chat is scrolled to the top.
+    fn load_history(&mut self) {
+        let conversation = self.conversations.active_mut();
+        let Some(store) = self.store.as_ref() else {
+            return;
+        };
+        if conversation.history.exhausted || !conversation.chat_area().is_at_top() {
+            return;
+        }
+        match store.range(conversation.name(), conversation.history.before, HISTORY_PAGE) {
+            Ok(page) => {
+                conversation.history.exhausted = page.len() < HISTORY_PAGE;
+                if let Some((key, _)) = page.first() {
+                    conversation.history.before = Some(*key);
+                }
+                conversation.chat_area_mut().prepend_messages(page.into_iter().map(|(_, msg)| msg).collect());
+            }
+            Err(error) => {
+                conversation.history.exhausted = true;
+                self.history_failed(&error);
+            }
+        }
+    }
+
+    fn history_failed(&mut self, error: &std::io::Error) {
+        let text = strings::fill(&self.strings.history_error, &[("error", error)]);
+        self.notify(ToastLevel::Error, text, 20);
+    }
+
+    fn message_added(&mut self, conversation: usize, id: MessageId) {
+        if let Some(handler) = self.on_message_added.as_mut()
+            && let Some(msg) = self.conversations.get(conversation).and_then(|c| c.chat_area().message(id))
+        {
+            handler(conversation, id, msg);
+        }
+    }
+
+    /// Runs the scroll callbacks if the active conversation's view started or stopped
+    /// following the newest messages since the last check. Switching conversations does not count.
+    fn check_following(&mut self) {
+        let current = (self.conversations.active_index(), self.conversations.active().chat_area().is_following());
+        let previous = std::mem::replace(&mut self.followed, current);
+        if previous.0 != current.0 || previous.1 == current.1 {
+            return;
+        }
+        let handler = if current.1 { &mut self.on_returned_to_bottom } else { &mut self.on_scrolled_away };
+        if let Some(handler) = handler.as_mut() {
+            handler(current.0);
+        }
+    }
+
+    /// Turns off motion: animated scrolling jumps straight to its target and the cursor stops
+    /// blinking. Equivalent to setting [`Settings::reduced_motion`] via [`ChatApp::apply_settings`].
+    pub fn set_reduced_motion(&mut self, enabled: bool) {
+        self.dirty = true;
+        self.apply_settings(Settings { reduced_motion: enabled, ..self.settings });
+    }
+
+    /// Captures each conversation's messages with their ids and pins, scroll position and
+    /// input draft, for the host to persist and later pass to [`ChatApp::restore_state`].
+    pub fn save_state(&self) -> SessionState {
+        let conversations = self
+            .conversations
+            .conversations()
+            .iter()
+            .map(|c| ConversationState {
+                name: c.name().to_string(),
+                messages: c.chat_area().messages.clone(),
+                ids: c.chat_area().ids.clone(),
+                pinned: c.chat_area().pinned.clone(),
+                offset: c.chat_area().offset,
+                following: c.chat_area().auto_scroll,
+                draft: c.input_area().buffer.to_string(),
+                cursor: c.input_area().cursor,
+            })
+            .collect();
+        SessionState {
+            conversations,
+            active: self.conversations.active_index(),
+        }
+    }
+
+    /// Restores a session saved with [`ChatApp::save_state`].
+    ///
+    /// Saved conversations replace the contents of existing ones by position, keeping their
+    /// members and header; conversations beyond the existing ones are added.
+    pub fn restore_state(&mut self, state: SessionState) {
+        self.dirty = true;
+        for (i, saved) in state.conversations.into_iter().enumerate() {
+            let index = if i < self.conversations.len() {
+                i
+            } else {
+                self.add_conversation(saved.name.clone())
+            };
+            let conversation = self.conversations.get_mut(index).expect("index in range");
+            conversation.set_name(saved.name);
+
+            let chat_area = conversation.chat_area_mut();
+            chat_area.restore_messages(saved.messages, saved.ids, &saved.pinned);
+            chat_area.mark_all_read();
+            chat_area.offset = saved.offset;
+            chat_area.auto_scroll = saved.following;
+
+            let input = conversation.input_area_mut();
+            input.buffer = Rope::from_str(&saved.draft);
+            input.edited();
+            let mut cursor = saved.cursor.min(input.buffer.len_bytes());
+            while !saved.draft.is_char_boundary(cursor) {
+                cursor -= 1;
+            }
+            input.cursor = cursor;
+            input.offset = (0, 0);
+            input.preedit.clear();
+        }
+        self.conversations.select(state.active);
+    }
+
+    /// Adds the conversations in a chat export, such as OpenAI's `conversations.json`, as
+    /// new conversations, see [`import_chat_export`]. Each is named by its title, or
+    /// [`Strings::imported_title`] without one. Each message passes through the message
+    /// filter and middleware, like one from [`ChatApp::add_message`], but is not saved,
+    /// announced or notified. Returns the new conversations' indices.
+    pub fn import_conversations(&mut self, json: &str) -> std::io::Result<Vec<usize>> {
+        let imported = import_chat_export(json)?;
+        self.dirty = true;
+        let mut indices = Vec::new();
+        for conversation in imported {
+            let index = self.add_conversation(conversation.title.unwrap_or_else(|| self.strings.imported_title.clone()));
+            for msg in conversation.messages {
+                for msg in self.process_message(index, msg) {
+                    self.conversations.get_mut(index).expect("index in range").chat_area_mut().add_message(msg);
+                }
+            }
+            self.conversations.get_mut(index).expect("index in range").chat_area_mut().mark_all_read();
+            indices.push(index);
+        }
+        Ok(indices)
+    }
+
+    /// Switches the accessibility mode on or off: the high-contrast theme with ASCII-only
+    /// borders and indicators. Use [`ChatApp::set_announcer`] to also forward new messages
+    /// to a screen reader.
+    pub fn set_accessible(&mut self, enabled: bool) {
+        self.dirty = true;
+        let theme = if enabled { ThemePreset::HighContrast } else { ThemePreset::Default };
+        self.apply_settings(Settings { theme, ..self.settings });
+    }
+
+    /// Sets a callback that receives every new message as plain text lines (`"sender: text"`,
+    /// then any further lines of the message), in arrival order, so the host can forward
+    /// them to a screen reader or braille display.
+    pub fn set_announcer(&mut self, announcer: impl FnMut(&str) + 'static) {
+        self.announcer = Some(Box::new(announcer));
+    }
+
+    /// A message with its spoilers hidden if the conversation hides them, to pass it on
+    /// outside the chat.
+    fn without_spoilers<'a>(&self, conversation: usize, msg: &'a ChatMessage) -> Cow<'a, ChatMessage> {
+        let glyph = self.conversations.get(conversation).and_then(|c| c.chat_area().spoiler_glyph());
+        spoiler::hide_message(msg, glyph)
+    }
+
+    fn announce(&mut self, msg: &ChatMessage) {
+        let Some(announcer) = self.announcer.as_mut() else {
+            return;
+        };
+        let mut lines = msg.content.lines();
+        announcer(&format!("{}: {}", msg.sender, lines.next().unwrap_or_default()));
+        for line in lines {
+            announcer(line);
+        }
+    }
+
+    /// Rings the terminal bell when a message arrives while the user is scrolled back,
+    /// unfocused, or looking at another conversation.
+    pub fn set_bell_on_message(&mut self, enabled: bool) {
+        self.bell_on_message = enabled;
+    }
+
+    /// Classifies messages added with [`ChatApp::add_message`] by `rules`, running the
+    /// [`NotifyActions`] of their level, and highlights matches in every conversation, see
+    /// [`ChatArea::set_notify_rules`].
+    pub fn set_notify_rules(&mut self, rules: NotifyRules) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_notify_rules(rules.clone());
+        }
+        self.notify_rules = rules;
+    }
+
+    /// Numbers the fenced code blocks in every conversation, see
+    /// [`ChatArea::set_label_code_blocks`], so they can be copied by number with
+    /// [`ChatApp::copy_code_block`].
+    pub fn set_label_code_blocks(&mut self, enabled: bool) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_label_code_blocks(enabled);
+        }
+        self.defaults.label_code_blocks = enabled;
+    }
+
+    /// Copies fenced code block `n`, counting from 1, to the clipboard. The block is taken
+    /// from the selected message, or with none selected from the newest message with a code
+    /// block. A toast reports the result. Returns whether the block was copied.
+    ///
+    /// With messages selected (Ctrl+↑), keys 1-9 copy that block; otherwise
+    /// [`Action::CopyCodeBlock`] (Ctrl+Y copies the first block) does.
+    pub fn copy_code_block(&mut self, n: usize) -> bool {
+        self.dirty = true;
+        let chat_area = self.chat_area();
+        let blocks = match chat_area.selected_message() {
+            Some(id) => chat_area.code_blocks(id),
+            None => chat_area
+                .messages
+                .iter()
+                .rev()
+                .map(|msg| markdown::code_blocks(&msg.content))
+                .find(|blocks| !blocks.is_empty())
+                .unwrap_or_default(),
+        };
+        let Some(code) = n.checked_sub(1).and_then(|i| blocks.into_iter().nth(i)) else {
+            let text = strings::fill(&self.strings.code_missing, &[("n", &n)]);
+            self.notify(ToastLevel::Warn, text, 20);
+            return false;
+        };
+        let copied = strings::fill(&self.strings.code_copied, &[("n", &n)]);
+        self.copy_to_clipboard(code, copied)
+    }
+
+    /// Copies the text selected with the mouse in the active chat to the clipboard, see
+    /// [`ChatArea::selected_text`]. Returns whether there was a selection and it was copied.
+    pub fn copy_selection(&mut self) -> bool {
+        let Some(text) = self.chat_area().selected_text().filter(|text| !text.is_empty()) else {
+            return false;
+        };
+        let copied = strings::fill(&self.strings.selection_copied, &[("count", &text.chars().count())]);
+        self.copy_to_clipboard(text, copied)
+    }
+
+    /// Whether releasing the mouse button after dragging over the chat copies the selected
+    /// text. On by default; otherwise [`Action::CopySelection`] (Alt+C) copies it.
+    pub fn set_copy_on_select(&mut self, enabled: bool) {
+        self.copy_on_select = enabled;
+    }
+
+    /// Puts `text` on the clipboard and shows `toast`, or an error toast if that fails.
+    fn copy_to_clipboard(&mut self, text: String, toast: String) -> bool {
+        self.dirty = true;
+        match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+            Ok(()) => {
+                self.notify(ToastLevel::Info, toast, 20);
+                true
+            }
+            Err(error) => {
+                let text = strings::fill(&self.strings.clipboard_error, &[("error", &error)]);
+                self.notify(ToastLevel::Error, text, 20);
+                false
+            }
+        }
+    }
+
+    /// Sets what happens when a message at `level` arrives while the user is away. By
+    /// default, [`NotifyLevel::Low`] shows a toast, and [`NotifyLevel::High`] also rings the
+    /// bell and sends a desktop notification.
+    pub fn set_notify_actions(&mut self, level: NotifyLevel, actions: NotifyActions) {
+        self.notify_actions[level as usize] = actions;
+    }
+
+    /// Sets the terminal window title. While there are unread messages it is prefixed with
+    /// their count, e.g. `"(3) my-chat"`. `None` leaves the title alone.
+    pub fn set_window_title(&mut self, title: Option<String>) {
+        self.window_title = title;
+    }
+
+    /// Number of unread messages: those that arrived in the active conversation while the user
+    /// was away from the bottom, plus the unread counts of the other conversations.
+    pub fn unread_count(&self) -> usize {
+        let others: usize = self.conversations.conversations().iter().map(Conversation::unread).sum();
+        self.unseen + others
+    }
+
+    /// The window title including the unread counter, if a title is set.
+    pub fn window_title(&self) -> Option<String> {
+        let title = self.window_title.as_ref()?;
+        Some(match self.unread_count() {
+            0 => title.clone(),
+            n => format!("({n}) {title}"),
+        })
+    }
+
+    /// Writes pending terminal side effects to `out`: the bell, title updates, and a steady
+    /// (non-blinking) cursor while [`Settings::reduced_motion`] is on.
+    ///
+    /// Call this after drawing, with the same writer the terminal backend uses.
+    pub fn write_terminal_effects(&mut self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        if std::mem::take(&mut self.pending_bell) {
+            out.write_all(b"\x07")?;
+        }
+        let title = self.window_title();
+        if title != self.written_title {
+            if let Some(title) = &title {
+                crossterm::execute!(out, crossterm::terminal::SetTitle(title))?;
+            }
+            self.written_title = title;
+        }
+        if self.settings.reduced_motion != self.steady_cursor {
+            use crossterm::cursor::SetCursorStyle;
+            let style = if self.settings.reduced_motion { SetCursorStyle::SteadyBlock } else { SetCursorStyle::DefaultUserShape };
+            crossterm::execute!(out, style)?;
+            self.steady_cursor = self.settings.reduced_motion;
+        }
+        out.flush()
+    }
+
+    /// Reports terminal focus changes (crossterm's `FocusGained`/`FocusLost` events).
+    pub fn on_focus(&mut self, focused: bool) {
+        self.dirty = true;
+        self.terminal_focused = focused;
+    }
+
+    /// Desktop notifications are sent for messages added with [`ChatApp::add_message`] while
+    /// the terminal is unfocused or the user is not looking at the newest messages.
+    #[cfg(feature = "notifications")]
+    pub fn notifier_mut(&mut self) -> &mut DesktopNotifier {
+        &mut self.notifier
+    }
+
+    /// The current settings, for the host to persist.
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Applies settings to every conversation. Changing the keymap preset switches the keymap,
+    /// keeping any keys rebound through [`ChatApp::keymap_mut`].
+    pub fn apply_settings(&mut self, settings: Settings) {
+        self.dirty = true;
+        if settings.keymap != self.settings.keymap {
+            self.keymap = self.keymap.rebased(&self.settings.keymap.keymap(), settings.keymap.keymap());
+        }
+        self.settings = settings;
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            Self::apply_settings_to(conversation, settings, self.smooth_scroll);
+        }
+        self.conversations.set_theme(settings.theme.theme());
+        self.channel_list.set_theme(settings.theme.theme());
+        if let Some(status_bar) = &mut self.status_bar {
+            status_bar.set_theme(settings.theme.theme());
+        }
+    }
+
+    fn apply_settings_to(conversation: &mut Conversation, settings: Settings, smooth_scroll: bool) {
+        let theme = settings.theme.theme();
+        let chat_area = conversation.chat_area_mut();
+        chat_area.set_density(settings.density);
+        chat_area.set_show_timestamps(settings.show_timestamps);
+        chat_area.set_show_metadata(settings.show_metadata);
+        chat_area.set_theme(theme);
+        chat_area.set_smooth_scroll(smooth_scroll && !settings.reduced_motion);
+        conversation.input_area_mut().set_theme(theme);
+        conversation.members_mut().set_theme(theme);
+    }
+
+    /// Sets how the markdown preview of the input is shown.
+    pub fn set_preview_mode(&mut self, mode: PreviewMode) {
+        self.dirty |= self.preview != mode;
+        self.preview = mode;
+    }
+
+    pub fn preview_mode(&self) -> PreviewMode {
+        self.preview
+    }
+
+    /// Opens the settings overlay. Changes are applied live as the user edits them.
+    pub fn open_settings(&mut self) {
+        self.dirty = true;
+        let mut panel = SettingsPanel::new(self.settings);
+        panel.set_strings(self.strings.clone());
+        self.settings_panel = Some(panel);
+    }
+
+    /// Opens a popup to jump to a date and time in the active conversation, starting from
+    /// the time of the topmost visible message.
+    pub fn open_date_picker(&mut self) {
+        self.dirty = true;
+        let chat_area = self.conversations.active().chat_area();
+        let time = chat_area
+            .message_lines
+            .get(chat_area.offset)
+            .and_then(|(i, _)| chat_area.messages[*i].timestamp)
+            .unwrap_or_else(SystemTime::now);
+        let mut picker = DatePicker::new(time);
+        picker.set_strings(self.strings.clone());
+        #[cfg(feature = "chrono")]
+        picker.set_time_format(self.time_format.clone());
+        self.date_picker = Some(picker);
+    }
+
+    /// Opens a large popup showing the selected message, or else the newest one, in full,
+    /// see [`MessageDetail`]. The popup follows the message as it changes, such as while a
+    /// reply streams in. Returns whether there was a message to show.
+    pub fn open_message_detail(&mut self) -> bool {
+        self.dirty = true;
+        let chat_area = self.chat_area();
+        let Some(id) = chat_area.selected_message().or(chat_area.ids.last().copied()) else {
+            return false;
+        };
+        let Some(msg) = chat_area.message(id) else {
+            return false;
+        };
+        let mut detail = MessageDetail::new(msg.clone());
+        detail.set_strings(self.strings.clone());
+        detail.set_theme(self.settings.theme.theme());
+        #[cfg(feature = "chrono")]
+        detail.set_time_format(self.time_format.clone());
+        self.message_detail = Some((self.conversations.active_index(), id, detail));
+        true
+    }
+
+    /// Whether the message detail popup is open.
+    pub fn is_message_detail_open(&self) -> bool {
+        self.message_detail.is_some()
+    }
+
+    /// Sets how timestamps are shown in every conversation and the jump-to-date popup, see
+    /// [`ChatArea::set_time_format`].
+    #[cfg(feature = "chrono")]
+    pub fn set_time_format(&mut self, time_format: TimeFormat) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_time_format(time_format.clone());
+        }
+        self.time_format = time_format;
+    }
+
+    pub fn strings(&self) -> &Strings {
+        &self.strings
+    }
+
+    /// Replaces the interface text of the app and every widget it owns, e.g. to localize it.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.dirty = true;
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_strings(strings.clone());
+            conversation.input_area_mut().set_strings(strings.clone());
+            conversation.members_mut().set_strings(strings.clone());
+            conversation.header_mut().set_strings(strings.clone());
+        }
+        self.conversations.set_strings(strings.clone());
+        self.channel_list.set_strings(strings.clone());
+        self.toasts.set_strings(strings.clone());
+        if let Some(status_bar) = &mut self.status_bar {
+            status_bar.set_strings(strings.clone());
+        }
+        if let Some(panel) = &mut self.settings_panel {
+            panel.set_strings(strings.clone());
+        }
+        if let Some(picker) = &mut self.date_picker {
+            picker.set_strings(strings.clone());
+        }
+        if let Some((_, _, detail)) = &mut self.message_detail {
+            detail.set_strings(strings.clone());
+        }
+        if let Some((_, dialog)) = &mut self.dialog {
+            dialog.set_strings(strings.clone());
+        }
+        self.strings = strings;
+    }
+
+    pub fn toasts_mut(&mut self) -> &mut Toasts {
+        self.dirty = true;
+        &mut self.toasts
+    }
+
+    /// Advances time-based state such as toast expiry and submit cooldowns. Call this
+    /// periodically from the host loop. Every conversation's chat is ticked, so both panes
+    /// of a split view keep scrolling smoothly and relative timestamps stay current.
+    pub fn tick(&mut self) {
+        self.dirty |= self.toasts.tick();
+        let (now, clock) = (Instant::now(), SystemTime::now());
+        for index in 0..self.conversations.len() {
+            if let Some(conversation) = self.conversations.get_mut(index) {
+                self.dirty |= conversation.chat_area_mut().tick(clock);
+                conversation.input_area_mut().tick();
+                self.dirty |= conversation.header_mut().tick(now);
+            }
+        }
+    }
+
+    /// Sets the users typing in the active conversation, see [`ChatHeader::set_typing`].
+    /// An empty slice hides the indicator.
+    pub fn set_typing_users(&mut self, users: &[&str]) {
+        self.dirty = true;
+        self.conversations.active_mut().header_mut().set_typing(users);
+    }
+
+    /// Shows the typing indicator of every conversation in its header or pinned under the
+    /// chat, see [`ChatHeader::set_typing_position`]. Under the chat it shows even while the
+    /// header is hidden.
+    pub fn set_typing_position(&mut self, position: TypingPosition) {
+        self.dirty = true;
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.header_mut().set_typing_position(position);
+        }
+        self.defaults.typing_position = position;
+    }
+
+    /// Clears the typing users of every conversation once `timeout` passes without an
+    /// update, checked on [`ChatApp::tick`], see [`ChatHeader::set_typing_timeout`].
+    pub fn set_typing_timeout(&mut self, timeout: Option<Duration>) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.header_mut().set_typing_timeout(timeout);
+        }
+        self.defaults.typing_timeout = timeout;
+    }
+
+    /// Dims the messages the filter rejects in every conversation instead of hiding them,
+    /// see [`ChatArea::set_dim_unmatched`].
+    pub fn set_dim_unmatched(&mut self, dim: bool) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_dim_unmatched(dim);
+        }
+        self.defaults.dim_unmatched = dim;
+    }
+
+    /// Sets the style of the selected message in every conversation, see
+    /// [`ChatArea::set_selection_style`].
+    pub fn set_selection_style(&mut self, style: Style) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_selection_style(style);
+        }
+        self.defaults.selection_style = Some(style);
+    }
+
+    /// Shows the scroll position after the chat title in every conversation, see
+    /// [`ChatArea::set_show_scroll_position`].
+    pub fn set_show_scroll_position(&mut self, show: bool) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_show_scroll_position(show);
+        }
+        self.defaults.show_scroll_position = show;
+    }
+
+    /// Sets the input prompts of every conversation, see [`InputArea::set_prompt`].
+    pub fn set_input_prompt(&mut self, first: &str, continuation: &str) {
+        self.dirty = true;
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.input_area_mut().set_prompt(first, continuation);
+        }
+        self.defaults.input_prompt = Some((first.to_string(), continuation.to_string()));
+    }
+
+    /// Sets the input height of every conversation, see [`InputArea::set_height`].
+    pub fn set_input_height(&mut self, height: InputHeight) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.input_area_mut().set_height(height);
+        }
+        self.defaults.input_height = Some(height);
+    }
+
+    /// Shows relative timestamps in every conversation, see [`ChatArea::set_relative_timestamps`].
+    /// [`ChatApp::tick`] keeps them current.
+    pub fn set_relative_timestamps(&mut self, max_age: Option<Duration>) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_relative_timestamps(max_age);
+        }
+        self.defaults.relative_timestamps = max_age;
+    }
+
+    /// Colors senders by name in every conversation, see [`ChatArea::set_sender_colors`].
+    pub fn set_sender_colors(&mut self, palette: &[Color]) {
+        for i in 0..self.conversations.len() {
+            let conversation = self.conversations.get_mut(i).expect("index in range");
+            conversation.chat_area_mut().set_sender_colors(palette);
+        }
+        self.defaults.sender_colors = palette.to_vec();
+    }
+
+    /// Gives the input a fixed percentage of the height it shares with the chat, as if the
+    /// user had dragged the splitter between them. `None` sizes the input to its text again.
+    pub fn set_input_split(&mut self, percent: Option<u16>) {
+        self.dirty = true;
+        self.input_split = percent.map(|percent| percent.clamp(Self::MIN_SPLIT, Self::MAX_SPLIT));
+    }
+
+    /// The splitter position set by the user or [`ChatApp::set_input_split`], for persisting.
+    pub fn input_split(&self) -> Option<u16> {
+        self.input_split
+    }
+
+    const MIN_SPLIT: u16 = 5;
+    const MAX_SPLIT: u16 = 90;
+    const SPLIT_STEP: u16 = 5;
+
+    /// Moves the splitter by `delta` percent, starting from the input's current share.
+    fn move_split(&mut self, delta: i16) {
+        let current = self.input_split.unwrap_or_else(|| {
+            let share = (u32::from(self.input_rect.height) * 100).checked_div(u32::from(self.split_rect.height)).unwrap_or(0);
+            u16::try_from(share).unwrap_or(Self::MAX_SPLIT)
+        });
+        self.set_input_split(Some(current.saturating_add_signed(delta)));
+    }
+
+    /// Sets a callback run when the user cancels the active conversation's streaming
+    /// messages (Esc by default), with the conversation index and message id, so the host
+    /// can abort the generation. The message is already marked as cancelled.
+    pub fn set_on_cancel_streaming(&mut self, handler: impl FnMut(usize, MessageId) + 'static) {
+        self.on_cancel_streaming = Some(Box::new(handler));
+    }
+
+    /// Sets a callback that plays voice messages externally, run with the conversation index,
+    /// message id and [`MessageKind::Voice`] source when the user presses Enter on a selected
+    /// voice message.
+    pub fn set_on_play_voice(&mut self, handler: impl FnMut(usize, MessageId, &str) + 'static) {
+        self.on_play_voice = Some(Box::new(handler));
+    }
+
+    /// Asks the host to play voice message `id` of the active conversation. Returns `false`
+    /// if it is not a voice message or no callback is set with [`ChatApp::set_on_play_voice`].
+    pub fn play_voice(&mut self, id: MessageId) -> bool {
+        let conversation = self.conversations.active_index();
+        let Some(MessageKind::Voice { source, .. }) = self.chat_area().message(id).map(|msg| &msg.kind) else {
+            return false;
+        };
+        let source = source.clone();
+        match self.on_play_voice.as_mut() {
+            Some(handler) => {
+                handler(conversation, id, &source);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets a callback run after a message is stored in a conversation, whether the user sent
+    /// it or it came through [`ChatApp::add_message`], with the conversation index, its id
+    /// and the message as stored. Useful for sounds or analytics.
+    pub fn set_on_message_added(&mut self, handler: impl FnMut(usize, MessageId, &ChatMessage) + 'static) {
+        self.on_message_added = Some(Box::new(handler));
+    }
+
+    /// Sets a callback run when the user scrolls the active conversation back from the
+    /// newest messages, with its index. Checked when the app renders.
+    pub fn set_on_scrolled_away_from_bottom(&mut self, handler: impl FnMut(usize) + 'static) {
+        self.on_scrolled_away = Some(Box::new(handler));
+    }
+
+    /// Sets a callback run when the active conversation's view reaches the newest messages
+    /// again, with its index, for example to move a read marker. Checked when the app renders.
+    pub fn set_on_returned_to_bottom(&mut self, handler: impl FnMut(usize) + 'static) {
+        self.on_returned_to_bottom = Some(Box::new(handler));
+    }
+
+    /// Sets a callback run when the user sends a message, with the conversation index and
+    /// the content as stored, after the message filter and middleware. Not run for messages
+    /// they dropped.
+    pub fn set_on_submit(&mut self, handler: impl FnMut(usize, &str) + 'static) {
+        self.on_submit = Some(Box::new(handler));
+    }
+
+    /// Sets a callback run on every message before it is stored, whether the user sent it or
+    /// it came through [`ChatApp::add_message`], with the conversation index. It can rewrite
+    /// the message, for example to redact API keys, or reject it by returning `false`.
+    ///
+    /// Messages streamed through [`ChatApp::start_streaming`] are filtered as they start, as
+    /// each chunk arrives and once finished, and conversations imported with
+    /// [`ChatApp::import_conversations`] message by message. Messages added directly to a
+    /// [`ChatArea`], such as through [`ChatApp::chat_area_mut`], are not filtered.
+    pub fn set_message_filter(&mut self, filter: impl FnMut(usize, &mut ChatMessage) -> bool + 'static) {
+        self.message_filter = Some(Box::new(filter));
+    }
+
+    pub fn clear_message_filter(&mut self) {
+        self.message_filter = None;
+    }
+
+    /// Appends a stage to the pipeline messages pass through before they are stored, after
+    /// the message filter and any earlier stages. Like the filter, it applies to messages the
+    /// user sends and to those added with [`ChatApp::add_message`].
+    ///
+    /// Stages can annotate, rewrite, split or drop messages, so processing such as extracting
+    /// code blocks or detecting links can be kept out of rendering.
+    pub fn add_middleware(&mut self, stage: impl MessageMiddleware + 'static) {
+        self.middleware.push(Box::new(stage));
+    }
+
+    pub fn clear_middleware(&mut self) {
+        self.middleware.clear();
+    }
+
+    /// Saves every message added through the app to `store`, under its conversation's name,
+    /// and loads each conversation's earlier history from it a page at a time as the user
+    /// scrolls to the top of the chat.
+    ///
+    /// Messages are saved as [`ChatApp::add_message`] adds them, as the user sends them and
+    /// as [`ChatApp::finish_streaming`] completes them. Messages already in the chats are not
+    /// saved, nor are messages added directly to a [`ChatArea`], such as through
+    /// [`ChatApp::chat_area_mut`]. A renamed conversation is saved under its new name.
+    pub fn set_message_store(&mut self, store: impl MessageStore + 'static) {
+        self.dirty = true;
+        self.store = Some(Box::new(store));
+        for i in 0..self.conversations.len() {
+            self.conversations.get_mut(i).expect("index in range").history = Default::default();
+        }
+    }
+
+    pub fn clear_message_store(&mut self) {
+        self.store = None;
+    }
+
+    /// Queues the messages the user sends for [`ChatApp::take_outgoing`].
+    /// [`ChatApp::connect_backend`] turns this on.
+    pub fn set_outbox(&mut self, enabled: bool) {
+        self.outbox = enabled.then(|| self.outbox.take().unwrap_or_default());
+    }
+
+    /// Takes the messages the user sent since the last call, with their conversation names,
+    /// oldest first.
+    pub fn take_outgoing(&mut self) -> Vec<(String, ChatMessage)> {
+        self.outbox.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Answers every message the user sends with a canned reply from "AI", for demos and
+    /// trying the widgets out without a backend. Off by default, and ignored while the
+    /// outbox is on.
+    pub fn set_simulated_replies(&mut self, enabled: bool) {
+        self.simulated_replies = enabled;
+    }
+
+    /// Connects `backend` and shows the outcome in the status bar, then queues the messages
+    /// the user sends for [`ChatApp::send_outgoing`].
+    pub async fn connect_backend(&mut self, backend: &mut impl ChatBackend) -> std::io::Result<()> {
+        self.set_outbox(true);
+        let result = backend.connect().await;
+        let status = if result.is_ok() { ConnectionStatus::Connected } else { ConnectionStatus::Disconnected };
+        self.apply_backend_event(BackendEvent::Connection(status));
+        result
+    }
+
+    /// Sends the queued messages the user wrote through `backend`, oldest first. If sending
+    /// fails, the unsent messages stay queued for the next call.
+    pub async fn send_outgoing(&mut self, backend: &mut impl ChatBackend) -> std::io::Result<()> {
+        let mut queued = self.take_outgoing().into_iter();
+        while let Some((conversation, msg)) = queued.next() {
+            if let Err(error) = backend.send(&conversation, &msg).await {
+                let outbox = self.outbox.get_or_insert_with(Vec::new);
+                outbox.splice(..0, std::iter::once((conversation, msg)).chain(queued));
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies an event from a [`ChatBackend`] to the widgets. Messages go through
+    /// [`ChatApp::add_message`], and a conversation that does not exist yet is added.
+    /// Connection changes are shown in the status bar, if it is shown.
+    pub fn apply_backend_event(&mut self, event: BackendEvent) {
+        self.dirty = true;
+        match event {
+            BackendEvent::Message { conversation, message } => {
+                let index = self.conversation_named(&conversation);
+                self.add_message(index, message);
+            }
+            BackendEvent::Typing { conversation, users } => {
+                let index = self.conversation_named(&conversation);
+                let users: Vec<&str> = users.iter().map(String::as_str).collect();
+                self.conversations.get_mut(index).expect("index in range").header_mut().set_typing(&users);
+            }
+            BackendEvent::Presence { user, presence } => {
+                for i in 0..self.conversations.len() {
+                    let conversation = self.conversations.get_mut(i).expect("index in range");
+                    conversation.members_mut().set_presence(&user, presence);
+                }
+            }
+            BackendEvent::Connection(status) => {
+                if let Some(status_bar) = self.status_bar.as_mut() {
+                    status_bar.set_connection(status);
+                }
+            }
+        }
+    }
+
+    /// The index of the conversation with this name, added if there is none.
+    fn conversation_named(&mut self, name: &str) -> usize {
+        match self.conversations.conversations().iter().position(|c| c.name() == name) {
+            Some(index) => index,
+            None => self.add_conversation(name),
+        }
+    }
+
+    /// Up to `limit` messages of the active conversation's stored history whose sender or
+    /// content contains `query`, newest first, including those not loaded into the chat.
+    /// Empty without a message store.
+    pub fn search_history(&self, query: &str, limit: usize) -> std::io::Result<Vec<ChatMessage>> {
+        let Some(store) = &self.store else {
+            return Ok(Vec::new());
+        };
+        let hits = store.search(self.conversations.active().name(), query, limit)?;
+        Ok(hits.into_iter().map(|(_, msg)| msg).collect())
+    }
+
+    /// Runs the message filter and then the middleware, returning the messages to store.
+    fn process_message(&mut self, conversation: usize, mut msg: ChatMessage) -> Vec<ChatMessage> {
+        if let Some(filter) = self.message_filter.as_mut()
+            && !filter(conversation, &mut msg)
+        {
+            return Vec::new();
+        }
+        middleware::run(&mut self.middleware, conversation, msg)
+    }
+
+    /// Rejects submission in the active conversation for `duration`, see [`InputArea::set_cooldown`].
+    pub fn set_cooldown(&mut self, duration: Duration) {
+        self.dirty = true;
+        self.input_area_mut().set_cooldown(duration);
+    }
+
+    /// Whether anything shown has changed since the last render, so the host can skip
+    /// `terminal.draw` until the next event or tick that changes something.
+    pub fn needs_redraw(&self) -> bool {
+        let active = self.conversations.active();
+        let other_pane = self.split.and_then(|panes| self.conversations.get(panes[1 - self.split_focus]));
+        self.dirty
+            || active.chat_area().needs_redraw()
+            || active.input_area().needs_redraw()
+            || other_pane.is_some_and(|pane| pane.chat_area().needs_redraw())
+    }
+
+    /// Forces the next [`ChatApp::needs_redraw`] to return `true`, for example after the
+    /// terminal is resized.
+    pub fn request_redraw(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether an animation is running, so the host can tick more often until it finishes.
+    pub fn is_animating(&self) -> bool {
+        self.conversations.active().chat_area().is_animating()
+    }
+
+    /// Animates scrolling in every conversation, unless [`Settings::reduced_motion`] is on.
+    pub fn set_smooth_scroll(&mut self, enabled: bool) {
+        self.dirty = true;
+        self.smooth_scroll = enabled;
+        self.apply_settings(self.settings);
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    pub fn keymap_mut(&mut self) -> &mut Keymap {
+        self.dirty = true;
+        &mut self.keymap
+    }
+
+    /// Refreshes the status bar's mode, hints and scroll position from the current state.
+    fn update_status_bar(&mut self) {
+        let Some(status_bar) = self.status_bar.as_mut() else {
+            return;
+        };
+        // Offer to stop a reply in place of sending while one is streaming.
+        let streaming = !self.conversations.active().chat_area().streaming().is_empty();
+        let send = if streaming { Action::CancelStreaming } else { Action::Submit };
+        let code_no_wrap = self.conversations.active().chat_area().code_no_wrap();
+        let hints: Vec<(String, String)> = match self.focus {
+            Focus::Input => [send, Action::Newline, Action::ScrollUp, Action::Quit]
+                .into_iter()
+                .filter_map(|action| {
+                    let key = self.keymap.keys_for(action).into_iter().next()?;
+                    Some((key.to_string(), self.strings.action(action).to_string()))
+                })
+                .collect(),
+            Focus::Channels => [
+                ("↑↓", &self.strings.hint_select),
+                ("Enter", &self.strings.hint_open),
+                ("Tab", &self.strings.hint_back),
+            ]
+            .into_iter()
+            .map(|(k, d)| (k.to_string(), d.clone()))
+            .collect(),
+            Focus::Messages => {
+                let mut hints = vec![
+                    ("↑↓", &self.strings.hint_select),
+                    ("Enter", &self.strings.hint_expand),
+                    ("z", &self.strings.hint_fold),
+                    ("r", &self.strings.hint_reveal),
+                    ("i", &self.strings.hint_detail),
+                    ("1-9", &self.strings.hint_copy_code),
+                    ("Esc", &self.strings.hint_back),
+                ];
+                if code_no_wrap {
+                    hints.insert(1, ("←→", &self.strings.hint_scroll_code));
+                }
+                hints.into_iter().map(|(k, d)| (k.to_string(), d.clone())).collect()
+            }
+            Focus::Filter => [
+                ("Enter", &self.strings.hint_keep),
+                ("Esc", &self.strings.hint_clear),
+                #[cfg(feature = "regex")]
+                ("Ctrl+R", &self.strings.hint_regex),
+            ]
+            .into_iter()
+            .map(|(k, d)| (k.to_string(), d.clone()))
+            .collect(),
+        };
+        status_bar.set_hints(hints);
+        status_bar.set_mode(match self.focus {
+            Focus::Input => self.strings.mode_input.as_str(),
+            Focus::Channels => self.strings.mode_channels.as_str(),
+            Focus::Filter => self.strings.mode_filter.as_str(),
+            Focus::Messages => self.strings.mode_messages.as_str(),
+        });
+        let (line, total) = self.conversations.active().chat_area().scroll_position();
+        status_bar.set_position((total > 0).then_some((line, total)));
+    }
+
+    fn on_filter_key(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyCode;
+        let mut filter = self.conversations.active().chat_area().filter().to_string();
+        match key.code {
+            KeyCode::Enter => {
+                self.focus = Focus::Input;
+                return;
+            }
+            KeyCode::Esc => {
+                filter.clear();
+                self.focus = Focus::Input;
+            }
+            KeyCode::Backspace => {
+                filter.pop();
+            }
+            #[cfg(feature = "regex")]
+            KeyCode::Char('r') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                let chat_area = self.chat_area_mut();
+                chat_area.set_filter_regex(!chat_area.filter_regex());
+                return;
+            }
+            KeyCode::Char(c) if keymap::is_text(&key) => filter.push(c),
+            _ => return,
+        }
+        self.chat_area_mut().set_filter(filter);
+    }
+
+    fn on_message_key(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyCode;
+        // The letter and digit commands below are for bare keys only, not shortcuts.
+        if matches!(key.code, KeyCode::Char(_)) && !keymap::is_text(&key) {
+            return;
+        }
+        let chat_area = self.chat_area_mut();
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                chat_area.select_prev();
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                chat_area.select_next();
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(id) = chat_area.selected_message()
+                    && !self.play_voice(id)
+                {
+                    self.chat_area_mut().toggle_expanded(id);
+                }
+            }
+            KeyCode::Char('z') => {
+                if let Some(id) = chat_area.selected_message() {
+                    chat_area.toggle_folded(id);
+                }
+            }
+            KeyCode::Char('r') => {
+                if let Some(id) = chat_area.selected_message() {
+                    chat_area.toggle_revealed(id);
+                }
+            }
+            KeyCode::Char(c @ '1'..='9') => {
+                self.copy_code_block(c as usize - '0' as usize);
+            }
+            KeyCode::Char('i') => {
+                self.open_message_detail();
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                if let Some(id) = chat_area.selected_message() {
+                    chat_area.scroll_code_left(id, CODE_SCROLL_STEP);
+                }
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(id) = chat_area.selected_message() {
+                    chat_area.scroll_code_right(id, CODE_SCROLL_STEP);
+                }
+            }
+            KeyCode::Esc => {
+                chat_area.select_message(None);
+                self.focus = Focus::Input;
+            }
+            _ => {}
+        }
+    }
+
+    fn on_channel_key(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::KeyCode;
+        match key.code {
+            KeyCode::Enter => {
+                if let Some(index) = self.channel_list.selected() {
+                    self.conversations.select(index);
+                }
+                self.channel_list.set_filter("");
+                self.focus = Focus::Input;
+            }
+            KeyCode::Esc if self.channel_list.filter().is_empty() => self.focus = Focus::Input,
+            _ => {
+                self.channel_list.on_key(key);
+            }
+        }
+    }
+
+    /// The active conversation's messages. Other conversations are reached through
+    /// [`ChatApp::conversations`].
+    pub fn chat_area(&self) -> &ChatArea {
+        self.conversations.active().chat_area()
+    }
+
+    /// The active conversation's messages, for changing them directly. Messages added here
+    /// bypass the message filter, middleware, notifications and the message store; use
+    /// [`ChatApp::add_message`] or [`ChatApp::start_streaming`] for those.
+    pub fn chat_area_mut(&mut self) -> &mut ChatArea {
+        self.dirty = true;
+        self.conversations.active_mut().chat_area_mut()
+    }
+
+    /// The active conversation's input.
+    pub fn input_area(&self) -> &InputArea {
+        self.conversations.active().input_area()
+    }
+
+    pub fn input_area_mut(&mut self) -> &mut InputArea {
+        self.dirty = true;
+        self.conversations.active_mut().input_area_mut()
+    }
+
+    pub fn on_key(&mut self, key: crossterm::event::KeyEvent) {
+        use crossterm::event::{KeyCode, KeyEventKind};
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        self.dirty = true;
+        // Quitting and suspending work wherever the focus is and whatever popup is open. Plain
+        // Esc is the exception: it closes popups, leaves focus modes and stops streaming first.
+        let plain_esc = key.code == KeyCode::Esc && key.modifiers.is_empty();
+        if !plain_esc && let Some(action @ (Action::Quit | Action::Suspend)) = self.keymap.action_for(&key) {
+            self.perform(action);
+            return;
+        }
+        if let Some((_, dialog)) = &mut self.dialog {
+            if let Some(choice) = dialog.on_key(key) {
+                let (tag, _) = self.dialog.take().expect("dialog is open");
+                self.confirmation = Some((tag, choice));
+            }
+            return;
+        }
+        if let Some((_, _, detail)) = &mut self.message_detail {
+            match detail.on_key(key) {
+                Some(DetailAction::Close) => self.message_detail = None,
+                Some(DetailAction::CopyMessage) => {
+                    let text = detail.message().content.clone();
+                    self.copy_to_clipboard(text, self.strings.message_copied.clone());
+                }
+                Some(DetailAction::CopyCodeBlock(n)) => match markdown::code_blocks(&detail.message().content).into_iter().nth(n - 1) {
+                    Some(code) => {
+                        let copied = strings::fill(&self.strings.code_copied, &[("n", &n)]);
+                        self.copy_to_clipboard(code, copied);
+                    }
+                    None => {
+                        let text = strings::fill(&self.strings.code_missing, &[("n", &n)]);
+                        self.notify(ToastLevel::Warn, text, 20);
+                    }
+                },
+                None => {}
+            }
+            return;
+        }
+        if let Some(picker) = &mut self.date_picker {
+            if let Some(jump) = picker.on_key(key) {
+                let time = picker.time();
+                self.date_picker = None;
+                if jump {
+                    self.chat_area_mut().scroll_to_time(time);
+                }
+            }
+            return;
+        }
+        if let Some(panel) = &mut self.settings_panel {
+            let open = panel.on_key(key);
+            let settings = panel.settings();
+            if !open {
+                self.settings_panel = None;
+            }
+            if settings != self.settings {
+                self.apply_settings(settings);
+            }
+            return;
+        }
+        if self.conversations.on_key(key) {
+            return;
+        }
+        if self.show_channels && key.code == KeyCode::Tab && key.modifiers.is_empty() {
+            self.focus = match self.focus {
+                Focus::Channels => Focus::Input,
+                Focus::Input | Focus::Filter | Focus::Messages => Focus::Channels,
+            };
+            return;
+        }
+        if self.focus == Focus::Channels {
+            self.on_channel_key(key);
+            return;
+        }
+        if self.focus == Focus::Filter {
+            self.on_filter_key(key);
+            return;
+        }
+        if self.focus == Focus::Messages {
+            self.on_message_key(key);
+            return;
+        }
+        let actions = self.keymap.actions_for(&key);
+        let streaming = !self.chat_area().streaming().is_empty();
+        let action = if streaming && actions.contains(&Action::CancelStreaming) {
+            Some(Action::CancelStreaming)
+        } else {
+            actions.iter().copied().find(|&a| a != Action::CancelStreaming).or(actions.first().copied())
+        };
+        if let Some(action) = action {
+            self.perform(action);
+        } else if let KeyCode::Char(c) = key.code
+            && keymap::is_text(&key)
+        {
+            self.input_area_mut().insert_char(c);
+        }
+    }
+
+    /// Performs a keymap action as if its key had been pressed.
+    pub fn perform(&mut self, action: Action) {
+        self.dirty = true;
+        match action {
+            Action::Submit => {
+                // The draft is kept until the rate limit allows sending it.
+                let input = self.input_area_mut().submit();
+                let active = self.conversations.active_index();
+                if !input.trim().is_empty() {
+                    let sent = self.process_message(active, ChatMessage::new("User", input));
+                    if sent.is_empty() {
+                        return;
+                    }
+                    for msg in sent {
+                        if let Some(handler) = self.on_submit.as_mut() {
+                            handler(active, &msg.content);
+                        }
+                        self.save_message(active, &msg);
+                        if let Some(outbox) = self.outbox.as_mut() {
+                            outbox.push((self.conversations.active().name().to_string(), msg.clone()));
+                        }
+                        let id = self.chat_area_mut().add_message(msg);
+                        self.message_added(active, id);
+                    }
+                    if self.outbox.is_some() || !self.simulated_replies {
+                        return;
+                    }
+                    // Simulate AI response
+                    for reply in self.process_message(active, ChatMessage::new("AI", "Hello! This is a simulated response.")) {
+                        let shown = self.without_spoilers(active, &reply).into_owned();
+                        self.announce(&shown);
+                        self.save_message(active, &reply);
+                        let id = self.chat_area_mut().add_message(reply);
+                        self.message_added(active, id);
+                    }
+                }
+            }
+            Action::Newline => self.input_area_mut().newline(),
+            Action::Paste => {
+                if let Ok(mut clipboard) = Clipboard::new()
+                    && let Ok(text) = clipboard.get_text() {
+                    self.on_paste(text);
+                }
+            }
+            Action::Backspace => self.input_area_mut().backspace(),
+            Action::CursorLeft => self.input_area_mut().cursor_left(),
+            Action::CursorRight => self.input_area_mut().cursor_right(),
+            Action::CursorUp => self.input_area_mut().cursor_up(),
+            Action::CursorDown => self.input_area_mut().cursor_down(),
+            Action::ScrollUp => self.chat_area_mut().scroll_up(5),
+            Action::ScrollDown => self.chat_area_mut().scroll_down(5),
+            Action::OpenSettings => self.open_settings(),
+            Action::Filter => self.focus = Focus::Filter,
+            Action::SelectMessages => {
+                if self.chat_area_mut().select_prev().is_some() {
+                    self.focus = Focus::Messages;
+                }
+            }
+            Action::JumpToDate => self.open_date_picker(),
+            Action::TogglePreview => self.preview = self.preview.next(),
+            Action::CancelStreaming => {
+                let conversation = self.conversations.active_index();
+                let chat_area = self.chat_area_mut();
+                let ids = chat_area.streaming().to_vec();
+                for &id in &ids {
+                    chat_area.cancel_streaming(id);
+                }
+                // What arrived before the cancellation is kept in the history.
+                for &id in &ids {
+                    if let Some(msg) = self.chat_area().message(id).cloned() {
+                        self.save_message(conversation, &msg);
+                    }
+                }
+                if let Some(handler) = self.on_cancel_streaming.as_mut() {
+                    for id in ids {
+                        handler(conversation, id);
+                    }
+                }
+            }
+            Action::GrowInput => self.move_split(Self::SPLIT_STEP as i16),
+            Action::ShrinkInput => self.move_split(-(Self::SPLIT_STEP as i16)),
+            Action::NextSuggestion => {
+                #[cfg(feature = "spellcheck")]
+                self.input_area_mut().next_suggestion();
+            }
+            Action::CopyCodeBlock(n) => {
+                self.copy_code_block(n as usize);
+            }
+            Action::CopySelection => {
+                self.copy_selection();
+            }
+            Action::ToggleSplit => {
+                if self.split.is_some() {
+                    self.close_split();
+                } else {
+                    let next = (self.conversations.active_index() + 1) % self.conversations.len();
+                    self.open_split(next);
+                }
+            }
+            Action::SwitchPane => self.focus_other_pane(),
+            Action::OpenMessageDetail => {
+                self.open_message_detail();
+            }
+            Action::ClearInput => self.input_area_mut().clear(),
+            Action::MoveLineUp => self.input_area_mut().move_line_up(),
+            Action::MoveLineDown => self.input_area_mut().move_line_down(),
+            Action::DuplicateLine => self.input_area_mut().duplicate_line(),
+            Action::SelectAll => self.input_area_mut().select_all(),
+            Action::Suspend => self.suspend_requested = true,
+            Action::Quit => self.should_quit = true,
+        }
+    }
+
+    pub fn on_mouse(&mut self, mouse: MouseEvent) {
+        self.dirty = true;
+        // Keep selecting text while the pointer strays off the chat.
+        if self.selecting_text {
+            match mouse.kind {
+                MouseEventKind::Drag(MouseButton::Left) => {
+                    // A drag ends a run of clicks.
+                    self.last_click = None;
+                    self.chat_area_mut().extend_text_selection(mouse.column, mouse.row);
+                    return;
+                }
+                MouseEventKind::Up(MouseButton::Left) => {
+                    self.selecting_text = false;
+                    if self.copy_on_select {
+                        self.copy_selection();
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+        if self.show_members && self.members_rect.contains((mouse.column, mouse.row).into()) {
+            let members = self.conversations.active_mut().members_mut();
+            match mouse.kind {
+                MouseEventKind::ScrollUp => members.scroll_up(3),
+                MouseEventKind::ScrollDown => members.scroll_down(3),
+                _ => {}
+            }
+            return;
+        }
+        let position = (mouse.column, mouse.row).into();
+        if self.show_channels && self.channels_rect.contains(position) {
+            if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+                && let Some(index) = self.channel_list.channel_at(mouse.column, mouse.row)
+            {
+                self.conversations.select(index);
+                self.channel_list.set_filter("");
+                self.focus = Focus::Input;
+            }
+            return;
+        }
+        if self.conversations.len() > 1 && self.tabs_rect.contains(position) {
+            if mouse.kind == MouseEventKind::Down(MouseButton::Left)
+                && let Some(index) = self.conversations.tab_at(self.tabs_rect, mouse.column)
+            {
+                self.conversations.select(index);
+            }
+            return;
+        }
+        // The input's top border doubles as the splitter.
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left)
+                if mouse.row == self.input_rect.y && self.input_rect.contains((mouse.column, mouse.row).into()) =>
+            {
+                self.dragging_split = true;
+                return;
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.dragging_split => {
+                let input_height = self.split_rect.bottom().saturating_sub(mouse.row);
+                if let Some(percent) = (input_height * 100).checked_div(self.split_rect.height) {
+                    self.set_input_split(Some(percent));
+                }
+                return;
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.dragging_split => {
+                self.dragging_split = false;
+                return;
+            }
+
+            _ => {}
+        }
+        if self.input_rect.contains(position) {
+            match mouse.kind {
+                MouseEventKind::ScrollUp => self.input_area_mut().scroll_up(1),
+                MouseEventKind::ScrollDown => self.input_area_mut().scroll_down(1),
+                MouseEventKind::Down(MouseButton::Left) => self.focus = Focus::Input,
+                _ => {}
+            }
+            return;
+        }
+        if self.split.is_some() && self.other_pane_rect.contains(position) {
+            let other = self.split.expect("split view")[1 - self.split_focus];
+            let Some(conversation) = self.conversations.get_mut(other) else {
+                return;
+            };
+            match mouse.kind {
+                MouseEventKind::ScrollUp => conversation.chat_area_mut().scroll_up(3),
+                MouseEventKind::ScrollDown => conversation.chat_area_mut().scroll_down(3),
+                MouseEventKind::Down(MouseButton::Left) => self.focus_other_pane(),
+                _ => {}
+            }
+            return;
+        }
+        // Check if mouse is within chat area
+        if mouse.column >= self.chat_rect.x
+            && mouse.column < self.chat_rect.x + self.chat_rect.width
+            && mouse.row >= self.chat_rect.y
+            && mouse.row < self.chat_rect.y + self.chat_rect.height
+        {
+            match mouse.kind {
+                MouseEventKind::ScrollUp => self.chat_area_mut().scroll_up(3),
+                MouseEventKind::ScrollDown => self.chat_area_mut().scroll_down(3),
+                MouseEventKind::Down(MouseButton::Left) => {
+                    let now = Instant::now();
+                    let clicks = match self.last_click {
+                        Some((at, column, row, count))
+                            if (column, row) == (mouse.column, mouse.row) && now.duration_since(at) < MULTI_CLICK =>
+                        {
+                            count % 3 + 1
+                        }
+                        _ => 1,
+                    };
+                    self.last_click = Some((now, mouse.column, mouse.row, clicks));
+                    let chat_area = self.chat_area_mut();
+                    self.selecting_text = match clicks {
+                        1 => chat_area.start_text_selection(mouse.column, mouse.row),
+                        2 => chat_area.select_word_at(mouse.column, mouse.row),
+                        _ => chat_area.select_message_text_at(mouse.column, mouse.row),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Handles any terminal event: keys, mouse events routed to the widget under the pointer
+    /// as of the last render, pastes, focus changes and resizes. A resize redraws at the new
+    /// size, keeping the message at the top of the view if the chat is scrolled back.
+    pub fn on_event(&mut self, event: crossterm::event::Event) {
+        use crossterm::event::Event;
+        match event {
+            Event::Key(key) => self.on_key(key),
+            Event::Mouse(mouse) => self.on_mouse(mouse),
+            Event::Paste(content) => self.on_paste(content),
+            Event::FocusGained => self.on_focus(true),
+            Event::FocusLost => self.on_focus(false),
+            Event::Resize(..) => self.request_redraw(),
+        }
+    }
+
+    /// Handles pasted content by inserting it into the input area.
+    pub fn on_paste(&mut self, content: String) {
+        self.dirty = true;
+        self.input_area_mut().insert_str(&content);
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        self.dirty = false;
+        self.load_history();
+        let mut size = frame.area();
+        if self.show_channels {
+            let [sidebar, rest] = Layout::horizontal([Constraint::Length(20), Constraint::Min(1)]).areas(size);
+            let active = self.conversations.active_index();
+            let entries = self
+                .conversations
+                .conversations()
+                .iter()
+                .map(|c| ChannelEntry {
+                    name: c.name().to_string(),
+                    unread: c.unread(),
+                })
+                .collect();
+            self.channel_list.set_channels(entries);
+            for (i, c) in self.conversations.conversations().iter().enumerate() {
+                self.channel_list.set_draft(i, i != active && c.has_draft());
+            }
+            if self.focus != Focus::Channels {
+                self.channel_list.select(self.conversations.active_index());
+            }
+            self.channels_rect = sidebar;
+            self.channel_list.render(frame, sidebar, self.focus == Focus::Channels);
+            size = rest;
+        }
+        if self.show_members {
+            let [rest, members] = Layout::horizontal([Constraint::Min(1), Constraint::Length(20)]).areas(size);
+            self.members_rect = members;
+            self.conversations.active_mut().members_mut().render(frame, members);
+            size = rest;
+        }
+        if self.conversations.len() > 1 {
+            let [tabs, rest] = Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(size);
+            self.tabs_rect = tabs;
+            self.conversations.render_tabs(frame, tabs);
+            size = rest;
+        }
+        if self.show_header {
+            let header = self.conversations.active().header();
+            let [top, rest] = Layout::vertical([Constraint::Length(header.height()), Constraint::Min(1)]).areas(size);
+            header.render(frame, top);
+            size = rest;
+        }
+        let mut status_area = None;
+        if self.status_bar.is_some() {
+            let [rest, bottom] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(size);
+            status_area = Some(bottom);
+            size = rest;
+        }
+        let conversation = self.conversations.active_mut();
+        let input_height = match self.input_split {
+            Some(percent) => (size.height as u32 * percent as u32 / 100) as u16,
+            None => conversation.input_area().calculate_display_lines(size.width),
+        }
+        .max(3);
+        self.split_rect = size;
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(1),
+                Constraint::Length(input_height),
+            ].as_ref())
+            .split(size);
+        let mut chat_rect = chunks[0];
+        self.sync_split();
+        self.sync_presence(self.conversations.active_index(), false);
+        if let Some(panes) = self.split {
+            self.sync_presence(panes[1 - self.split_focus], false);
+            let rects: [Rect; 2] = Layout::horizontal([Constraint::Fill(1); 2]).areas(chunks[0]);
+            let other = panes[1 - self.split_focus];
+            chat_rect = rects[self.split_focus];
+            self.other_pane_rect = rects[1 - self.split_focus];
+            if let Some(conversation) = self.conversations.get_mut(other) {
+                conversation.mark_read();
+                let name = conversation.name().to_string();
+                conversation.chat_area_mut().set_pane(Some((name, false)));
+                conversation.chat_area_mut().render(frame, self.other_pane_rect);
+            }
+            let conversation = self.conversations.active_mut();
+            let name = conversation.name().to_string();
+            conversation.chat_area_mut().set_pane(Some((name, true)));
+        }
+        let conversation = self.conversations.active_mut();
+        if conversation.header().typing_position() == TypingPosition::BelowChat
+            && let Some(typing) = conversation.header().typing_line()
+            && chat_rect.height > 1
+        {
+            let [rest, row] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(chat_rect);
+            let style = Style::default().add_modifier(Modifier::DIM | Modifier::ITALIC);
+            frame.render_widget(Paragraph::new(Line::styled(format!(" {typing}"), style)), row);
+            chat_rect = rest;
+        }
+        self.chat_rect = chat_rect;
+        self.input_rect = chunks[1];
+        conversation.chat_area_mut().render(frame, chat_rect);
+        if self.terminal_focused && conversation.chat_area().is_following() {
+            self.unseen = 0;
+        }
+        self.check_following();
+        let conversation = self.conversations.active_mut();
+        let input_area = match self.preview {
+            PreviewMode::Off => chunks[1],
+            PreviewMode::SideBySide => {
+                let [input, preview] = Layout::horizontal([Constraint::Fill(1); 2]).areas(chunks[1]);
+                conversation.input_area().render_preview(frame, preview);
+                input
+            }
+            PreviewMode::Only => {
+                // Lay the input out for scrolling, then draw the preview over it.
+                conversation.input_area_mut().render(frame, chunks[1]);
+                conversation.input_area().render_preview(frame, chunks[1]);
+                Rect::default()
+            }
+        };
+        if self.preview != PreviewMode::Only {
+            conversation.input_area_mut().render(frame, input_area);
+        }
+
+        // Calculate cursor position
+        self.cursor_pos = conversation
+            .input_area()
+            .cursor_cell()
+            .map(|(line, col)| (input_area.x + 1 + col, input_area.y + 1 + line));
+
+        if matches!(self.focus, Focus::Filter | Focus::Messages) || self.preview == PreviewMode::Only || !conversation.input_area().is_enabled() {
+            self.cursor_pos = None;
+        }
+
+        if let Some(area) = status_area {
+            self.update_status_bar();
+            if let Some(status_bar) = &self.status_bar {
+                status_bar.render(frame, area);
+            }
+        }
+
+        self.toasts.render(frame, self.chat_rect);
+        if let Some(panel) = &self.settings_panel {
+            panel.render(frame, frame.area());
+            self.cursor_pos = None;
+        }
+        if let Some(picker) = &self.date_picker {
+            picker.render(frame, frame.area());
+            self.cursor_pos = None;
+        }
+        if let Some((conversation, id, detail)) = &mut self.message_detail {
+            if let Some(msg) = self.conversations.get(*conversation).and_then(|c| c.chat_area().message(*id))
+                && (msg.content != detail.message().content || msg.reasoning != detail.message().reasoning)
+            {
+                detail.set_message(msg.clone());
+            }
+            detail.render(frame, frame.area());
+            self.cursor_pos = None;
+        }
+        if let Some((_, dialog)) = &self.dialog {
+            dialog.render(frame, frame.area());
+            self.cursor_pos = None;
+        }
+    }
+
+
+
+    pub fn should_quit(&self) -> bool {
+        self.should_quit
+    }
+
+    /// Whether the user asked to suspend to the shell (Ctrl+Z by default) since the last call.
+    /// The host answers by calling [`ChatApp::suspend`], or by restoring the terminal and
+    /// stopping the process itself.
+    pub fn take_suspend_request(&mut self) -> bool {
+        std::mem::take(&mut self.suspend_requested)
+    }
+
+    /// Suspends the process to the shell and sets the terminal up again when it is resumed
+    /// with `fg`. `out` is the writer the terminal backend uses; call `Terminal::clear`
+    /// afterwards so the whole screen is redrawn.
+    ///
+    /// The terminal is restored as the example sets it up: the alternate screen, mouse
+    /// capture, bracketed paste and focus change reporting are left and then re-entered.
+    #[cfg(all(unix, feature = "suspend"))]
+    pub fn suspend(&mut self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        self.dirty = true;
+        suspend::suspend(out)
+    }
+
+    pub fn get_cursor_pos(&self) -> Option<(u16, u16)> {
+        self.cursor_pos
+    }
+
+    /// Runs the app in `terminal` until the user quits: sets up raw mode, the alternate
+    /// screen, mouse capture, bracketed paste and focus reporting, redraws when something
+    /// changed, routes input to [`ChatApp::on_event`] and calls [`ChatApp::tick`] every
+    /// `tick_rate` (at frame rate while a scroll animation runs). The terminal is restored
+    /// before returning, also on error, and on a panic before its message is printed to
+    /// stderr. With the `suspend` feature, Ctrl+Z suspends to the shell.
+    ///
+    /// The panic hook cannot reach `terminal`, so on a panic it writes the restoring escape
+    /// sequences to stdout whatever `W` is. A terminal drawing to another writer, such as
+    /// stderr or `/dev/tty`, is left on the alternate screen if the loop panics.
+    pub fn run<W: std::io::Write>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<W>>,
+        tick_rate: Duration,
+    ) -> std::io::Result<()> {
+        self.run_with(terminal, tick_rate, |_| {})
+    }
+
+    /// Like [`ChatApp::run`], and calls `poll` with the app on every pass through the loop,
+    /// at least once per tick, to feed in messages from async sources, such as draining a
+    /// channel a [`ChatBackend`] task sends [`BackendEvent`]s on.
+    pub fn run_with<W: std::io::Write>(
+        &mut self,
+        terminal: &mut ratatui::Terminal<ratatui::backend::CrosstermBackend<W>>,
+        tick_rate: Duration,
+        mut poll: impl FnMut(&mut ChatApp),
+    ) -> std::io::Result<()> {
+        run_loop::run(self, terminal, tick_rate, &mut poll)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{Terminal, backend::TestBackend};
+
+    #[test]
+    fn test_insert_str_normalization() {
+        let mut input = InputArea::new();
+        input.insert_str("hello\r\nworld\r");
+        assert_eq!(input.buffer, "hello\nworld\n");
+        assert_eq!(input.cursor, 12);
+    }
+
+    #[test]
+    fn test_insert_str_cursor_position() {
+        let mut input = InputArea::new();
+        input.insert_char('a');
+        input.insert_char('b');
+        input.cursor_left();
+        input.insert_str("test");
+        assert_eq!(input.buffer, "atestb");
+        assert_eq!(input.cursor, 5); // 'a' 't' 'e' 's' 't' 'b' cursor after 't'
+    }
+
+    #[cfg(feature = "spellcheck")]
+    #[test]
+    fn test_next_suggestion_replaces_word_at_cursor() {
+        struct Teh;
+        impl SpellChecker for Teh {
+            fn check(&self, word: &str) -> bool {
+                word != "teh"
+            }
+            fn suggest(&self, _word: &str) -> Vec<String> {
+                vec!["the".to_string(), "tech".to_string()]
+            }
+        }
+        let mut input = InputArea::new();
+        input.insert_str("first\nsee teh");
+        assert!(!input.next_suggestion());
+        // The style may be chosen before there is a checker.
+        input.set_misspelled_style(Style::default().fg(ratatui::style::Color::Blue));
+        input.set_spellchecker(Teh);
+        let area = Rect::new(0, 0, 20, 4);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        input.render_to_buffer(&mut buf, area);
+        let cells: Vec<_> = (0..area.width).map(|x| &buf[(x, 2)]).collect();
+        let word = cells.iter().position(|c| c.symbol() == "t").unwrap();
+        assert!(cells[word..word + 3].iter().all(|c| c.fg == ratatui::style::Color::Blue));
+        assert_ne!(cells[word - 2].fg, ratatui::style::Color::Blue);
+        assert!(input.next_suggestion());
+        assert!(input.next_suggestion());
+        assert_eq!(input.buffer, "first\nsee tech");
+        assert_eq!(input.cursor, input.buffer.len_bytes());
+    }
+
+    #[test]
+    fn test_large_paste_and_line_navigation() {
+        let mut input = InputArea::new();
+        let document: String = (0..5000).map(|i| format!("line {i}\n")).collect();
+        input.insert_str(&document);
+        input.insert_str("héllo");
+        assert_eq!(input.buffer.len_lines(), 5001);
+        input.cursor_up();
+        assert_eq!(input.cursor, document.len() - "line 4999\n".len() + "héllo".chars().count());
+        input.cursor_down();
+        input.backspace();
+        assert!(input.buffer.to_string().ends_with("line 4999\nhéll"));
+        assert_eq!(input.calculate_display_lines(40), 12);
+    }
+
+    #[test]
+    fn test_disabled_input_ignores_edits() {
+        let mut app = ChatApp::new();
+        app.input_area_mut().insert_str("draft");
+        app.input_area_mut().set_enabled(false);
+        app.input_area_mut().set_disabled_reason(Some("waiting for response…".to_string()));
+        app.on_key(KeyEvent::from(KeyCode::Char('x')));
+        app.on_key(KeyEvent::from(KeyCode::Backspace));
+        app.on_key(KeyEvent::from(KeyCode::Enter));
+        app.on_paste("pasted".to_string());
+        app.on_key(KeyEvent::from(KeyCode::Left));
+        assert_eq!(app.input_area_mut().buffer, "draft");
+        assert_eq!(app.input_area_mut().cursor, 4);
+        assert!(app.chat_area_mut().messages.is_empty());
+
+        let area = Rect::new(0, 0, 40, 3);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        app.input_area_mut().render_to_buffer(&mut buf, area);
+        assert!(testing::buffer_to_string(&buf).starts_with("┌Input─(waiting for response…)"));
+        assert!(buf[(2, 1)].modifier.contains(Modifier::DIM));
+
+        app.input_area_mut().set_enabled(true);
+        app.on_key(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.chat_area_mut().messages[0].content, "draft");
+    }
+
+    #[test]
+    fn test_cancel_streaming_with_esc() {
+        use std::{cell::RefCell, rc::Rc};
+        let mut app = ChatApp::new();
+        let cancelled = Rc::new(RefCell::new(Vec::new()));
+        let sink = cancelled.clone();
+        app.set_on_cancel_streaming(move |conversation, id| sink.borrow_mut().push((conversation, id)));
+
+        let chat = app.chat_area_mut();
+        let done = chat.start_streaming(ChatMessage::new("AI", "Hel"));
+        assert!(chat.append_to_message(done, "lo"));
+        assert!(chat.finish_streaming(done));
+        let id = chat.start_streaming(ChatMessage::new("AI", ""));
+        chat.append_to_message(id, "Once upon");
+        assert!(chat.is_streaming(id));
+
+        app.on_key(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(*cancelled.borrow(), [(0, id)]);
+        let chat = app.chat_area_mut();
+        assert!(chat.streaming().is_empty());
+        assert_eq!(chat.message(done).unwrap().content, "Hello");
+        assert_eq!(chat.message(id).unwrap().content, "Once upon (cancelled)");
+        assert!(!chat.cancel_streaming(id));
+        app.on_key(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(cancelled.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_input_height_limits() {
+        let mut input = InputArea::new();
+        input.set_height(InputHeight::Grow { min: 3, max: 5 });
+        assert_eq!(input.calculate_display_lines(40), 5);
+        input.insert_str("1\n2\n3\n4");
+        assert_eq!(input.calculate_display_lines(40), 6);
+        input.insert_str("\n5\n6\n7");
+        assert_eq!(input.calculate_display_lines(40), 7);
+        input.set_height(InputHeight::Fixed(2));
+        assert_eq!(input.calculate_display_lines(40), 4);
+
+        let area = Rect::new(0, 0, 20, 4);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        input.render_to_buffer(&mut buf, area);
+        assert_eq!(testing::buffer_to_string(&buf), "┌Input─────────────┐\n│> 6               │\n│> 7               │\n└──────────────────┘\n");
+        assert_eq!(input.cursor_cell(), Some((1, 3)));
+        input.cursor_up();
+        input.cursor_up();
+        input.render_to_buffer(&mut buf, area);
+        assert_eq!(input.cursor_cell(), Some((0, 3)));
+        assert!(testing::buffer_to_string(&buf).contains("> 5"));
+    }
+
+    #[test]
+    fn test_cursor_and_height_follow_wrapped_rows() {
+        let mut input = InputArea::new();
+        input.set_height(InputHeight::Grow { min: 1, max: 5 });
+        input.insert_str("the quick brown fox jumps");
+        assert_eq!(input.calculate_display_lines(14), 5);
+        let area = Rect::new(0, 0, 14, 4);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        input.render_to_buffer(&mut buf, area);
+        assert_eq!(testing::buffer_to_string(&buf), "┌Input───────┐\n│  brown fox │\n│  jumps     │\n└────────────┘\n");
+        assert_eq!(input.cursor_cell(), Some((1, 7)));
+        input.cursor = 0;
+        input.render_to_buffer(&mut buf, area);
+        assert!(testing::buffer_to_string(&buf).contains("> the quick"));
+        assert_eq!(input.cursor_cell(), Some((0, 2)));
+
+        // Wide characters take two columns, so fewer fit on a row.
+        input.set_text("日本語日本語");
+        assert_eq!(input.calculate_display_lines(8), 5);
+
+        let mut app = ChatApp::new();
+        app.input_area_mut().insert_str("word ".repeat(10).trim_end());
+        let mut terminal = Terminal::new(TestBackend::new(30, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let (x, y) = app.get_cursor_pos().unwrap();
+        assert!(x < 29 && y < 11);
+        assert_eq!(terminal.backend().buffer()[(x - 1, y)].symbol(), "d");
+        assert_eq!(terminal.backend().buffer()[(x - 4, y)].symbol(), "w");
+    }
+
+    #[test]
+    fn test_cooldown_keeps_the_draft() {
+        let mut input = InputArea::new();
+        input.insert_str("wait");
+        input.set_cooldown(Duration::from_secs(60));
+        assert_eq!(input.submit(), "");
+        assert_eq!(input.buffer, "wait");
+        input.clear_cooldown();
+        assert_eq!(input.submit(), "wait");
+
+        let mut app = ChatApp::new();
+        app.input_area_mut().insert_str("hi");
+        app.set_cooldown(Duration::from_secs(60));
+        app.perform(Action::Submit);
+        assert!(app.chat_area().messages.is_empty());
+        assert_eq!(app.input_area().buffer, "hi");
+    }
+
+    #[test]
+    fn test_wrapped_rtl_input_keeps_row_order() {
+        let mut input = InputArea::new();
+        input.insert_str("אבג דהו");
+        let area = Rect::new(0, 0, 10, 4);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        input.render_to_buffer(&mut buf, area);
+        assert_eq!(testing::buffer_to_string(&buf), "┌Input───┐\n│> גבא   │\n│  והד   │\n└────────┘\n");
+    }
+
+    #[test]
+    fn test_mouse_scroll() {
+        let mut app = ChatApp::new();
+        app.chat_area_mut().add_message(ChatMessage::new("Test", "This is a long message that will wrap into multiple lines when displayed in the chat area."));
+        // Manually calculate message_lines as in render
+        let visible_width = 10;
+        let chat_area = app.chat_area_mut();
+        chat_area.message_lines.clear();
+        for (i, msg) in chat_area.messages.iter().enumerate() {
+            let content = format!("{}: {}", msg.sender, msg.content);
+            let lines = textwrap::wrap(&content, visible_width);
+            for j in 0..lines.len() {
+                chat_area.message_lines.push((i, j));
+            }
+        }
+        // Set offset to 10
+        chat_area.offset = 10;
+        // Set chat_rect
+        app.chat_rect = Rect::new(0, 0, 20, 20);
+        // Mouse event inside rect
+        let mouse = MouseEvent {
+            kind: MouseEventKind::ScrollUp,
+            column: 5,
+            row: 5,
+            modifiers: KeyModifiers::NONE,
+        };
+        app.on_mouse(mouse);
+        assert_eq!(app.chat_area_mut().offset, 7); // 10 - 3
+        // Reset offset
+        app.chat_area_mut().offset = 10;
+        // Mouse event outside rect
+        let mouse_out = MouseEvent {
+            kind: MouseEventKind::ScrollUp,
+            column: 25,
+            row: 5,
+            modifiers: KeyModifiers::NONE,
+        };
+        app.on_mouse(mouse_out);
+        assert_eq!(app.chat_area_mut().offset, 10); // unchanged
+    }
+
+    #[cfg(feature = "ansi-to-tui")]
+    #[test]
+    fn test_ansi_content_is_styled() {
+        use ratatui::style::Color;
+        let mut chat = ChatArea::new();
+        chat.set_parse_ansi(true);
+        let msg = ChatMessage::new("sh", "\x1b[31merror\x1b[0m done");
+        let spans = chat.message_spans(&msg);
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "sh: error done");
+        assert!(spans.iter().any(|s| s.content == "error" && s.style.fg == Some(Color::Red)));
+    }
+
+    #[test]
+    fn test_diff_content_is_colored() {
+        use ratatui::style::Color;
+        let mut chat = ChatArea::new();
+        let msg = ChatMessage::new("bot", "--- a/lib.rs\n+++ b/lib.rs\n@@ -1 +1 @@\n-old\n+new");
+        assert!(chat.message_spans(&msg).iter().all(|s| s.style.fg.is_none()));
+        chat.set_render_diffs(true);
+        let spans = chat.message_spans(&msg);
+        assert!(spans.iter().any(|s| s.content == "-old" && s.style.fg == Some(Color::Red)));
+        assert!(spans.iter().any(|s| s.content == "+new" && s.style.fg == Some(Color::Green)));
+    }
+
+    #[test]
+    fn test_single_line_mode_truncates_all_but_the_selected_message() {
+        let mut chat = ChatArea::new();
+        chat.set_single_line(true);
+        chat.set_density(Density::Compact);
+        let first = chat.add_message(ChatMessage::new("svc", "started\nlistening on port 8080"));
+        chat.add_message(ChatMessage::new("svc", "ok"));
+        let area = Rect::new(0, 0, 24, 6);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        chat.render_to_buffer(&mut buf, area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows, ["svc: started listeni…", "svc: ok"]);
+
+        chat.select_message(Some(first));
+        chat.render_to_buffer(&mut buf, area);
+        assert_eq!(chat.lines.len(), 4);
+    }
+
+    #[test]
+    fn test_inline_code_drops_backticks() {
+        let mut chat = ChatArea::new();
+        let msg = ChatMessage::new("AI", "call `foo()` first");
+        chat.set_render_inline_code(true);
+        let spans = chat.message_spans(&msg);
+        let text: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert!(text.ends_with("call foo() first"));
+        assert!(spans.iter().any(|s| s.content == "foo()" && s.style.fg == Some(ratatui::style::Color::Cyan)));
+    }
+
+    #[test]
+    fn test_relative_timestamps_follow_the_clock() {
+        let now = UNIX_EPOCH + Duration::from_secs(86_400);
+        let mut chat = ChatArea::new();
+        chat.set_show_timestamps(true);
+        chat.set_relative_timestamps(Some(Duration::from_secs(3600)));
+        chat.tick(now);
+        let label = |chat: &ChatArea, secs| chat.prefix_spans(&ChatMessage::new("AI", "x").with_timestamp(now - Duration::from_secs(secs)))[0].content.to_string();
+        assert_eq!(label(&chat, 10), "just now ");
+        assert_eq!(label(&chat, 600), "10m ago ");
+        assert_eq!(label(&chat, 7200), "22:00 ");
+
+        let area = Rect::new(0, 0, 20, 5);
+        chat.render_to_buffer(&mut ratatui::buffer::Buffer::empty(area), area);
+        chat.tick(now + Duration::from_secs(30));
+        assert!(!chat.needs_redraw());
+        chat.tick(now + Duration::from_secs(60));
+        assert!(chat.needs_redraw());
+        assert_eq!(label(&chat, 10), "1m ago ");
+    }
+
+    #[test]
+    fn test_search_uses_index_kept_up_to_date() {
+        let mut chat = ChatArea::new();
+        let old = chat.add_message(ChatMessage::new("bob", "deploy finished"));
+        assert_eq!(chat.search("DEPLOY", 10), [old]);
+        chat.set_search_index(InvertedIndex::new());
+        let reply = chat.start_streaming(ChatMessage::new("AI", "the deploy"));
+        assert_eq!(chat.search("deploy", 10).len(), 2);
+        chat.append_to_message(reply, " broke staging");
+        assert_eq!(chat.search("staging", 10), [reply]);
+        chat.clear();
+        assert!(chat.search("deploy", 10).is_empty());
+
+        for i in 0..20_000 {
+            chat.add_message(ChatMessage::new("AI", format!("message {i} about topic{}", i % 100)));
+        }
+        let hits = chat.search("topic42", 5);
+        assert_eq!(hits.len(), 5);
+        assert!(hits.iter().all(|&id| chat.message(id).unwrap().content.ends_with("topic42")));
+    }
+
+    #[test]
+    fn test_sender_colors_are_stable_per_name() {
+        let mut chat = ChatArea::new();
+        let color = |chat: &ChatArea, sender: &str| chat.prefix_spans(&ChatMessage::new(sender, "hi"))[0].style.fg;
+        assert_eq!(color(&chat, "alice"), None);
+        chat.set_sender_colors(&SENDER_PALETTE);
+        let alice = color(&chat, "alice");
+        assert!(alice.is_some_and(|c| SENDER_PALETTE.contains(&c)));
+        assert_eq!(color(&chat, "alice"), alice);
+        let distinct: HashSet<_> = ["alice", "bob", "carol", "dave", "erin"].iter().map(|s| color(&chat, s)).collect();
+        assert!(distinct.len() > 1);
+        chat.set_sender_colors(&[Color::Red]);
+        assert_eq!(color(&chat, "bob"), Some(Color::Red));
+    }
+
+    #[test]
+    fn test_prefix_formatter_controls_wrapping() {
+        let mut chat = ChatArea::new();
+        chat.set_prefix_formatter(|msg| Span::raw(format!("<{}> ", msg.sender)));
+        let msg = ChatMessage::new("nick", "hello there");
+        let rows = wrap::wrap_spans(&chat.message_spans(&msg), &textwrap::Options::new(12), false);
+        assert_eq!(rows.iter().map(wrap::plain).collect::<Vec<_>>(), vec!["<nick> hello", "there"]);
+
+        chat.set_prefix_formatter(|_| Span::raw(""));
+        let rows = wrap::wrap_spans(&chat.message_spans(&msg), &textwrap::Options::new(12), false);
+        assert_eq!(rows.iter().map(wrap::plain).collect::<Vec<_>>(), vec!["hello there"]);
+    }
+
+    #[test]
+    fn test_hanging_indent_aligns_continuation_lines() {
+        let mut chat = ChatArea::new();
+        chat.set_hanging_indent(true);
+        let msg = ChatMessage::new("AI", "one two three\nfour");
+        let rows = chat.wrap_message(&msg, 11);
+        assert_eq!(rows.iter().map(wrap::plain).collect::<Vec<_>>(), vec!["AI: one two", "    three", "    four"]);
+    }
+
+    #[test]
+    fn test_density_moves_sender_to_header_line() {
+        let msg = ChatMessage::new("AI", "hi there");
+        let mut chat = ChatArea::new();
+        chat.set_density(Density::Cozy);
+        let rows = chat.wrap_message(&msg, 20);
+        assert_eq!(rows.iter().map(wrap::plain).collect::<Vec<_>>(), vec!["AI:", "hi there"]);
+
+        chat.set_density(Density::Comfortable);
+        let rows = chat.wrap_message(&msg, 20);
+        assert_eq!(rows.iter().map(wrap::plain).collect::<Vec<_>>(), vec!["AI:", "  hi there"]);
+    }
+
+    #[test]
+    fn test_gutter_keeps_content_column_aligned() {
+        let mut chat = ChatArea::new();
+        chat.set_gutter(6, |_| Span::raw("12:01 ✓"));
+        let msg = ChatMessage::new("AI", "one two three");
+        let rows = chat.wrap_message(&msg, 17);
+        assert_eq!(rows.iter().map(wrap::plain).collect::<Vec<_>>(), vec!["12:01 AI: one two", "      three"]);
+    }
+
+    #[test]
+    fn test_sticky_header_follows_top_row() {
+        let mut chat = ChatArea::new();
+        chat.set_sticky_header(|msg| msg.sender.clone());
+        for sender in ["alice", "bob"] {
+            chat.add_message(ChatMessage::new(sender, "line"));
+        }
+        chat.message_lines = vec![(0, 0), (1, 0)];
+        chat.offset = 1;
+        assert_eq!(chat.sticky_header().as_deref(), Some("bob"));
+        chat.offset = 0;
+        assert_eq!(chat.sticky_header().as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_pin_navigation_wraps_around() {
+        let mut chat = ChatArea::new();
+        let ids: Vec<MessageId> = (0..3)
+            .map(|i| {
+                chat.add_message(ChatMessage::new("AI", format!("message {i}")))
+            })
+            .collect();
+        chat.message_lines = vec![(0, 0), (1, 0), (2, 0)];
+        assert!(chat.pin_message(ids[2]));
+        assert!(chat.pin_message(ids[0]));
+        assert!(!chat.pin_message(MessageId(42)));
+        assert_eq!(chat.next_pin(), Some(ids[2]));
+        assert_eq!(chat.offset, 2);
+        assert_eq!(chat.next_pin(), Some(ids[0]));
+        assert_eq!(chat.offset, 0);
+        assert_eq!(chat.next_pin(), Some(ids[2]));
+        assert!(chat.unpin_message(ids[2]));
+        assert_eq!(chat.pinned(), &[ids[0]]);
+    }
+
+    #[test]
+    fn test_window_title_counts_unseen_messages() {
+        let mut app = ChatApp::new();
+        app.set_bell_on_message(true);
+        app.set_window_title(Some("chat".to_string()));
+        app.add_message(0, ChatMessage::new("AI", "seen"));
+        assert_eq!(app.window_title().as_deref(), Some("chat"));
+
+        app.on_focus(false);
+        app.add_message(0, ChatMessage::new("AI", "one"));
+        app.add_message(0, ChatMessage::new("AI", "two"));
+        assert_eq!(app.unread_count(), 2);
+
+        let mut out = Vec::new();
+        app.write_terminal_effects(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with('\x07'));
+        assert!(out.contains("(2) chat"));
+
+        let mut out = Vec::new();
+        app.write_terminal_effects(&mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_reduced_motion_writes_a_steady_cursor_once() {
+        let effects = |app: &mut ChatApp| {
+            let mut out = Vec::new();
+            app.write_terminal_effects(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let mut app = ChatApp::new();
+        effects(&mut app);
+        app.set_reduced_motion(true);
+        assert!(app.settings().reduced_motion);
+        assert_eq!(effects(&mut app), "\x1b[2 q");
+        assert_eq!(effects(&mut app), "");
+        app.set_reduced_motion(false);
+        assert_eq!(effects(&mut app), "\x1b[0 q");
+    }
+
+    #[test]
+    fn test_preedit_shown_at_cursor_and_moves_hardware_cursor() {
+        let mut app = ChatApp::new();
+        app.input_area_mut().insert_str("ab");
+        app.input_area_mut().cursor_left();
+        app.input_area_mut().set_preedit("日本");
+        let mut terminal = Terminal::new(TestBackend::new(20, 8)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        // Input row: border, "> ", "a", then the double-width preedit, then "b".
+        assert_eq!(buffer[(4, 6)].symbol(), "日");
+        assert!(buffer[(4, 6)].modifier.contains(Modifier::UNDERLINED));
+        assert_eq!(buffer[(8, 6)].symbol(), "b");
+        assert_eq!(app.get_cursor_pos(), Some((8, 6)));
+        assert_eq!(app.input_area_mut().submit(), "ab");
+        assert_eq!(app.input_area_mut().preedit(), "");
+    }
+
+    #[test]
+    fn test_accessible_mode_announces_and_uses_ascii() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let announced = Rc::new(RefCell::new(Vec::new()));
+        let mut app = ChatApp::new();
+        app.set_accessible(true);
+        let sink = announced.clone();
+        app.set_announcer(move |line| sink.borrow_mut().push(line.to_string()));
+        app.add_message(0, ChatMessage::new("bob", "hi\nthere"));
+        assert_eq!(*announced.borrow(), vec!["bob: hi", "there"]);
+
+        let mut terminal = Terminal::new(TestBackend::new(20, 8)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_eq!(buffer[(0, 0)].symbol(), "+");
+        assert!(buffer.content().iter().all(|cell| cell.symbol().is_ascii()));
+    }
+
+    #[test]
+    fn test_message_filter_rewrites_and_rejects() {
+        let mut app = ChatApp::new();
+        app.set_message_filter(|_, msg| {
+            msg.content = msg.content.replace("sk-secret", "[redacted]");
+            !msg.content.contains("spam")
+        });
+        assert!(app.add_message(0, ChatMessage::new("bot", "key sk-secret")).is_some());
+        assert!(app.add_message(0, ChatMessage::new("bot", "buy spam")).is_none());
+        app.input_area_mut().insert_str("mine is sk-secret");
+        app.perform(Action::Submit);
+        let contents: Vec<&str> = app.chat_area_mut().messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents[..2], ["key [redacted]", "mine is [redacted]"]);
+    }
+
+    #[test]
+    fn test_middleware_splits_messages_after_the_filter() {
+        let mut app = ChatApp::new();
+        app.set_message_filter(|_, msg| !msg.content.is_empty());
+        app.add_middleware(|_, msg: ChatMessage| -> Vec<ChatMessage> {
+            msg.content.split("\n\n").map(|part| ChatMessage::new(msg.sender.clone(), part)).collect()
+        });
+        let id = app.add_message(0, ChatMessage::new("AI", "first\n\nsecond"));
+        assert_eq!(app.chat_area_mut().message(id.unwrap()).unwrap().content, "second");
+        assert_eq!(app.chat_area_mut().messages.len(), 2);
+        assert!(app.add_message(0, ChatMessage::new("AI", "")).is_none());
+    }
+
+    #[test]
+    fn test_observers_see_messages_scrolling_and_submits() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut app = ChatApp::new();
+        let sink = events.clone();
+        app.set_on_message_added(move |c, _, msg| sink.borrow_mut().push(format!("added {c} {}", msg.content)));
+        let sink = events.clone();
+        app.set_on_submit(move |c, text| sink.borrow_mut().push(format!("submit {c} {text}")));
+        let sink = events.clone();
+        app.set_on_scrolled_away_from_bottom(move |c| sink.borrow_mut().push(format!("away {c}")));
+        let sink = events.clone();
+        app.set_on_returned_to_bottom(move |c| sink.borrow_mut().push(format!("bottom {c}")));
+
+        for i in 0..20 {
+            app.add_message(0, ChatMessage::new("AI", format!("m{i}")));
+        }
+        app.set_simulated_replies(true);
+        app.input_area_mut().insert_str("hi");
+        app.perform(Action::Submit);
+        let mut terminal = Terminal::new(TestBackend::new(20, 8)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        app.chat_area_mut().scroll_up(5);
+        terminal.draw(|f| app.render(f)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        app.chat_area_mut().scroll_down(100);
+        terminal.draw(|f| app.render(f)).unwrap();
+
+        let events = events.borrow();
+        assert_eq!(events[19], "added 0 m19");
+        assert_eq!(events[20..], ["submit 0 hi", "added 0 hi", "added 0 Hello! This is a simulated response.", "away 0", "bottom 0"]);
+    }
+
+    #[test]
+    fn test_quit_keys_are_configurable_and_suspend_is_requested() {
+        let mut app = ChatApp::new();
+        app.keymap_mut().set_keys(Action::Quit, &[KeyBinding::ctrl('q')]);
+        app.on_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(!app.should_quit());
+        app.on_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL));
+        assert!(app.should_quit());
+
+        assert!(!app.take_suspend_request());
+        app.perform(Action::Suspend);
+        assert!(app.take_suspend_request());
+        assert!(!app.take_suspend_request());
+    }
+
+    #[test]
+    fn test_plain_esc_quits_once_nothing_else_takes_it() {
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        let mut app = ChatApp::new();
+        app.set_simulated_replies(false);
+        app.perform(Action::Filter);
+        app.on_key(esc);
+        assert_eq!(app.focus, Focus::Input);
+        let id = app.chat_area_mut().start_streaming(ChatMessage::new("AI", "Once"));
+        app.on_key(esc);
+        assert!(!app.chat_area().is_streaming(id));
+        assert!(!app.should_quit());
+        app.on_key(esc);
+        assert!(app.should_quit());
+    }
+
+    #[test]
+    fn test_switching_keymap_preset_keeps_rebound_keys() {
+        let mut app = ChatApp::new();
+        app.keymap_mut().set_keys(Action::Quit, &[KeyBinding::ctrl('q')]);
+        let mut settings = app.settings();
+        settings.keymap = KeymapPreset::Multiline;
+        app.apply_settings(settings);
+        assert_eq!(app.keymap().keys_for(Action::Quit), [KeyBinding::ctrl('q')]);
+        assert_eq!(app.keymap().action_for(&KeyEvent::from(KeyCode::Enter)), Some(Action::Newline));
+        settings.keymap = KeymapPreset::Standard;
+        app.apply_settings(settings);
+        assert_eq!(app.keymap().keys_for(Action::Quit), [KeyBinding::ctrl('q')]);
+        assert_eq!(app.keymap().action_for(&KeyEvent::from(KeyCode::Enter)), Some(Action::Submit));
+    }
+
+    #[test]
+    fn test_quit_works_from_filter_and_channels() {
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let mut app = ChatApp::new();
+        app.perform(Action::Filter);
+        app.on_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::ALT));
+        assert_eq!(app.chat_area().filter(), "");
+        app.on_key(ctrl_c);
+        assert!(app.should_quit());
+
+        let mut app = ChatApp::new();
+        app.set_show_channels(true);
+        app.on_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        assert_eq!(app.focus, Focus::Channels);
+        app.on_key(ctrl_c);
+        assert!(app.should_quit());
+        assert_eq!(app.channel_list.filter(), "");
+
+        let mut app = ChatApp::new();
+        app.on_key(KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL));
+        assert_eq!(app.input_area().text(), "");
+    }
+
+    #[test]
+    fn test_set_text_prefills_input() {
+        let mut app = ChatApp::new();
+        app.input_area_mut().insert_str("draft");
+        app.input_area_mut().set_text("> quoted\r\nreply");
+        assert_eq!(app.input_area().text(), "> quoted\nreply");
+        app.input_area_mut().insert_char('!');
+        assert_eq!(app.input_area().text(), "> quoted\nreply!");
+        app.chat_area_mut().add_message(ChatMessage::new("net", "pushed"));
+        assert_eq!(app.chat_area().messages.len(), 1);
+    }
+
+    #[test]
+    fn test_smooth_scroll_eases_towards_target() {
+        let mut chat = ChatArea::new();
+        chat.message_lines = vec![(0, 0); 40];
+        chat.offset = 30;
+        chat.set_smooth_scroll(true);
+        chat.scroll_up(20);
+        assert_eq!(chat.offset, 10);
+        assert_eq!(chat.shown_offset, Some(30));
+        assert!(chat.tick(SystemTime::now()));
+        assert_eq!(chat.shown_offset, Some(20));
+        while chat.tick(SystemTime::now()) {}
+        assert_eq!(chat.shown_offset, None);
+
+        let mut app = ChatApp::new();
+        app.set_smooth_scroll(true);
+        app.set_reduced_motion(true);
+        app.chat_area_mut().scroll_up(5);
+        assert!(!app.is_animating());
+    }
+
+    #[test]
+    fn test_save_and_restore_session() {
+        let mut app = ChatApp::new();
+        app.conversations_mut().add_conversation("other");
+        app.add_message(1, ChatMessage::new("bob", "hi"));
+        app.conversations_mut().select(1);
+        app.chat_area_mut().scroll_up(1);
+        app.input_area_mut().insert_str("draft");
+        app.input_area_mut().cursor_left();
+        let state = app.save_state();
+
+        let mut restored = ChatApp::new();
+        restored.restore_state(state);
+        let conversations = restored.conversations();
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations.active_index(), 1);
+        let active = conversations.active();
+        assert_eq!(active.name(), "other");
+        assert_eq!(active.chat_area().messages[0].content, "hi");
+        assert!(!active.chat_area().is_following());
+        assert_eq!(active.input_area().buffer, "draft");
+        assert_eq!(active.input_area().cursor, 4);
+    }
+
+    #[test]
+    fn test_restore_keeps_message_ids_and_pins() {
+        let mut app = ChatApp::new();
+        let chat = app.chat_area_mut();
+        let first = chat.add_message(ChatMessage::new("bob", "first"));
+        let last = chat.add_message(ChatMessage::new("bob", "last"));
+        let older = chat.prepend_messages(vec![ChatMessage::new("bob", "older"), ChatMessage::new("bob", "old")]);
+        chat.pin_message(last);
+        chat.pin_message(older[1]);
+        let state = app.save_state();
+
+        let mut restored = ChatApp::new();
+        restored.restore_state(state);
+        let chat = restored.chat_area_mut();
+        assert_eq!(chat.pinned(), [last, older[1]]);
+        for (id, content) in [(older[0], "older"), (older[1], "old"), (first, "first"), (last, "last")] {
+            assert_eq!(chat.message(id).unwrap().content, content);
+        }
+        let next = chat.add_message(ChatMessage::new("bob", "new"));
+        assert!(next > older[1]);
+        assert_eq!(chat.message(next).unwrap().content, "new");
+    }
+
+    #[test]
+    fn test_filter_hides_non_matching_messages() {
+        let mut app = ChatApp::new();
+        for (sender, content) in [("alice", "disk full"), ("bob", "hello"), ("carol", "Disk ok")] {
+            app.add_message(0, ChatMessage::new(sender, content));
+        }
+        app.perform(Action::Filter);
+        for c in "disk".chars() {
+            app.on_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.chat_area_mut().filter_matches(), 2);
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let chat = app.chat_area_mut();
+        assert_eq!(chat.message_lines, vec![(0, 0), (2, 0)]);
+        assert_eq!(app.input_area_mut().buffer, "");
+
+        app.on_key(KeyEvent::from(KeyCode::Esc));
+        assert_eq!(app.chat_area_mut().filter(), "");
+        app.on_key(KeyEvent::from(KeyCode::Char('x')));
+        assert_eq!(app.input_area_mut().buffer, "x");
+    }
+
+    #[test]
+    fn test_scroll_to_time_finds_first_message_at_or_after() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut chat = ChatArea::new();
+        let ids: Vec<MessageId> = [10, 20, 30]
+            .into_iter()
+            .map(|secs| chat.add_message(ChatMessage::new("AI", "x").with_timestamp(UNIX_EPOCH + Duration::from_secs(secs))))
+            .collect();
+        chat.message_lines = vec![(0, 0), (1, 0), (2, 0)];
+        assert_eq!(chat.scroll_to_time(UNIX_EPOCH + Duration::from_secs(15)), Some(ids[1]));
+        assert_eq!(chat.offset, 1);
+        assert!(!chat.is_following());
+        assert_eq!(chat.scroll_to_time(UNIX_EPOCH + Duration::from_secs(31)), None);
+        assert!(chat.is_following());
+    }
+
+    #[test]
+    fn test_metadata_footer_is_right_aligned() {
+        use std::time::Duration;
+        let mut chat = ChatArea::new();
+        let msg = ChatMessage::new("AI", "hi").with_metadata(MessageMetadata {
+            model: Some("gpt-4o".to_string()),
+            elapsed: Some(Duration::from_millis(1250)),
+            prompt_tokens: Some(12),
+            completion_tokens: Some(40),
+            cost: Some(0.0031),
+        });
+        assert_eq!(chat.wrap_message(&msg, 50).len(), 1);
+        chat.set_show_metadata(true);
+        let rows = chat.wrap_message(&msg, 50);
+        assert_eq!(wrap::plain(&rows[1]), format!("{:>50}", "gpt-4o · 1.2s · 12 → 40 tokens · $0.0031"));
+        assert_eq!(rows[1].spans[1].style, chat.theme.muted);
+    }
+
+    #[test]
+    fn test_scroll_to_message_alignment() {
+        let mut chat = ChatArea::new();
+        chat.set_density(Density::Cozy);
+        let ids: Vec<MessageId> = (0..4).map(|_| chat.add_message(ChatMessage::new("AI", "x"))).collect();
+        // Each message after the first has one gap row followed by three content rows.
+        chat.message_lines = (0..4).flat_map(|m| std::iter::repeat_n((m, 0), if m == 0 { 3 } else { 4 })).collect();
+        chat.viewport_height = 4;
+        assert!(chat.scroll_to_message(ids[2], ScrollAlignment::Top));
+        assert_eq!(chat.offset, 8);
+        assert!(!chat.is_following());
+        assert!(chat.scroll_to_message(ids[2], ScrollAlignment::Bottom));
+        assert_eq!(chat.offset, 7);
+        assert!(chat.scroll_to_message(ids[1], ScrollAlignment::Center));
+        assert_eq!(chat.offset, 3);
+        assert!(chat.scroll_to_message(ids[3], ScrollAlignment::Top));
+        assert_eq!(chat.offset, 11);
+        assert!(!chat.scroll_to_message(MessageId(999), ScrollAlignment::Top));
+    }
+
+    #[test]
+    fn test_token_count_in_input_title() {
+        let mut input = InputArea::new();
+        input.insert_str("hello there world");
+        assert_eq!(input.token_count(), None);
+        input.set_token_counter(HeuristicCounter);
+        assert_eq!(input.token_count(), Some(4));
+        let area = Rect::new(0, 0, 30, 3);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        input.render_to_buffer(&mut buf, area);
+        assert!(testing::buffer_to_string(&buf).starts_with("┌Input──────────────~4 tokens┐"));
+        input.set_token_budget(Some(3));
+        input.render_to_buffer(&mut buf, area);
+        assert!(testing::buffer_to_string(&buf).starts_with("┌Input────────────~4/3 tokens┐"));
+        assert_eq!(buf[(18, 0)].fg, ratatui::style::Color::Red);
+    }
+
+    #[test]
+    fn test_token_count_is_cached_until_an_edit() {
+        struct Counting(std::rc::Rc<Cell<usize>>);
+        impl TokenCounter for Counting {
+            fn count(&self, text: &str) -> usize {
+                self.0.set(self.0.get() + 1);
+                text.len()
+            }
+        }
+        let calls = std::rc::Rc::new(Cell::new(0));
+        let mut input = InputArea::new();
+        input.set_token_counter(Counting(calls.clone()));
+        input.insert_str("hi");
+        assert_eq!(input.token_count(), Some(2));
+        assert_eq!(input.token_count(), Some(2));
+        input.cursor_left();
+        assert_eq!(input.token_count(), Some(2));
+        assert_eq!(calls.get(), 1);
+        input.backspace();
+        assert_eq!(input.token_count(), Some(1));
+        assert_eq!(calls.get(), 2);
+    }
+
+    /// A message store in a shared vector, keyed by position.
+    #[derive(Clone, Default)]
+    struct VecStore(std::rc::Rc<std::cell::RefCell<Vec<(String, ChatMessage)>>>);
+
+    impl MessageStore for VecStore {
+        fn append(&mut self, conversation: &str, msg: &ChatMessage) -> std::io::Result<u64> {
+            let mut messages = self.0.borrow_mut();
+            messages.push((conversation.to_string(), msg.clone()));
+            Ok(messages.len() as u64 - 1)
+        }
+
+        fn range(&self, conversation: &str, before: Option<u64>, limit: usize) -> std::io::Result<Vec<(u64, ChatMessage)>> {
+            let messages = self.0.borrow();
+            let end = before.map_or(messages.len(), |key| key as usize);
+            let mut page: Vec<(u64, ChatMessage)> = messages[..end]
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(_, (name, _))| name == conversation)
+                .take(limit)
+                .map(|(key, (_, msg))| (key as u64, msg.clone()))
+                .collect();
+            page.reverse();
+            Ok(page)
+        }
+
+        fn search(&self, conversation: &str, query: &str, limit: usize) -> std::io::Result<Vec<(u64, ChatMessage)>> {
+            let messages = self.0.borrow();
+            let hits = messages.iter().enumerate().rev().filter(|(_, (name, msg))| name == conversation && msg.content.contains(query));
+            Ok(hits.take(limit).map(|(key, (_, msg))| (key as u64, msg.clone())).collect())
+        }
+    }
+
+    #[test]
+    fn test_message_store_saves_and_pages_in_history() {
+        let mut app = ChatApp::new();
+        let name = app.conversations.active().name().to_string();
+        let mut store = VecStore::default();
+        for i in 0..60 {
+            store.append(&name, &ChatMessage::new("AI", format!("old {i}"))).unwrap();
+        }
+        app.set_message_store(store.clone());
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.chat_area_mut().messages.len(), HISTORY_PAGE);
+        assert_eq!(app.chat_area_mut().messages[0].content, "old 10");
+
+        app.add_message(0, ChatMessage::new("AI", "new"));
+        app.set_simulated_replies(true);
+        app.input_area_mut().insert_str("sent");
+        app.perform(Action::Submit);
+        assert_eq!(store.0.borrow().len(), 63);
+        assert_eq!(store.0.borrow()[61].1.content, "sent");
+
+        // Scrolling to the top loads the rest, keeping the same message at the top.
+        app.chat_area_mut().scroll_up(1000);
+        terminal.draw(|f| app.render(f)).unwrap();
+        let chat = app.chat_area_mut();
+        assert_eq!(chat.messages.len(), 63);
+        assert_eq!(chat.messages[0].content, "old 0");
+        assert_eq!(chat.message_lines[chat.offset], (10, 0));
+        assert_eq!(chat.message(chat.ids[0]).unwrap().content, "old 0");
+        assert_eq!(chat.message(chat.ids[60]).unwrap().content, "new");
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.chat_area_mut().messages.len(), 63);
+
+        let hits = app.search_history("old 4", 2).unwrap();
+        let contents: Vec<&str> = hits.iter().map(|msg| msg.content.as_str()).collect();
+        assert_eq!(contents, ["old 49", "old 48"]);
+    }
+
+    /// Polls a future that never waits to completion.
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+        match std::pin::pin!(future).poll(&mut context) {
+            std::task::Poll::Ready(output) => output,
+            std::task::Poll::Pending => panic!("future was not ready"),
+        }
+    }
+
+    /// A backend that replays scripted events and records what it was sent.
+    #[derive(Default)]
+    struct ScriptedBackend {
+        events: std::collections::VecDeque<BackendEvent>,
+        sent: Vec<(String, String)>,
+        fail_sends: bool,
+    }
+
+    impl ChatBackend for ScriptedBackend {
+        async fn connect(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        async fn send(&mut self, conversation: &str, msg: &ChatMessage) -> std::io::Result<()> {
+            if self.fail_sends {
+                return Err(std::io::Error::other("offline"));
+            }
+            self.sent.push((conversation.to_string(), msg.content.clone()));
+            Ok(())
+        }
+
+        async fn next_event(&mut self) -> Option<BackendEvent> {
+            self.events.pop_front()
+        }
+    }
+
+    #[test]
+    fn test_backend_events_reach_the_widgets_and_sent_messages_the_backend() {
+        let mut app = ChatApp::new();
+        app.set_show_status_bar(true);
+        let name = app.conversations.active().name().to_string();
+        app.conversations.active_mut().members_mut().upsert(Member { name: "bob".into(), ..Member::default() });
+        let mut backend = ScriptedBackend {
+            events: [
+                BackendEvent::Message { conversation: "#rust".into(), message: ChatMessage::new("bob", "hi") },
+                BackendEvent::Typing { conversation: name.clone(), users: vec!["bob".into()] },
+                BackendEvent::Presence { user: "bob".into(), presence: Presence::Online },
+            ]
+            .into(),
+            ..ScriptedBackend::default()
+        };
+        block_on(app.connect_backend(&mut backend)).unwrap();
+        assert_eq!(app.status_bar.as_ref().unwrap().connection(), ConnectionStatus::Connected);
+        while let Some(event) = block_on(backend.next_event()) {
+            app.apply_backend_event(event);
+        }
+        assert_eq!(app.conversations.len(), 2);
+        assert_eq!(app.conversations.get(1).unwrap().chat_area().messages[0].content, "hi");
+        assert_eq!(app.conversations.active().header().typing(), ["bob"]);
+        assert_eq!(app.conversations.active().members().presence("bob"), Some(Presence::Online));
+
+        // Sent messages are queued instead of answered, and kept if sending fails.
+        app.input_area_mut().insert_str("hello");
+        app.perform(Action::Submit);
+        assert_eq!(app.chat_area_mut().messages.len(), 1);
+        backend.fail_sends = true;
+        assert!(block_on(app.send_outgoing(&mut backend)).is_err());
+        backend.fail_sends = false;
+        block_on(app.send_outgoing(&mut backend)).unwrap();
+        assert_eq!(backend.sent, [(name, "hello".to_string())]);
+        assert!(app.take_outgoing().is_empty());
+    }
+
+    #[test]
+    fn test_notify_rules_highlight_and_alert_per_level() {
+        let mut app = ChatApp::new();
+        app.conversations_mut().add_conversation("#ops");
+        let mut rules = NotifyRules::new();
+        rules.set_nick("alice", NotifyLevel::High);
+        rules.add_keyword("deploy", NotifyLevel::Low);
+        app.set_notify_rules(rules);
+
+        app.add_message(1, ChatMessage::new("bob", "deploy started\nstep 1"));
+        app.add_message(1, ChatMessage::new("bob", "lunch?"));
+        let toasts: Vec<&str> = app.toasts.visible().map(|toast| toast.text.as_str()).collect();
+        assert_eq!(toasts, ["bob: deploy started"]);
+        assert!(!app.pending_bell);
+        app.add_message(1, ChatMessage::new("bob", "alice, can you look?"));
+        assert!(app.pending_bell);
+
+        app.add_message(0, ChatMessage::new("bob", "deploy done"));
+        app.add_message(0, ChatMessage::new("bob", "thanks"));
+        let mut terminal = Terminal::new(TestBackend::new(40, 8)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let highlight = ThemePreset::Default.theme().highlight;
+        let chat = app.chat_area_mut();
+        assert_eq!(chat.lines[0].style, highlight);
+        assert_eq!(chat.lines.last().unwrap().style, Style::default());
+    }
+
+    #[test]
+    fn test_code_blocks_scroll_sideways_without_wrapping() {
+        let mut chat = ChatArea::new();
+        chat.set_density(Density::Compact);
+        chat.set_code_no_wrap(true);
+        let id = chat.add_message(ChatMessage::new("AI", "here:\n```\nlet total = prices.iter().sum::<u32>();\n```"));
+        let area = Rect::new(0, 0, 23, 8);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        chat.render_to_buffer(&mut buf, area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows, ["AI: here:", "```", "let total = prices.›", "```"]);
+
+        chat.select_message(Some(id));
+        chat.scroll_code_right(id, 100);
+        chat.render_to_buffer(&mut buf, area);
+        let code_width = "let total = prices.iter().sum::<u32>();".len();
+        assert_eq!(chat.code_offset(id), code_width - 20);
+        assert_eq!(wrap::plain(&chat.lines[2]), "iter().sum::<u32>();");
+        let bottom: String = (0..area.width).map(|x| buf[(x, area.height - 1)].symbol()).collect();
+        assert!(bottom.contains("col 20-39/39"), "{bottom}");
+
+        chat.scroll_code_left(id, 100);
+        chat.render_to_buffer(&mut buf, area);
+        assert_eq!(chat.code_offset(id), 0);
+    }
+
+    #[test]
+    fn test_code_blocks_are_labelled_and_copied_by_number() {
+        let mut app = ChatApp::new();
+        app.set_label_code_blocks(true);
+        app.add_message(0, ChatMessage::new("AI", "Build:\n```sh\ncargo build\n```\nThen:\n```\ncargo test\n```"));
+        let thanks = app.add_message(0, ChatMessage::new("User", "thanks")).unwrap();
+        let mut terminal = Terminal::new(TestBackend::new(40, 16)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let rows: Vec<String> = app.chat_area().lines.iter().map(wrap::plain).collect();
+        assert!(rows.contains(&"```sh [1]".to_string()), "{rows:?}");
+        assert!(rows.contains(&"``` [2]".to_string()), "{rows:?}");
+        assert_eq!(app.chat_area().code_blocks(thanks), Vec::<String>::new());
+
+        // The newest message with code has two blocks.
+        assert!(!app.copy_code_block(3));
+        app.chat_area_mut().select_message(Some(thanks));
+        assert!(!app.copy_code_block(1));
+        let toasts: Vec<&str> = app.toasts.visible().map(|toast| toast.text.as_str()).collect();
+        assert_eq!(toasts, ["No code block 3", "No code block 1"]);
+    }
+
+    #[test]
+    fn test_tool_calls_collapse_to_one_line_until_expanded() {
+        let mut chat = ChatArea::new();
+        chat.set_density(Density::Compact);
+        let call = chat.add_message(ChatMessage::tool_call("AI", "search", r#"{"query":"ratatui","limit":5}"#));
+        chat.add_message(ChatMessage::tool_result("tool", "search", "3 results"));
+        let area = Rect::new(0, 0, 50, 10);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        chat.render_to_buffer(&mut buf, area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows, [r#"AI: ⚙ search {"query": "ratatui", "limit": 5}"#, "tool: ↳ search 3 results"]);
+        let name = chat.lines[0].spans.iter().find(|span| span.content == "⚙ search").unwrap();
+        assert!(name.style.add_modifier.contains(Modifier::BOLD));
+
+        chat.toggle_expanded(call);
+        chat.render_to_buffer(&mut buf, area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows[..4], ["AI: ⚙ search", "{", r#"  "query": "ratatui","#, r#"  "limit": 5"#]);
+    }
+
+    #[test]
+    fn test_reasoning_collapses_to_a_dim_row() {
+        let mut chat = ChatArea::new();
+        chat.set_density(Density::Compact);
+        let field = chat.add_message(ChatMessage::new("AI", "4").with_reasoning("2 + 2 is 4"));
+        chat.add_message(ChatMessage::new("AI", format!("<think>{}</think>\nDone.", "x".repeat(1234))));
+        chat.add_message(ChatMessage::new("AI", "<think>hmm"));
+        let area = Rect::new(0, 0, 40, 10);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        chat.render_to_buffer(&mut buf, area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(
+            rows,
+            ["AI: ▸ reasoning (10 chars)", "4", "AI: ▸ reasoning (1.2k chars)", "Done.", "AI: ▸ reasoning (3 chars)"]
+        );
+        assert_eq!(chat.lines[0].spans[1].style, chat.theme.muted);
+
+        chat.toggle_expanded(field);
+        chat.render_to_buffer(&mut buf, area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows[..3], ["AI: ▾ reasoning", "2 + 2 is 4", "4"]);
+    }
+
+    #[test]
+    fn test_mouse_drag_selects_text_across_messages() {
+        let mut app = ChatApp::new();
+        app.set_copy_on_select(false);
+        app.add_message(0, ChatMessage::new("AI", "hello world"));
+        app.add_message(0, ChatMessage::new("Bob", "second line"));
+        let mut terminal = Terminal::new(TestBackend::new(30, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let area = app.chat_area().text_area;
+        let mouse = |kind, column, row| MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE };
+
+        app.on_mouse(mouse(MouseEventKind::Down(MouseButton::Left), area.x + 4, area.y));
+        app.on_mouse(mouse(MouseEventKind::Drag(MouseButton::Left), area.x + 5, area.y + 1));
+        app.on_mouse(mouse(MouseEventKind::Up(MouseButton::Left), area.x + 5, area.y + 1));
+        assert_eq!(app.chat_area().selected_text().as_deref(), Some("hello world\nBob: s"));
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        assert!(buffer[(area.x + 4, area.y)].modifier.contains(Modifier::REVERSED));
+        assert!(!buffer[(area.x + 3, area.y)].modifier.contains(Modifier::REVERSED));
+        assert!(!buffer[(area.x + 6, area.y + 1)].modifier.contains(Modifier::REVERSED));
+
+        // Dragging off the top selects up to the first column.
+        app.on_mouse(mouse(MouseEventKind::Down(MouseButton::Left), area.x + 4, area.y));
+        app.on_mouse(mouse(MouseEventKind::Drag(MouseButton::Left), area.x, 0));
+        assert_eq!(app.chat_area().selected_text().as_deref(), Some("AI: h"));
+        app.on_mouse(mouse(MouseEventKind::Down(MouseButton::Left), area.x + 4, area.y));
+        assert_eq!(app.chat_area().selected_text(), None);
+    }
+
+    #[test]
+    fn test_double_click_selects_a_word_and_triple_click_the_message() {
+        let mut app = ChatApp::new();
+        app.set_copy_on_select(false);
+        app.add_message(0, ChatMessage::new("AI", "hello world"));
+        app.add_message(0, ChatMessage::new("Bob", "a second line that wraps"));
+        let mut terminal = Terminal::new(TestBackend::new(20, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let area = app.chat_area().text_area;
+        let click = |app: &mut ChatApp, column, row| {
+            for kind in [MouseEventKind::Down(MouseButton::Left), MouseEventKind::Up(MouseButton::Left)] {
+                app.on_mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE });
+            }
+        };
+        click(&mut app, area.x + 12, area.y);
+        click(&mut app, area.x + 12, area.y);
+        assert_eq!(app.chat_area().selected_text().as_deref(), Some("world"));
+        click(&mut app, area.x + 12, area.y);
+        assert_eq!(app.chat_area().selected_text().as_deref(), Some("AI: hello world"));
+
+        for _ in 0..3 {
+            click(&mut app, area.x + 1, area.y + 2);
+        }
+        let rows: Vec<String> = app.chat_area().lines[1..].iter().map(wrap::plain).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(app.chat_area().selected_text(), Some(rows.join("\n")));
+        click(&mut app, area.x + 3, area.y + 1);
+        click(&mut app, area.x + 3, area.y + 1);
+        assert_eq!(app.chat_area().selected_text().as_deref(), Some(":"));
+    }
+
+    #[test]
+    fn test_last_read_divider_and_read_markers() {
+        let mut chat = ChatArea::new();
+        let first = chat.add_message(ChatMessage::new("AI", "one"));
+        let second = chat.add_message(ChatMessage::new("AI", "two"));
+        chat.add_message(ChatMessage::new("AI", "three"));
+        assert_eq!(chat.unread_count(), 3);
+        assert!(chat.set_last_read(Some(first)));
+        assert!(!chat.set_last_read(Some(MessageId(99))));
+        assert_eq!(chat.unread_count(), 2);
+        chat.set_read_marker("bob", first);
+        chat.set_read_marker("eve", second);
+        chat.set_read_marker("bob", second);
+
+        let area = Rect::new(0, 0, 23, 8);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        chat.render_to_buffer(&mut buf, area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows, ["AI: one", "─────── new ────────", "AI: two   ✓ bob, eve", "AI: three"]);
+
+        chat.mark_all_read();
+        assert_eq!(chat.unread_count(), 0);
+        chat.render_to_buffer(&mut buf, area);
+        assert_eq!(chat.lines.len(), 3);
+    }
+
+    #[test]
+    fn test_typing_below_chat_times_out() {
+        let mut app = ChatApp::new();
+        app.set_typing_position(TypingPosition::BelowChat);
+        app.set_typing_timeout(Some(Duration::ZERO));
+        app.set_typing_users(&["alice", "bob"]);
+        let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let row = |terminal: &Terminal<TestBackend>, y: u16| (0..40).map(|x| terminal.backend().buffer()[(x, y)].symbol()).collect::<String>();
+        let typing_row = app.chat_rect.bottom();
+        assert_eq!(row(&terminal, typing_row).trim_end(), " alice and bob are typing…");
+
+        app.tick();
+        assert!(app.conversations.active().header().typing().is_empty());
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.chat_rect.bottom(), typing_row + 1);
+        assert!(!row(&terminal, typing_row).contains("typing"));
+    }
+
+    #[test]
+    fn test_split_view_panes_scroll_and_focus() {
+        let mut app = ChatApp::new();
+        let docs = app.conversations.add_conversation("docs");
+        let third = app.conversations.add_conversation("third");
+        for i in 0..20 {
+            app.add_message(0, ChatMessage::new("AI", format!("chat {i}")));
+            app.add_message(docs, ChatMessage::new("AI", format!("docs {i}")));
+        }
+        app.perform(Action::ToggleSplit);
+        assert_eq!(app.split(), Some([0, docs]));
+        let mut terminal = Terminal::new(TestBackend::new(60, 14)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let top = app.chat_rect.y;
+        let title = |terminal: &Terminal<TestBackend>, x: u16| (x + 1..x + 5).map(|x| terminal.backend().buffer()[(x, top)].symbol()).collect::<String>();
+        assert_eq!(title(&terminal, 0), "Chat");
+        assert_eq!(title(&terminal, 30), "docs");
+        assert_eq!(app.conversations.get(docs).unwrap().unread(), 0);
+
+        // The wheel scrolls the pane under the pointer; a click there moves the focus.
+        let mouse = |kind| MouseEvent { kind, column: 40, row: top + 3, modifiers: KeyModifiers::NONE };
+        app.on_mouse(mouse(MouseEventKind::ScrollUp));
+        assert!(app.chat_area().is_following());
+        assert!(!app.conversations.get(docs).unwrap().chat_area().is_following());
+        app.on_mouse(mouse(MouseEventKind::Down(MouseButton::Left)));
+        assert_eq!(app.conversations.active_index(), docs);
+
+        // Switching conversations replaces the focused pane only.
+        app.conversations.select(third);
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.split(), Some([0, third]));
+        assert_eq!(app.chat_rect.x, 30);
+        app.on_key(KeyEvent::new(KeyCode::F(6), KeyModifiers::NONE));
+        assert_eq!(app.conversations.active_index(), 0);
+
+        app.perform(Action::ToggleSplit);
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.split(), None);
+        assert_eq!(title(&terminal, 0), "Chat");
+        assert_eq!(app.chat_rect.width, 60);
+    }
+
+    #[test]
+    fn test_tick_animates_both_panes() {
+        let mut app = ChatApp::new();
+        let docs = app.conversations.add_conversation("docs");
+        app.set_smooth_scroll(true);
+        for i in 0..20 {
+            app.add_message(docs, ChatMessage::new("AI", format!("docs {i}")));
+        }
+        app.perform(Action::ToggleSplit);
+        let mut terminal = Terminal::new(TestBackend::new(60, 14)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let top = app.chat_rect.y;
+        app.on_mouse(MouseEvent { kind: MouseEventKind::ScrollUp, column: 40, row: top + 3, modifiers: KeyModifiers::NONE });
+        assert!(app.conversations.get(docs).unwrap().chat_area().is_animating());
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert!(!app.needs_redraw());
+
+        app.tick();
+        assert!(app.needs_redraw());
+        for _ in 0..10 {
+            app.tick();
+        }
+        assert!(!app.conversations.get(docs).unwrap().chat_area().is_animating());
+    }
+
+    #[test]
+    fn test_scroll_position_in_title() {
+        let mut chat = ChatArea::new();
+        for i in 0..20 {
+            chat.add_message(ChatMessage::new("AI", format!("line {i}")));
+        }
+        let area = Rect::new(0, 0, 30, 6);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        let title = |buf: &ratatui::buffer::Buffer| (1..29).map(|x| buf[(x, 0)].symbol()).collect::<String>().trim_end_matches(['─', '┐']).to_string();
+        chat.render_to_buffer(&mut buf, area);
+        assert_eq!(title(&buf), "Chat");
+
+        chat.set_show_scroll_position(true);
+        chat.render_to_buffer(&mut buf, area);
+        assert_eq!(title(&buf), "Chat — 20/20 (100%)");
+        chat.scroll_up(10);
+        chat.render_to_buffer(&mut buf, area);
+        assert_eq!(title(&buf), "Chat — 10/20 (50%)");
+
+        chat.set_position_formatter(|line, total| format!("[{line} of {total}]"));
+        chat.render_to_buffer(&mut buf, area);
+        assert_eq!(title(&buf), "Chat [10 of 20]");
+    }
+
+    #[test]
+    fn test_selection_fills_every_row_and_unmatched_are_dimmed() {
+        let mut chat = ChatArea::new();
+        chat.add_message(ChatMessage::new("AI", "deploy the first service now please"));
+        let other = chat.add_message(ChatMessage::new("AI", "unrelated"));
+        let last = chat.add_message(ChatMessage::new("AI", "deploy done"));
+        let area = Rect::new(0, 0, 24, 12);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        chat.set_selection_style(Style::default().bg(Color::Blue));
+        chat.select_message(Some(MessageId(0)));
+        chat.render_to_buffer(&mut buf, area);
+        // Both wrapped rows are filled to the right border.
+        assert!(chat.message_lines.iter().filter(|&&(i, _)| i == 0).count() > 1);
+        for y in 1..=2 {
+            assert_eq!(buf[(1, y)].bg, Color::Blue);
+            assert_eq!(buf[(21, y)].bg, Color::Blue);
+        }
+        assert_ne!(buf[(1, 3)].bg, Color::Blue);
+
+        chat.set_filter("deploy");
+        chat.set_dim_unmatched(true);
+        chat.render_to_buffer(&mut buf, area);
+        let row = chat.message_lines.iter().position(|&(i, _)| i == 1).unwrap() as u16 + 1;
+        assert!(buf[(1, row)].modifier.contains(Modifier::DIM));
+        assert!(!buf[(1, 1)].modifier.contains(Modifier::DIM));
+        assert_eq!(chat.select_prev(), Some(MessageId(0)));
+        assert_eq!(chat.select_next(), Some(last));
+        assert_ne!(chat.selected_message(), Some(other));
+    }
+
+    #[test]
+    fn test_custom_prompts_place_text_and_cursor() {
+        let mut app = ChatApp::new();
+        app.set_input_prompt("❯❯ ", "· ");
+        app.input_area_mut().insert_str("ab\ncd");
+        let mut terminal = Terminal::new(TestBackend::new(20, 10)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let y = app.input_rect.y + 1;
+        let row = |y: u16| (1..8).map(|x| buffer[(x, y)].symbol()).collect::<String>();
+        assert_eq!(row(y), "❯❯ ab  ");
+        assert_eq!(row(y + 1), "· cd   ");
+        assert_eq!(app.get_cursor_pos(), Some((5, y + 1)));
+        app.input_area_mut().cursor_up();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.get_cursor_pos(), Some((6, y)));
+        assert_eq!(app.input_area().prompt_width(0), 3);
+    }
+
+    #[test]
+    fn test_move_and_clear_lines() {
+        let mut app = ChatApp::new();
+        app.input_area_mut().insert_str("one\ntwo\nthree");
+        app.input_area_mut().cursor_up();
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        app.on_key(key(KeyCode::Up, KeyModifiers::ALT));
+        assert_eq!(app.input_area().text(), "two\none\nthree");
+        app.input_area_mut().insert_char('!');
+        assert_eq!(app.input_area().text(), "two!\none\nthree");
+        app.on_key(key(KeyCode::Down, KeyModifiers::ALT));
+        app.on_key(key(KeyCode::Down, KeyModifiers::ALT));
+        app.on_key(key(KeyCode::Down, KeyModifiers::ALT));
+        assert_eq!(app.input_area().text(), "one\nthree\ntwo!");
+        app.input_area_mut().insert_char('?');
+        assert_eq!(app.input_area().text(), "one\nthree\ntwo!?");
+        app.on_key(key(KeyCode::Up, KeyModifiers::ALT));
+        assert_eq!(app.input_area().text(), "one\ntwo!?\nthree");
+        assert_eq!(app.input_area().cursor, "one\ntwo!?".len());
+        app.on_key(key(KeyCode::Char('d'), KeyModifiers::CONTROL));
+        app.input_area_mut().insert_char('.');
+        assert_eq!(app.input_area().text(), "one\ntwo!?\ntwo!?.\nthree");
+        app.input_area_mut().cursor_down();
+        app.on_key(key(KeyCode::Char('d'), KeyModifiers::CONTROL));
+        assert_eq!(app.input_area().text(), "one\ntwo!?\ntwo!?.\nthree\nthree");
+        app.on_key(key(KeyCode::Char('l'), KeyModifiers::CONTROL));
+        assert_eq!(app.input_area().text(), "");
+    }
+
+    #[test]
+    fn test_select_all_replaces_the_draft() {
+        let mut app = ChatApp::new();
+        let ctrl_a = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        app.input_area_mut().insert_str("first draft");
+        app.on_key(ctrl_a);
+        assert!(app.input_area().is_all_selected());
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        app.input_area_mut().render_to_buffer(&mut buf, area);
+        assert!(buf[(3, 1)].modifier.contains(Modifier::REVERSED));
+        assert!(!buf[(1, 1)].modifier.contains(Modifier::REVERSED));
+        app.input_area_mut().insert_str("second");
+        assert_eq!(app.input_area().text(), "second");
+        assert!(!app.input_area().is_all_selected());
+
+        app.on_key(ctrl_a);
+        app.input_area_mut().cursor_left();
+        app.input_area_mut().insert_char('!');
+        assert_eq!(app.input_area().text(), "secon!d");
+        app.on_key(ctrl_a);
+        app.input_area_mut().backspace();
+        assert_eq!(app.input_area().text(), "");
+    }
+
+    #[test]
+    fn test_import_conversations() {
+        let mut app = ChatApp::new();
+        let indices = app.import_conversations(r#"[{"role": "user", "content": "hi"}, {"role": "assistant", "content": "hello"}]"#).unwrap();
+        assert_eq!(indices, [1]);
+        let conversation = app.conversations.get(1).unwrap();
+        assert_eq!(conversation.name(), "Imported");
+        assert_eq!(conversation.chat_area().messages.len(), 2);
+        assert!(app.import_conversations("not json").is_err());
+        assert_eq!(app.conversations.len(), 2);
+
+        app.set_message_filter(|_, msg| msg.content != "hi");
+        let indices = app.import_conversations(r#"[{"role": "user", "content": "hi"}, {"role": "assistant", "content": "hello"}]"#).unwrap();
+        let messages = &app.conversations.get(indices[0]).unwrap().chat_area().messages;
+        assert_eq!(messages.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["hello"]);
+    }
+
+    #[test]
+    fn test_voice_message_placeholder_and_playback() {
+        use std::{cell::RefCell, rc::Rc};
+        let mut app = ChatApp::new();
+        let played = Rc::new(RefCell::new(Vec::new()));
+        let log = played.clone();
+        app.set_on_play_voice(move |conversation, _, source| log.borrow_mut().push((conversation, source.to_string())));
+        let voice = ChatMessage { content: "see you at 5".into(), ..ChatMessage::voice("bob", "media/42.ogg", Some(Duration::from_secs(42))) };
+        app.chat_area_mut().add_message(voice);
+        app.chat_area_mut().add_message(ChatMessage::voice("bob", "media/43.ogg", None));
+        let chat = app.chat_area_mut();
+        let area = Rect::new(0, 0, 40, 8);
+        chat.render_to_buffer(&mut ratatui::buffer::Buffer::empty(area), area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows[..3], ["bob: ▶ Voice message (0:42)", "see you at 5", "bob: ▶ Voice message"]);
+
+        app.perform(Action::SelectMessages);
+        app.on_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(*played.borrow(), [(0, "media/43.ogg".to_string())]);
+        assert!(!app.play_voice(MessageId(99)));
+    }
+
+    #[test]
+    fn test_presence_dots_follow_member_list() {
+        let mut app = ChatApp::new();
+        let members = app.conversations.active_mut().members_mut();
+        members.upsert(Member { name: "alice".into(), presence: Presence::Online, role: None });
+        members.upsert(Member { name: "ci-bot".into(), presence: Presence::Offline, role: Some("bot".into()) });
+        app.add_message(0, ChatMessage::new("alice", "hi"));
+        app.add_message(0, ChatMessage::new("ci-bot", "build failed"));
+        app.add_message(0, ChatMessage::new("carol", "who?"));
+        app.set_presence_in_chat(true);
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let rows: Vec<String> = app.chat_area().lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows, ["● alice: hi", "○ ci-bot: build failed", "carol: who?"]);
+        assert!(app.chat_area().lines[1].style.add_modifier.contains(Modifier::DIM));
+        assert!(!app.chat_area().lines[0].style.add_modifier.contains(Modifier::DIM));
+
+        app.conversations.active_mut().members_mut().set_presence("ci-bot", Presence::Online);
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert!(!app.chat_area().lines[1].style.add_modifier.contains(Modifier::DIM));
+        app.set_presence_in_chat(false);
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(wrap::plain(&app.chat_area().lines[0]), "alice: hi");
+    }
+
+    #[test]
+    fn test_message_detail_opens_on_selected_message() {
+        let mut app = ChatApp::new();
+        assert!(!app.open_message_detail());
+        app.chat_area_mut().add_message(ChatMessage::new("alice", "first"));
+        app.chat_area_mut().add_message(ChatMessage::new("bob", "second"));
+        app.set_show_status_bar(true);
+        app.perform(Action::SelectMessages);
+        app.on_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen = testing::buffer_to_string(terminal.backend().buffer());
+        assert!(screen.contains("i details"), "{screen}");
+        app.on_key(KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE));
+        assert!(app.is_message_detail_open());
+        assert_eq!(app.message_detail.as_ref().unwrap().2.message().content, "first");
+
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.cursor_pos, None);
+        // Keys go to the popup while it is open.
+        app.on_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE));
+        assert!(app.message_detail.as_ref().unwrap().2.is_raw());
+        app.on_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert!(!app.is_message_detail_open());
+        assert_eq!(app.chat_area().selected_message(), Some(MessageId(0)));
+
+        app.on_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        app.perform(Action::OpenMessageDetail);
+        assert_eq!(app.message_detail.as_ref().unwrap().2.message().content, "second");
+    }
+
+    #[test]
+    fn test_message_detail_follows_streaming_and_lets_quit_through() {
+        let mut app = ChatApp::new();
+        let id = app.chat_area_mut().start_streaming(ChatMessage::new("AI", ""));
+        app.chat_area_mut().append_to_message(id, "partial");
+        assert!(app.open_message_detail());
+        app.on_key(KeyEvent::new(KeyCode::Char('/'), KeyModifiers::NONE));
+        app.on_key(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL));
+        assert_eq!(app.message_detail.as_ref().unwrap().2.query(), "");
+        app.chat_area_mut().append_to_message(id, " and the rest");
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.message_detail.as_ref().unwrap().2.message().content, "partial and the rest");
+        app.on_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.should_quit());
+    }
+
+    #[test]
+    fn test_added_conversations_get_app_settings() {
+        let mut app = ChatApp::new();
+        app.set_input_prompt("$ ", ". ");
+        app.set_label_code_blocks(true);
+        app.set_typing_position(TypingPosition::BelowChat);
+        app.apply_settings(Settings { theme: ThemePreset::HighContrast, ..app.settings() });
+        let index = app.add_conversation("later");
+        let conversation = &app.conversations().conversations()[index];
+        assert_eq!(conversation.input_area().prompt(1), ". ");
+        assert!(conversation.chat_area().label_code_blocks);
+        assert_eq!(conversation.header().typing_position(), TypingPosition::BelowChat);
+        assert_eq!(conversation.chat_area().theme, ThemePreset::HighContrast.theme());
+
+        app.apply_backend_event(BackendEvent::Message { conversation: "#new".into(), message: ChatMessage::new("bob", "hi") });
+        let conversation = app.conversations().conversations().last().unwrap();
+        assert_eq!(conversation.name(), "#new");
+        assert_eq!(conversation.input_area().prompt(0), "$ ");
+    }
+
+    #[test]
+    fn test_submit_only_simulates_a_reply_when_enabled() {
+        let mut app = ChatApp::new();
+        app.input_area_mut().insert_str("hi");
+        app.perform(Action::Submit);
+        assert_eq!(app.chat_area().messages.len(), 1);
+        app.set_simulated_replies(true);
+        app.input_area_mut().insert_str("again");
+        app.perform(Action::Submit);
+        assert_eq!(app.chat_area().messages.len(), 3);
+        assert_eq!(app.chat_area().messages[2].sender, "AI");
+    }
+
+    #[test]
+    fn test_message_keys_ignore_modified_chars() {
+        let mut app = ChatApp::new();
+        app.chat_area_mut().add_message(ChatMessage::new("AI", "```\ncode\n```"));
+        app.perform(Action::SelectMessages);
+        let id = app.chat_area().selected_message().unwrap();
+        app.on_key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert!(!app.chat_area().folded.contains(&id));
+        app.on_key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE));
+        assert!(app.chat_area().folded.contains(&id));
+        app.on_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.should_quit());
+    }
+
+    #[test]
+    fn test_streamed_replies_are_saved_when_finished() {
+        let mut app = ChatApp::new();
+        let store = VecStore::default();
+        app.set_message_store(store.clone());
+        let id = app.start_streaming(0, ChatMessage::new("AI", "")).unwrap();
+        assert!(app.append_to_message(0, id, "Hello"));
+        assert!(app.append_to_message(0, id, ", world"));
+        assert!(app.chat_area().is_streaming(id));
+        assert!(store.0.borrow().is_empty());
+        assert!(app.finish_streaming(0, id));
+        assert!(!app.finish_streaming(0, id));
+        assert_eq!(store.0.borrow().len(), 1);
+        assert_eq!(store.0.borrow()[0].1.content, "Hello, world");
+
+        let id = app.start_streaming(0, ChatMessage::new("AI", "cut")).unwrap();
+        app.perform(Action::CancelStreaming);
+        assert!(!app.chat_area().is_streaming(id));
+        assert_eq!(store.0.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_ids_of_prepended_pages_resolve() {
+        let mut chat = ChatArea::new();
+        let newest = chat.add_message(ChatMessage::new("AI", "newest"));
+        let older = chat.prepend_messages(vec![ChatMessage::new("AI", "b1"), ChatMessage::new("AI", "b2")]);
+        let oldest = chat.prepend_messages(vec![ChatMessage::new("AI", "a1"), ChatMessage::new("AI", "a2")]);
+        assert_eq!(chat.message(oldest[1]).unwrap().content, "a2");
+        assert_eq!(chat.message(older[0]).unwrap().content, "b1");
+        assert_eq!(chat.message(newest).unwrap().content, "newest");
+        assert!(chat.message(MessageId(99)).is_none());
+    }
+
+    #[test]
+    fn test_streamed_messages_pass_the_filter() {
+        let mut app = ChatApp::new();
+        app.set_message_filter(|_, msg| {
+            msg.content = msg.content.replace("sk-secret", "[redacted]");
+            msg.sender != "spam" && !msg.content.contains("buy now")
+        });
+        assert!(app.start_streaming(0, ChatMessage::new("spam", "")).is_none());
+        let id = app.start_streaming(0, ChatMessage::new("AI", "key: sk-")).unwrap();
+        app.append_to_message(0, id, "secr");
+        app.append_to_message(0, id, "et!");
+        assert_eq!(app.chat_area().message(id).unwrap().content, "key: [redacted]!");
+        app.finish_streaming(0, id);
+        assert_eq!(app.chat_area().message(id).unwrap().content, "key: [redacted]!");
+        assert_eq!(app.chat_area().messages.len(), 1);
+
+        let rejected = app.start_streaming(0, ChatMessage::new("AI", "buy")).unwrap();
+        app.chat_area_mut().pin_message(rejected);
+        app.append_to_message(0, rejected, " now");
+        assert_eq!(app.chat_area().message(rejected).unwrap().content, "");
+        assert!(app.finish_streaming(0, rejected));
+        assert!(app.chat_area().message(rejected).is_none());
+        assert!(app.chat_area().pinned().is_empty());
+        assert_eq!(app.chat_area().messages.len(), 1);
+        assert_eq!(app.chat_area().message(id).unwrap().content, "key: [redacted]!");
+    }
+
+    #[test]
+    fn test_hidden_spoilers_are_not_announced_or_matched() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let announced = Rc::new(RefCell::new(Vec::new()));
+        let mut app = ChatApp::new();
+        app.chat_area_mut().set_parse_spoilers(true);
+        let sink = announced.clone();
+        app.set_announcer(move |line| sink.borrow_mut().push(line.to_string()));
+        let id = app.add_message(0, ChatMessage::new("bob", "the ||butler|| did it")).unwrap();
+        assert_eq!(*announced.borrow(), vec!["bob: the ▒▒▒▒▒▒ did it"]);
+
+        let chat = app.chat_area_mut();
+        let mut rules = NotifyRules::new();
+        rules.add_keyword("butler", NotifyLevel::Low);
+        chat.set_notify_rules(rules);
+        let area = Rect::new(0, 0, 40, 6);
+        chat.render_to_buffer(&mut ratatui::buffer::Buffer::empty(area), area);
+        let highlight = ThemePreset::Default.theme().highlight;
+        assert_ne!(chat.lines[0].style, highlight);
+        assert!(chat.search("butler", 5).is_empty());
+        chat.set_filter("butler");
+        assert_eq!(chat.filter_matches(), 0);
+        chat.toggle_revealed(id);
+        assert_eq!(chat.filter_matches(), 1);
+        assert_eq!(chat.search("butler", 5), vec![id]);
+        chat.render_to_buffer(&mut ratatui::buffer::Buffer::empty(area), area);
+        assert_eq!(chat.lines[0].style, highlight);
+    }
+
+    #[test]
+    fn test_text_selection_follows_relayout() {
+        let mut app = ChatApp::new();
+        app.set_copy_on_select(false);
+        app.add_message(0, ChatMessage::new("AI", "hello world"));
+        app.add_message(0, ChatMessage::new("Bob", "second line"));
+        let mut terminal = Terminal::new(TestBackend::new(30, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let area = app.chat_area().text_area;
+        for _ in 0..3 {
+            for kind in [MouseEventKind::Down(MouseButton::Left), MouseEventKind::Up(MouseButton::Left)] {
+                app.on_mouse(MouseEvent { kind, column: area.x + 1, row: area.y, modifiers: KeyModifiers::NONE });
+            }
+        }
+        assert_eq!(app.chat_area().selected_text().as_deref(), Some("AI: hello world"));
+
+        app.chat_area_mut().prepend_messages(vec![ChatMessage::new("Old", "older")]);
+        app.add_message(0, ChatMessage::new("Bob", "newer"));
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.chat_area().selected_text().as_deref(), Some("AI: hello world"));
+
+        app.chat_area_mut().set_filter("second");
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(app.chat_area().selected_text(), None);
+    }
+
+    #[test]
+    fn test_voice_caption_renders_like_text() {
+        let mut chat = ChatArea::new();
+        chat.set_parse_spoilers(true);
+        chat.set_render_inline_code(true);
+        chat.add_message(ChatMessage { content: "run `ls` ||now||".into(), ..ChatMessage::voice("bob", "media/42.ogg", None) });
+        let area = Rect::new(0, 0, 40, 6);
+        chat.render_to_buffer(&mut ratatui::buffer::Buffer::empty(area), area);
+        let rows: Vec<String> = chat.lines.iter().map(wrap::plain).collect();
+        assert_eq!(rows[..2], ["bob: ▶ Voice message", "run ls ▒▒▒"]);
+    }
+
+    #[test]
+    fn test_host_presence_wins_over_member_list() {
+        let mut app = ChatApp::new();
+        app.conversations.active_mut().members_mut().upsert(Member { name: "alice".into(), presence: Presence::Offline, role: None });
+        app.conversations.active_mut().members_mut().upsert(Member { name: "bob".into(), presence: Presence::Away, role: None });
+        app.chat_area_mut().set_presence("alice", Presence::Online);
+        app.set_presence_in_chat(true);
+        assert_eq!(app.chat_area().presence("alice"), Some(Presence::Online));
+        assert_eq!(app.chat_area().presence("bob"), Some(Presence::Away));
+
+        app.set_presence_in_chat(false);
+        assert_eq!(app.chat_area().presence("alice"), Some(Presence::Online));
+        assert_eq!(app.chat_area().presence("bob"), None);
+    }
+
+    #[test]
+    fn test_move_split_on_tall_terminals() {
+        let mut app = ChatApp::new();
+        app.input_rect = Rect::new(0, 0, 80, 1000);
+        app.split_rect = Rect::new(0, 0, 80, 2000);
+        app.move_split(5);
+        assert_eq!(app.input_split(), Some(55));
+    }
+
+    #[test]
+    fn test_rows_fit_left_of_the_scrollbar() {
+        let mut chat = ChatArea::new();
+        // 18 columns: one more than fits between the border and the scrollbar.
+        chat.add_message(ChatMessage::new("AI", "abcdefgh ijklm"));
+        let area = Rect::new(0, 0, 20, 6);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        chat.render_to_buffer(&mut buf, area);
+        let row: String = (0..20).map(|x| buf[(x, 1)].symbol()).collect();
+        assert_eq!(row, "│AI: abcdefgh     │ ");
+        let row: String = (0..20).map(|x| buf[(x, 2)].symbol()).collect();
+        assert_eq!(row, "│ijklm            │ ");
+    }
+
+    #[test]
+    fn test_conversation_unread_follows_last_read() {
+        let mut app = ChatApp::new();
+        let docs = app.conversations.add_conversation("docs");
+        let first = app.add_message(docs, ChatMessage::new("AI", "one")).unwrap();
+        app.add_message(docs, ChatMessage::new("AI", "two"));
+        assert_eq!(app.unread_count(), 2);
+        // A read position synced from another device.
+        app.conversations.get_mut(docs).unwrap().chat_area_mut().set_last_read(Some(first));
+        assert_eq!(app.unread_count(), 1);
+
+        app.add_message(0, ChatMessage::new("AI", "seen"));
+        let chat = app.chat_area_mut();
+        chat.set_last_read(None);
+        assert_eq!(chat.unread_count(), 1);
+        app.add_message(0, ChatMessage::new("AI", "also unread"));
+        assert_eq!(app.conversations.active().unread(), 2);
+        app.conversations.select(docs);
+        assert_eq!(app.unread_count(), 2);
+
+        let mut chat = ChatArea::new();
+        chat.set_theme(Theme { plain_glyphs: true, ..Theme::default() });
+        let id = chat.add_message(ChatMessage::new("AI", "hi"));
+        chat.set_read_marker("bob", id);
+        let area = Rect::new(0, 0, 20, 4);
+        chat.render_to_buffer(&mut ratatui::buffer::Buffer::empty(area), area);
+        assert_eq!(wrap::plain(&chat.lines[0]), "AI: hi      v bob");
+    }
+
+    #[test]
+    fn test_code_and_json_colors_come_from_the_theme() {
+        let mut chat = ChatArea::new();
+        chat.set_render_inline_code(true);
+        let code = Style::default().fg(Color::Magenta);
+        let json_key = Style::default().fg(Color::Red);
+        chat.set_theme(Theme { code, json_key, ..Theme::default() });
+        let spans = chat.content_spans(&ChatMessage::new("AI", "run `ls`"));
+        assert!(spans.iter().any(|s| s.content == "ls" && s.style == code));
+        let call = ChatMessage { kind: MessageKind::ToolCall { name: "search".into() }, ..ChatMessage::new("AI", r#"{"q": 1}"#) };
+        let spans = chat.content_spans(&call);
+        assert!(spans.iter().any(|s| s.content == "\"q\"" && s.style == json_key));
+    }
+
+    #[test]
+    fn test_only_real_changes_need_a_redraw() {
+        let mut app = ChatApp::new();
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert!(!app.needs_redraw());
+        let chat = app.conversations.active_mut().chat_area_mut();
+        chat.set_show_timestamps(chat.show_timestamps);
+        app.set_dim_unmatched(false);
+        app.set_show_channels(false);
+        let mut release = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE);
+        release.kind = crossterm::event::KeyEventKind::Release;
+        app.on_key(release);
+        assert!(!app.needs_redraw());
+        app.set_dim_unmatched(true);
+        assert!(app.needs_redraw());
+
+        let mut chat = ChatArea::new();
+        let id = chat.add_message(ChatMessage::new("bob", "hi"));
+        chat.pin_message(id);
+        chat.set_last_read(Some(id));
+        chat.set_read_marker("alice", id);
+        let area = Rect::new(0, 0, 30, 8);
+        chat.render_to_buffer(&mut ratatui::buffer::Buffer::empty(area), area);
+        let unknown = MessageId(99);
+        assert!(!chat.toggle_revealed(unknown) && !chat.toggle_expanded(unknown) && !chat.toggle_folded(unknown));
+        assert!(!chat.pin_message(unknown) && !chat.unpin_message(unknown));
+        assert!(!chat.set_last_read(Some(unknown)) && !chat.set_read_marker("alice", unknown));
+        assert!(!chat.scroll_to_message(unknown, ScrollAlignment::Top));
+        assert!(chat.pin_message(id) && chat.set_last_read(Some(id)) && chat.set_read_marker("alice", id));
+        chat.remove_read_marker("carol");
+        assert!(!chat.needs_redraw());
+        assert!(chat.toggle_folded(id));
+        assert!(chat.needs_redraw());
+    }
+
+    #[cfg(feature = "notifications")]
+    #[test]
+    fn test_desktop_notifications_wait_for_focus_loss() {
+        use std::cell::RefCell;
+
+        thread_local! {
+            static SENT: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+        let mut app = ChatApp::new();
+        app.notifier_mut().set_rule(NotifyRule::AllMessages);
+        app.notifier_mut().set_send(|_, sender, body| {
+            SENT.with_borrow_mut(|sent| sent.push(format!("{sender}: {body}")));
+            true
+        });
+        app.add_message(0, ChatMessage::new("bob", "while focused"));
+        app.on_focus(false);
+        app.add_message(0, ChatMessage::new("bob", "while away"));
+        SENT.with_borrow(|sent| assert_eq!(*sent, ["bob: while away"]));
+    }
+}
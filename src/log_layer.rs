@@ -0,0 +1,124 @@
+//! A `tracing-subscriber` layer that turns log events into chat messages.
+
+use std::{
+    fmt::{self, Write as _},
+    sync::mpsc::{self, Receiver, Sender},
+    time::SystemTime,
+};
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+use tracing_core::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{Layer, layer::Context};
+
+use crate::{ChatArea, ChatMessage};
+
+/// A [`Layer`] that sends every event as a [`ChatMessage`] to a [`LogReceiver`].
+///
+/// The sender is the event's level (`"ERROR"`, `"WARN"`, ...) and the content is
+/// `target: message key=value ...`. Pair it with [`level_prefix`] to color messages by level.
+#[derive(Clone, Debug)]
+pub struct ChatLayer {
+    tx: Sender<ChatMessage>,
+}
+
+/// The receiving end of a [`ChatLayer`], drained into a [`ChatArea`] from the UI thread.
+#[derive(Debug)]
+pub struct LogReceiver {
+    rx: Receiver<ChatMessage>,
+}
+
+impl ChatLayer {
+    pub fn new() -> (Self, LogReceiver) {
+        let (tx, rx) = mpsc::channel();
+        (Self { tx }, LogReceiver { rx })
+    }
+}
+
+impl LogReceiver {
+    /// Adds every message logged since the last call to `chat`. Returns how many were added.
+    pub fn drain_into(&self, chat: &mut ChatArea) -> usize {
+        let mut count = 0;
+        for msg in self.rx.try_iter() {
+            chat.add_message(msg);
+            count += 1;
+        }
+        count
+    }
+}
+
+/// Collects the `message` field and the remaining fields as `key=value`.
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ChatLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let meta = event.metadata();
+        let content = format!("{}: {}{}", meta.target(), visitor.message, visitor.fields);
+        // The receiver being dropped just means nobody is watching the console any more.
+        let _ = self.tx.send(ChatMessage::new(meta.level().as_str(), content).with_timestamp(SystemTime::now()));
+    }
+}
+
+/// A prefix formatter for [`ChatArea::set_prefix_formatter`] that colors the level
+/// sent by [`ChatLayer`].
+pub fn level_prefix(msg: &ChatMessage) -> Span<'static> {
+    let color = match msg.sender.parse::<Level>() {
+        Ok(Level::ERROR) => Color::Red,
+        Ok(Level::WARN) => Color::Yellow,
+        Ok(Level::INFO) => Color::Green,
+        Ok(Level::DEBUG) => Color::Blue,
+        Ok(Level::TRACE) => Color::Magenta,
+        Err(_) => return Span::raw(format!("{}: ", msg.sender)),
+    };
+    Span::styled(format!("{:<5} ", msg.sender), Style::default().fg(color).add_modifier(Modifier::BOLD))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_events_become_messages() {
+        let (layer, receiver) = ChatLayer::new();
+        let subscriber = tracing_subscriber::registry::Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "net", retries = 3, "reconnecting");
+        });
+        let mut chat = ChatArea::new();
+        assert_eq!(receiver.drain_into(&mut chat), 1);
+        let msg = &chat.messages[0];
+        assert_eq!(msg.sender, "WARN");
+        assert_eq!(msg.content, "net: reconnecting retries=3");
+        assert_eq!(level_prefix(msg).style.fg, Some(Color::Yellow));
+    }
+}
@@ -0,0 +1,297 @@
+//! A small markdown renderer for previewing what a message will look like.
+//!
+//! Handles the subset people type in chat: headings, emphasis, inline code, fenced code
+//! blocks, block quotes, lists and links. Anything it does not recognise is shown as is.
+
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use crate::Theme;
+
+/// Renders markdown `text` into styled lines, one per source line. Wrapping is left to the caller.
+pub(crate) fn render(text: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in text.split('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            // Show the fence as a dim rule, with the language on the opening one.
+            let lang = trimmed.trim_start_matches('`').trim();
+            let rule = theme.glyph("─", "-").repeat(3);
+            lines.push(Line::styled(if in_code { format!("{rule} {lang}") } else { rule }, theme.muted));
+            continue;
+        }
+        if in_code {
+            lines.push(Line::from(vec![Span::raw("  "), Span::styled(line.to_string(), theme.code)]));
+            continue;
+        }
+        lines.push(render_line(line, theme));
+    }
+    lines
+}
+
+/// Renders one line outside a code block.
+fn render_line(line: &str, theme: &Theme) -> Line<'static> {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let rest = line.trim_start();
+    let level = rest.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&level) && rest[level..].starts_with(' ') {
+        let mut style = Style::default().add_modifier(Modifier::BOLD);
+        if level == 1 {
+            style = style.add_modifier(Modifier::UNDERLINED);
+        }
+        return Line::from(inline(rest[level..].trim(), style, theme.code));
+    }
+    if let Some(quote) = rest.strip_prefix('>') {
+        let mut spans = vec![Span::styled(theme.glyph("│ ", "| "), theme.muted)];
+        spans.extend(inline(quote.trim_start(), Style::default().add_modifier(Modifier::ITALIC), theme.code));
+        return Line::from(spans);
+    }
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = rest.strip_prefix(bullet) {
+            let mut spans = vec![Span::raw(format!("{indent}{}", theme.glyph("• ", "* ")))];
+            spans.extend(inline(item, Style::default(), theme.code));
+            return Line::from(spans);
+        }
+    }
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && rest[digits..].starts_with(". ") {
+        let mut spans = vec![Span::raw(format!("{indent}{} ", &rest[..digits + 1]))];
+        spans.extend(inline(&rest[digits + 2..], Style::default(), theme.code));
+        return Line::from(spans);
+    }
+    let mut spans = vec![Span::raw(indent.to_string())];
+    spans.extend(inline(rest, Style::default(), theme.code));
+    Line::from(spans)
+}
+
+/// Renders inline markup: `code`, **bold**, *italic* or _italic_, ~~strikethrough~~ and
+/// [links](url). Unclosed markers are shown literally.
+pub(crate) fn inline(text: &str, base: Style, code: Style) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let parsed = match c {
+            '`' => delimited(rest, "`").map(|(inner, after)| (vec![Span::styled(inner.to_string(), base.patch(code))], after)),
+            '*' | '_' | '~' => [("**", Modifier::BOLD), ("__", Modifier::BOLD), ("~~", Modifier::CROSSED_OUT), ("*", Modifier::ITALIC), ("_", Modifier::ITALIC)]
+                .into_iter()
+                .find_map(|(marker, modifier)| {
+                    delimited(rest, marker).map(|(inner, after)| (inline(inner, base.add_modifier(modifier), code), after))
+                }),
+            '[' => link(rest).map(|(label, after)| (inline(label, base.add_modifier(Modifier::UNDERLINED), code), after)),
+            _ => None,
+        };
+        match parsed {
+            Some((inner, after)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), base));
+                }
+                spans.extend(inner);
+                rest = after;
+            }
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::styled(plain, base));
+    }
+    spans
+}
+
+/// Styled spans for `content` with `inline code` in `style` and its backticks
+/// dropped, without any other markdown. A span opened by a run of backticks closes at the
+/// next run of the same length, so ``` `` a`b `` ``` works. Fenced code blocks are left as
+/// written. Returns `None` if there is no inline code.
+pub(crate) fn code_spans(content: &str, style: Style) -> Option<Vec<Span<'static>>> {
+    let mut spans = Vec::new();
+    let mut found = false;
+    let mut in_block = false;
+    for (i, line) in content.split('\n').enumerate() {
+        if i > 0 {
+            spans.push(Span::raw("\n"));
+        }
+        let fence = line.trim_start().starts_with("```");
+        in_block ^= fence;
+        if fence || in_block {
+            spans.push(Span::raw(line.to_string()));
+            continue;
+        }
+        let mut plain = String::new();
+        let mut rest = line;
+        while let Some(start) = rest.find('`') {
+            let run = backticks(&rest[start..]);
+            let after = &rest[start + run..];
+            match closing(after, run) {
+                Some(end) if !after[..end].trim().is_empty() => {
+                    plain.push_str(&rest[..start]);
+                    if !plain.is_empty() {
+                        spans.push(Span::raw(std::mem::take(&mut plain)));
+                    }
+                    // Like CommonMark, one space of padding on both sides is not part of the code.
+                    let code = &after[..end];
+                    let code = code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')).unwrap_or(code);
+                    spans.push(Span::styled(code.to_string(), style));
+                    rest = &after[end + run..];
+                    found = true;
+                }
+                _ => {
+                    plain.push_str(&rest[..start + run]);
+                    rest = after;
+                }
+            }
+        }
+        plain.push_str(rest);
+        if !plain.is_empty() {
+            spans.push(Span::raw(plain));
+        }
+    }
+    found.then_some(spans)
+}
+
+/// Indices of the lines of `content` inside fenced code blocks, not counting the fences.
+pub(crate) fn code_lines(content: &str) -> Vec<usize> {
+    let mut in_block = false;
+    let mut lines = Vec::new();
+    for (i, line) in content.split('\n').enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_block = !in_block;
+        } else if in_block {
+            lines.push(i);
+        }
+    }
+    lines
+}
+
+/// The contents of the fenced code blocks in `content`, without the fences. An unclosed
+/// block runs to the end.
+pub(crate) fn code_blocks(content: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut block: Option<Vec<&str>> = None;
+    for line in content.split('\n') {
+        if line.trim_start().starts_with("```") {
+            match block.take() {
+                Some(lines) => blocks.push(lines.join("\n")),
+                None => block = Some(Vec::new()),
+            }
+        } else if let Some(lines) = &mut block {
+            lines.push(line);
+        }
+    }
+    blocks.extend(block.map(|lines| lines.join("\n")));
+    blocks
+}
+
+/// `content` with each opening code fence numbered, as in ```` ```rust [1] ````.
+pub(crate) fn label_code_blocks(content: &str) -> String {
+    let mut in_block = false;
+    let mut count = 0;
+    let lines: Vec<String> = content
+        .split('\n')
+        .map(|line| {
+            if !line.trim_start().starts_with("```") {
+                return line.to_string();
+            }
+            in_block = !in_block;
+            if !in_block {
+                return line.to_string();
+            }
+            count += 1;
+            format!("{} [{count}]", line.trim_end())
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Length of the run of backticks `text` starts with.
+fn backticks(text: &str) -> usize {
+    text.bytes().take_while(|&b| b == b'`').count()
+}
+
+/// Where in `text` a run of exactly `run` backticks starts.
+fn closing(text: &str, run: usize) -> Option<usize> {
+    let mut from = 0;
+    while let Some(pos) = text[from..].find('`') {
+        let start = from + pos;
+        let len = backticks(&text[start..]);
+        if len == run {
+            return Some(start);
+        }
+        from = start + len;
+    }
+    None
+}
+
+/// Splits `text`, which starts with `marker`, into the non-empty content up to the closing
+/// marker and what follows it.
+fn delimited<'a>(text: &'a str, marker: &str) -> Option<(&'a str, &'a str)> {
+    let body = text.strip_prefix(marker)?;
+    let end = body.find(marker)?;
+    // `**` must not close a `*` span, and emphasis must hug its content.
+    if end == 0 || body[..end].starts_with(' ') || body[..end].ends_with(' ') {
+        return None;
+    }
+    Some((&body[..end], &body[end + marker.len()..]))
+}
+
+/// Splits a `[label](url)` link at the start of `text` into its label and what follows.
+fn link(text: &str) -> Option<(&str, &str)> {
+    let close = text.find("](")?;
+    let end = text[close..].find(')')? + close;
+    Some((&text[1..close], &text[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::*;
+
+    fn plain(line: &Line<'_>) -> String {
+        line.spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_renders_common_markdown() {
+        let theme = Theme::default();
+        let lines = render("# Title\n- **bold** and `code`\n> quote\n```rust\nlet x = 1;\n```\n2 * 3 * 4", &theme);
+        let text: Vec<String> = lines.iter().map(plain).collect();
+        assert_eq!(text, ["Title", "• bold and code", "│ quote", "─── rust", "  let x = 1;", "───", "2 * 3 * 4"]);
+        assert!(lines[0].spans[0].style.add_modifier.contains(Modifier::UNDERLINED));
+        assert!(lines[1].spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(lines[1].spans[3].style.fg, Some(Color::Cyan));
+
+        let spans = inline("see [docs](https://x.y) ~~old~~ *it*", Style::default(), Theme::default().code);
+        let parts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(parts, ["see ", "docs", " ", "old", " ", "it"]);
+        assert!(spans[1].style.add_modifier.contains(Modifier::UNDERLINED));
+        assert!(spans[5].style.add_modifier.contains(Modifier::ITALIC));
+    }
+
+    #[test]
+    fn test_code_spans_only() {
+        let spans = code_spans("run `cargo test` and ``a ` b`` but *not* this\n```\n`x`\n```", Theme::default().code).unwrap();
+        let parts: Vec<&str> = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(parts, ["run ", "cargo test", " and ", "a ` b", " but *not* this", "\n", "```", "\n", "`x`", "\n", "```"]);
+        assert_eq!(spans[1].style.fg, Some(Color::Cyan));
+        assert_eq!(spans[8].style, Style::default());
+        assert!(code_spans("a lone ` backtick and `` ``", Theme::default().code).is_none());
+    }
+
+    #[test]
+    fn test_code_blocks_are_extracted_and_labelled() {
+        let content = "Try:\n```rust\nfn a() {}\n\nfn b() {}\n```\nor\n  ```\nls\n  ```\n```sh\nopen";
+        assert_eq!(code_blocks(content), ["fn a() {}\n\nfn b() {}", "ls", "open"]);
+        assert_eq!(
+            label_code_blocks(content),
+            "Try:\n```rust [1]\nfn a() {}\n\nfn b() {}\n```\nor\n  ``` [2]\nls\n  ```\n```sh [3]\nopen"
+        );
+        assert!(code_blocks("no code").is_empty());
+    }
+}
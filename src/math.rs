@@ -0,0 +1,259 @@
+//! Simple LaTeX-style inline math, such as `$x^2 + \alpha$`, rewritten in unicode.
+
+const SYMBOLS: &[(&str, &str)] = &[
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("iota", "ι"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("pi", "π"),
+    ("rho", "ρ"),
+    ("sigma", "σ"),
+    ("tau", "τ"),
+    ("upsilon", "υ"),
+    ("phi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Theta", "Θ"),
+    ("Lambda", "Λ"),
+    ("Xi", "Ξ"),
+    ("Pi", "Π"),
+    ("Sigma", "Σ"),
+    ("Phi", "Φ"),
+    ("Psi", "Ψ"),
+    ("Omega", "Ω"),
+    ("cdot", "·"),
+    ("times", "×"),
+    ("div", "÷"),
+    ("pm", "±"),
+    ("leq", "≤"),
+    ("le", "≤"),
+    ("geq", "≥"),
+    ("ge", "≥"),
+    ("neq", "≠"),
+    ("ne", "≠"),
+    ("approx", "≈"),
+    ("equiv", "≡"),
+    ("infty", "∞"),
+    ("partial", "∂"),
+    ("nabla", "∇"),
+    ("sum", "∑"),
+    ("prod", "∏"),
+    ("int", "∫"),
+    ("in", "∈"),
+    ("to", "→"),
+    ("rightarrow", "→"),
+    ("leftarrow", "←"),
+    ("Rightarrow", "⇒"),
+    ("forall", "∀"),
+    ("exists", "∃"),
+    ("ldots", "…"),
+    ("dots", "…"),
+];
+
+const SUPERSCRIPTS: &[(char, char)] = &[
+    ('0', '⁰'),
+    ('1', '¹'),
+    ('2', '²'),
+    ('3', '³'),
+    ('4', '⁴'),
+    ('5', '⁵'),
+    ('6', '⁶'),
+    ('7', '⁷'),
+    ('8', '⁸'),
+    ('9', '⁹'),
+    ('+', '⁺'),
+    ('-', '⁻'),
+    ('=', '⁼'),
+    ('(', '⁽'),
+    (')', '⁾'),
+    ('i', 'ⁱ'),
+    ('n', 'ⁿ'),
+    ('x', 'ˣ'),
+    ('y', 'ʸ'),
+    ('k', 'ᵏ'),
+    ('T', 'ᵀ'),
+];
+
+const SUBSCRIPTS: &[(char, char)] = &[
+    ('0', '₀'),
+    ('1', '₁'),
+    ('2', '₂'),
+    ('3', '₃'),
+    ('4', '₄'),
+    ('5', '₅'),
+    ('6', '₆'),
+    ('7', '₇'),
+    ('8', '₈'),
+    ('9', '₉'),
+    ('+', '₊'),
+    ('-', '₋'),
+    ('=', '₌'),
+    ('(', '₍'),
+    (')', '₎'),
+    ('i', 'ᵢ'),
+    ('j', 'ⱼ'),
+    ('k', 'ₖ'),
+    ('n', 'ₙ'),
+    ('x', 'ₓ'),
+];
+
+/// Rewrites each `$…$` span in `content` in unicode: Greek letters and common operators,
+/// `^` and `_` scripts, `\frac{a}{b}` and `\sqrt{x}`. A span using anything else is left
+/// as written, dollar signs included. Code blocks and inline code are not touched, and a
+/// `$` followed by a space, as in prices, does not start a span.
+pub(crate) fn render(content: &str) -> String {
+    let mut in_code = false;
+    let lines: Vec<String> = content
+        .split('\n')
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+            }
+            if in_code || !line.contains('$') {
+                return line.to_string();
+            }
+            // Odd pieces between backticks are inline code.
+            let pieces: Vec<String> =
+                line.split('`').enumerate().map(|(i, piece)| if i % 2 == 0 { spans(piece) } else { piece.to_string() }).collect();
+            pieces.join("`")
+        })
+        .collect();
+    lines.join("\n")
+}
+
+fn spans(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let found = after.find('$').map(|len| &after[..len]);
+        match found {
+            Some(inner) if !inner.is_empty() && !inner.starts_with(' ') && !inner.ends_with(' ') => {
+                match convert(inner) {
+                    Some(converted) => out.push_str(&converted),
+                    None => out.push_str(&rest[start..start + inner.len() + 2]),
+                }
+                rest = &after[inner.len() + 1..];
+            }
+            _ => {
+                out.push('$');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The unicode form of a math expression, or `None` if it uses anything unsupported.
+fn convert(math: &str) -> Option<String> {
+    let chars: Vec<char> = math.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                let name: String = chars[i + 1..].iter().take_while(|c| c.is_ascii_alphabetic()).collect();
+                i += 1 + name.chars().count();
+                match name.as_str() {
+                    "frac" => {
+                        let (numerator, next) = group(&chars, i)?;
+                        let (denominator, next) = group(&chars, next)?;
+                        i = next;
+                        out.push_str(&format!("{}/{}", parenthesize(&convert(&numerator)?), parenthesize(&convert(&denominator)?)));
+                    }
+                    "sqrt" => {
+                        let (radicand, next) = group(&chars, i)?;
+                        i = next;
+                        out.push_str(&format!("√{}", parenthesize(&convert(&radicand)?)));
+                    }
+                    "" => {
+                        // Escaped punctuation such as `\{` or `\,`.
+                        let c = *chars.get(i)?;
+                        i += 1;
+                        match c {
+                            '{' | '}' | '$' | '%' | '_' | '&' | '#' => out.push(c),
+                            ',' | ';' | ' ' => out.push(' '),
+                            _ => return None,
+                        }
+                    }
+                    name => out.push_str(SYMBOLS.iter().find(|(command, _)| *command == name)?.1),
+                }
+            }
+            c @ ('^' | '_') => {
+                let (script, next) = group(&chars, i + 1)?;
+                i = next;
+                let table = if c == '^' { SUPERSCRIPTS } else { SUBSCRIPTS };
+                for c in convert(&script)?.chars() {
+                    out.push(table.iter().find(|(plain, _)| *plain == c)?.1);
+                }
+            }
+            '{' => {
+                let (inner, next) = group(&chars, i)?;
+                i = next;
+                out.push_str(&convert(&inner)?);
+            }
+            '}' => return None,
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    Some(out)
+}
+
+/// The braced group or single character at `start`, and the index after it.
+fn group(chars: &[char], start: usize) -> Option<(String, usize)> {
+    if *chars.get(start)? != '{' {
+        return Some((chars[start].to_string(), start + 1));
+    }
+    let mut depth = 0;
+    for (i, &c) in chars.iter().enumerate().skip(start) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((chars[start + 1..i].iter().collect(), i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parenthesize(text: &str) -> String {
+    if text.chars().count() > 1 && text.chars().any(|c| !c.is_alphanumeric()) { format!("({text})") } else { text.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_math_becomes_unicode() {
+        assert_eq!(render(r"so $x^2 + y_{10} \leq \alpha$ holds"), "so x² + y₁₀ ≤ α holds");
+        assert_eq!(render(r"$\frac{a+b}{2}$ and $\sqrt{x}$"), "(a+b)/2 and √x");
+        assert_eq!(render(r"$e^{in}$, $\Sigma_{k}$"), "eⁱⁿ, Σₖ");
+        // Unsupported constructs, prices and code stay as written.
+        assert_eq!(render(r"$\mathbb{R}$ and $x^q$"), r"$\mathbb{R}$ and $x^q$");
+        assert_eq!(render("costs $5 or $10"), "costs $5 or $10");
+        assert_eq!(render("`$x^2$`\n```\n$\\alpha$\n```"), "`$x^2$`\n```\n$\\alpha$\n```");
+    }
+}
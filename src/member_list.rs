@@ -0,0 +1,223 @@
+//! Participant list with presence indicators.
+
+use std::collections::HashMap;
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState},
+};
+
+use crate::{Strings, Theme, strings};
+
+/// Availability of a participant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Presence {
+    Online,
+    Away,
+    #[default]
+    Offline,
+}
+
+impl Presence {
+    /// The status dot shown before a member's name.
+    pub fn symbol(self) -> Span<'static> {
+        match self {
+            Presence::Online => Span::styled("●", Style::default().fg(Color::Green)),
+            Presence::Away => Span::styled("●", Style::default().fg(Color::Yellow)),
+            Presence::Offline => Span::styled("○", Style::default().fg(Color::DarkGray)),
+        }
+    }
+
+    /// An ASCII stand-in for [`Presence::symbol`].
+    pub fn plain_symbol(self) -> Span<'static> {
+        match self {
+            Presence::Online => Span::raw("+"),
+            Presence::Away => Span::raw("~"),
+            Presence::Offline => Span::raw("-"),
+        }
+    }
+}
+
+/// A participant in a conversation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    pub presence: Presence,
+    /// Free-form role such as `"admin"` or `"bot"`, styled via [`MemberList::set_role_style`].
+    pub role: Option<String>,
+}
+
+/// A widget listing participants, grouped by presence and sorted by name.
+pub struct MemberList {
+    members: Vec<Member>,
+    role_styles: HashMap<String, Style>,
+    filter: String,
+    state: ListState,
+    strings: Strings,
+    theme: Theme,
+    // Whether the members changed since the last take_changed.
+    changed: bool,
+}
+
+impl Default for MemberList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemberList {
+    pub fn new() -> Self {
+        Self {
+            members: Vec::new(),
+            role_styles: HashMap::new(),
+            filter: String::new(),
+            state: ListState::default(),
+            strings: Strings::default(),
+            theme: Theme::default(),
+            changed: false,
+        }
+    }
+
+    /// Sets the theme used for the border and presence indicators.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    pub fn set_members(&mut self, members: Vec<Member>) {
+        self.changed = true;
+        self.members = members;
+    }
+
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// Adds a member, or replaces the existing member with the same name.
+    pub fn upsert(&mut self, member: Member) {
+        self.changed = true;
+        match self.members.iter_mut().find(|m| m.name == member.name) {
+            Some(existing) => *existing = member,
+            None => self.members.push(member),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.changed = true;
+        self.members.retain(|m| m.name != name);
+    }
+
+    /// Updates the presence of a member. Returns `false` if no member has that name.
+    pub fn set_presence(&mut self, name: &str, presence: Presence) -> bool {
+        match self.members.iter_mut().find(|m| m.name == name) {
+            Some(member) => {
+                self.changed |= member.presence != presence;
+                member.presence = presence;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn presence(&self, name: &str) -> Option<Presence> {
+        self.members.iter().find(|m| m.name == name).map(|m| m.presence)
+    }
+
+    /// Whether the members or their presence changed since the last call.
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    /// Sets the style used for names of members with the given role.
+    pub fn set_role_style(&mut self, role: impl Into<String>, style: Style) {
+        self.role_styles.insert(role.into(), style);
+    }
+
+    pub fn set_filter(&mut self, filter: impl Into<String>) {
+        self.filter = filter.into();
+        *self.state.offset_mut() = 0;
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Members matching the filter, online first, then away, then offline.
+    pub fn visible(&self) -> Vec<&Member> {
+        let filter = self.filter.to_lowercase();
+        let mut visible: Vec<&Member> = self
+            .members
+            .iter()
+            .filter(|m| m.name.to_lowercase().contains(&filter))
+            .collect();
+        visible.sort_by(|a, b| a.presence.cmp(&b.presence).then_with(|| a.name.cmp(&b.name)));
+        visible
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        *self.state.offset_mut() = self.state.offset().saturating_sub(lines);
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        let max = self.visible().len().saturating_sub(1);
+        *self.state.offset_mut() = (self.state.offset() + lines).min(max);
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let visible = self.visible();
+        let online = visible.iter().filter(|m| m.presence != Presence::Offline).count();
+        let items: Vec<ListItem> = visible
+            .iter()
+            .map(|m| {
+                let style = m
+                    .role
+                    .as_ref()
+                    .and_then(|role| self.role_styles.get(role))
+                    .copied()
+                    .unwrap_or_default();
+                let style = if m.presence == Presence::Offline {
+                    style.fg(Color::DarkGray)
+                } else {
+                    style
+                };
+                let symbol = if self.theme.plain_glyphs { m.presence.plain_symbol() } else { m.presence.symbol() };
+                ListItem::new(Line::from(vec![symbol, Span::raw(" "), Span::styled(m.name.clone(), style)]))
+            })
+            .collect();
+        let title = strings::fill(&self.strings.members_title, &[("online", &online), ("total", &visible.len())]);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(self.theme.border)
+            .border_set(self.theme.border_set())
+            .title(title)
+            .title_style(self.theme.title);
+        let list = List::new(items).block(block);
+        frame.render_stateful_widget(list, area, &mut self.state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_groups_by_presence_and_filters() {
+        let mut list = MemberList::new();
+        for (name, presence) in [("zed", Presence::Online), ("amy", Presence::Offline), ("bob", Presence::Away), ("abe", Presence::Online)] {
+            list.upsert(Member { name: name.to_string(), presence, role: None });
+        }
+        let names: Vec<&str> = list.visible().iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["abe", "zed", "bob", "amy"]);
+
+        assert!(list.set_presence("amy", Presence::Online));
+        list.set_filter("A");
+        let names: Vec<&str> = list.visible().iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["abe", "amy"]);
+    }
+}
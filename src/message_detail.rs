@@ -0,0 +1,372 @@
+//! Popup showing one message in full, with search, its raw source and copying.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{
+    ChatMessage, MessageKind, Strings, Theme, ThemePreset, keymap, markdown, metadata_summary, reasoning, search, strings,
+    timestamp, tool_call, wrap,
+};
+
+/// What [`MessageDetail::on_key`] asks its owner to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetailAction {
+    Close,
+    /// Copy the message's raw content.
+    CopyMessage,
+    /// Copy fenced code block N, counting from 1.
+    CopyCodeBlock(usize),
+}
+
+/// The wrapped rows for one width, query and view, and the first row of every line with a
+/// match.
+#[derive(Clone, Debug)]
+struct Rows {
+    width: usize,
+    query: String,
+    raw: bool,
+    rows: Vec<Line<'static>>,
+    matches: Vec<usize>,
+}
+
+/// A large popup showing a message unwrapped and unfolded, scrolled on its own.
+///
+/// The view starts with the sender, time and metadata, followed by the reasoning and the
+/// rendered content, or with `r` the content as sent. `/` searches it, highlighting every
+/// match, and `n`/`N` jump between the matching lines.
+#[derive(Clone, Debug)]
+pub struct MessageDetail {
+    message: ChatMessage,
+    raw: bool,
+    offset: usize,
+    query: String,
+    searching: bool,
+    // The inner size at the last render, for paging and jumping to matches.
+    width: usize,
+    height: usize,
+    cache: Option<Rows>,
+    strings: Strings,
+    theme: Theme,
+    #[cfg(feature = "chrono")]
+    time_format: timestamp::TimeFormat,
+}
+
+impl MessageDetail {
+    pub fn new(message: ChatMessage) -> Self {
+        Self {
+            message,
+            raw: false,
+            offset: 0,
+            query: String::new(),
+            searching: false,
+            width: 80,
+            height: 20,
+            cache: None,
+            strings: Strings::default(),
+            theme: ThemePreset::default().theme(),
+            #[cfg(feature = "chrono")]
+            time_format: timestamp::TimeFormat::default(),
+        }
+    }
+
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Sets the timezone and pattern the message's time is shown in.
+    #[cfg(feature = "chrono")]
+    pub fn set_time_format(&mut self, time_format: timestamp::TimeFormat) {
+        self.time_format = time_format;
+    }
+
+    pub fn message(&self) -> &ChatMessage {
+        &self.message
+    }
+
+    /// Replaces the message shown, keeping the scroll position and search, e.g. as more of
+    /// a streamed reply arrives.
+    pub fn set_message(&mut self, message: ChatMessage) {
+        self.message = message;
+        self.cache = None;
+    }
+
+    /// Whether the content is shown as sent rather than rendered.
+    pub fn is_raw(&self) -> bool {
+        self.raw
+    }
+
+    pub fn toggle_raw(&mut self) {
+        self.raw = !self.raw;
+    }
+
+    /// Highlights the case-insensitive occurrences of `query`; empty clears the search.
+    pub fn set_query(&mut self, query: &str) {
+        self.query = query.to_string();
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Index of the first shown row.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Handles a key, returning what the owner should do, if anything.
+    ///
+    /// Up/Down, PageUp/PageDown and Home/End scroll, `r` toggles the raw source, `/` starts
+    /// a search and `n`/`N` move to the next or previous matching line. `y` copies the
+    /// message and 1-9 copy its code blocks. Esc or `q` closes the popup.
+    pub fn on_key(&mut self, key: KeyEvent) -> Option<DetailAction> {
+        if self.searching {
+            match key.code {
+                KeyCode::Enter => {
+                    self.searching = false;
+                    self.next_match(true);
+                }
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.query.clear();
+                }
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Char(c) if keymap::is_text(&key) => self.query.push(c),
+                _ => {}
+            }
+            return None;
+        }
+        if matches!(key.code, KeyCode::Char(_)) && !keymap::is_text(&key) {
+            return None;
+        }
+        let page = self.height.saturating_sub(1).max(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Some(DetailAction::Close),
+            KeyCode::Up | KeyCode::Char('k') => self.offset = self.offset.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.offset = self.offset.saturating_add(1),
+            KeyCode::PageUp => self.offset = self.offset.saturating_sub(page),
+            KeyCode::PageDown => self.offset = self.offset.saturating_add(page),
+            KeyCode::Home | KeyCode::Char('g') => self.offset = 0,
+            KeyCode::End | KeyCode::Char('G') => self.offset = usize::MAX,
+            KeyCode::Char('r') => self.toggle_raw(),
+            KeyCode::Char('/') => {
+                self.searching = true;
+                self.query.clear();
+            }
+            KeyCode::Char('n') => self.next_match(true),
+            KeyCode::Char('N') => self.next_match(false),
+            KeyCode::Char('y') => return Some(DetailAction::CopyMessage),
+            KeyCode::Char(c @ '1'..='9') => return Some(DetailAction::CopyCodeBlock(c as usize - '0' as usize)),
+            _ => {}
+        }
+        self.offset = self.offset.min(self.max_offset());
+        None
+    }
+
+    /// Scrolls to the nearest matching line below the top row, or above it if not `forward`,
+    /// wrapping around at either end.
+    fn next_match(&mut self, forward: bool) {
+        let (offset, height) = (self.offset, self.height);
+        let Rows { rows, matches, .. } = self.rows();
+        let (Some(&first), Some(&last)) = (matches.first(), matches.last()) else {
+            return;
+        };
+        let target = if forward {
+            matches.iter().copied().find(|&row| row > offset).unwrap_or(first)
+        } else {
+            matches.iter().copied().rev().find(|&row| row < offset).unwrap_or(last)
+        };
+        self.offset = target.min(rows.len().saturating_sub(height));
+    }
+
+    fn max_offset(&mut self) -> usize {
+        let height = self.height;
+        self.rows().rows.len().saturating_sub(height)
+    }
+
+    /// The header rows naming the sender, time, kind and metadata, before the content.
+    fn header(&self) -> Vec<Line<'static>> {
+        let msg = &self.message;
+        let mut title = vec![Span::styled(msg.sender.clone(), self.theme.sender.add_modifier(Modifier::BOLD))];
+        if let Some(time) = msg.timestamp {
+            #[cfg(feature = "chrono")]
+            let time = self.time_format.format_date_time(time);
+            #[cfg(not(feature = "chrono"))]
+            let time = format!("{} UTC", timestamp::format_date_time(time));
+            title.push(Span::styled(format!(" · {time}"), self.theme.muted));
+        }
+        let mut lines = vec![Line::from(title)];
+        let kind = match &msg.kind {
+            MessageKind::Text => None,
+            MessageKind::ToolCall { name } => Some(format!("{} {name}", self.theme.glyph("⚙", "*"))),
+            MessageKind::ToolResult { name } => Some(format!("{} {name}", self.theme.glyph("↳", "<-"))),
+            MessageKind::Voice { source, .. } => Some(format!("{} {source}", self.theme.glyph("▶", ">"))),
+        };
+        lines.extend(kind.map(|kind| Line::styled(kind, self.theme.muted)));
+        if let Some(metadata) = &msg.metadata {
+            lines.push(Line::styled(metadata_summary(metadata, &self.strings), self.theme.muted));
+        }
+        lines.push(Line::styled(self.theme.glyph("─", "-").repeat(3), self.theme.muted));
+        lines
+    }
+
+    /// The message's lines before wrapping.
+    fn lines(&self) -> Vec<Line<'static>> {
+        let msg = &self.message;
+        let mut lines = self.header();
+        if self.raw {
+            lines.extend(msg.content.split('\n').map(|line| Line::raw(line.to_string())));
+            return lines;
+        }
+        let (thought, answer) = reasoning::split(&msg.content).unwrap_or(("", &msg.content));
+        for text in [msg.reasoning.as_deref().unwrap_or_default(), thought] {
+            if !text.trim().is_empty() {
+                lines.extend(text.split('\n').map(|line| Line::styled(line.to_string(), self.theme.muted)));
+                lines.push(Line::default());
+            }
+        }
+        if matches!(msg.kind, MessageKind::ToolCall { .. } | MessageKind::ToolResult { .. }) {
+            lines.extend(tool_call::pretty(answer).split('\n').map(|line| Line::raw(line.to_string())));
+        } else {
+            lines.extend(markdown::render(answer, &self.theme));
+        }
+        lines
+    }
+
+    /// The rows at the current width, query and view, wrapped again only when one of them
+    /// or the message changed.
+    fn rows(&mut self) -> &Rows {
+        let fresh = self.cache.as_ref().is_some_and(|rows| rows.width == self.width && rows.query == self.query && rows.raw == self.raw);
+        if !fresh {
+            self.cache = Some(self.wrap_rows());
+        }
+        self.cache.as_ref().expect("rows are cached")
+    }
+
+    /// Wraps the lines with search matches highlighted.
+    fn wrap_rows(&self) -> Rows {
+        let matcher = search::Matcher::new(&self.query, false).expect("substring queries are valid");
+        let style = Style::default().add_modifier(Modifier::REVERSED);
+        let options = textwrap::Options::new(self.width.max(1));
+        let mut rows = Vec::new();
+        let mut matches = Vec::new();
+        for line in self.lines() {
+            let text = wrap::plain(&line);
+            let ranges = matcher.find_iter(&text);
+            if !ranges.is_empty() {
+                matches.push(rows.len());
+            }
+            let spans = search::highlight(line.spans, &ranges, style);
+            let wrapped = wrap::wrap_spans(&spans, &options, false);
+            if wrapped.is_empty() {
+                rows.push(Line::default());
+            }
+            rows.extend(wrapped);
+        }
+        Rows { width: self.width, query: self.query.clone(), raw: self.raw, rows, matches }
+    }
+
+    /// Draws the popup over most of `area`.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let [popup] = Layout::horizontal([Constraint::Percentage(90)]).flex(Flex::Center).areas(area);
+        let [popup] = Layout::vertical([Constraint::Percentage(90)]).flex(Flex::Center).areas(popup);
+        let title = if self.raw { &self.strings.detail_source_title } else { &self.strings.detail_title };
+        let block = Block::default().borders(Borders::ALL).title(title.clone()).border_style(self.theme.border);
+        let inner = block.inner(popup);
+        let [body, footer] = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+        self.width = body.width as usize;
+        self.height = body.height as usize;
+        let (offset, height) = (self.offset, self.height);
+        let Rows { rows, matches, .. } = self.rows();
+        let offset = offset.min(rows.len().saturating_sub(height));
+        let shown: Vec<Line> = rows.iter().skip(offset).take(height).cloned().collect();
+        let match_count = matches.len();
+        self.offset = offset;
+        let footer_text = if self.searching || !self.query.is_empty() {
+            let cursor = if self.searching { "▏" } else { "" };
+            let query = format!("{}{cursor}", self.query);
+            strings::fill(&self.strings.detail_search, &[("query", &query), ("count", &match_count)])
+        } else {
+            self.strings.detail_help.clone()
+        };
+        frame.render_widget(Clear, popup);
+        frame.render_widget(block, popup);
+        frame.render_widget(Paragraph::new(shown), body);
+        frame.render_widget(Paragraph::new(Line::styled(footer_text, self.theme.muted)), footer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend};
+
+    use super::*;
+
+    fn render(detail: &mut MessageDetail) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal.draw(|frame| detail.render(frame, frame.area())).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect::<String>() + "\n")
+            .collect()
+    }
+
+    #[test]
+    fn test_raw_toggle_search_and_copy() {
+        let content = "# Title\nsome **bold** text\n```rust\nfn main() {}\n```";
+        let mut detail = MessageDetail::new(ChatMessage::new("AI", content));
+        let screen = render(&mut detail);
+        assert!(screen.contains("AI"));
+        assert!(screen.contains("some bold text"));
+        assert!(!screen.contains("**bold**"));
+
+        assert_eq!(detail.on_key(KeyEvent::from(KeyCode::Char('r'))), None);
+        assert!(detail.is_raw());
+        let screen = render(&mut detail);
+        assert!(screen.contains("Message source"));
+        assert!(screen.contains("some **bold** text"));
+
+        for key in [KeyCode::Char('/'), KeyCode::Char('m'), KeyCode::Char('a'), KeyCode::Char('i'), KeyCode::Char('n')] {
+            assert_eq!(detail.on_key(KeyEvent::from(key)), None);
+        }
+        assert_eq!(detail.on_key(KeyEvent::from(KeyCode::Enter)), None);
+        assert_eq!(detail.query(), "main");
+        assert!(render(&mut detail).contains("/main · 1 lines"));
+
+        assert_eq!(detail.on_key(KeyEvent::from(KeyCode::Char('y'))), Some(DetailAction::CopyMessage));
+        assert_eq!(detail.on_key(KeyEvent::from(KeyCode::Char('2'))), Some(DetailAction::CopyCodeBlock(2)));
+        assert_eq!(detail.on_key(KeyEvent::from(KeyCode::Esc)), Some(DetailAction::Close));
+    }
+
+    #[test]
+    fn test_scrolls_and_jumps_to_matches() {
+        let content = (1..=30).map(|n| format!("line {n}")).collect::<Vec<_>>().join("\n");
+        let mut detail = MessageDetail::new(ChatMessage::new("AI", content));
+        render(&mut detail);
+        detail.on_key(KeyEvent::from(KeyCode::End));
+        let bottom = detail.offset();
+        assert!(bottom > 0);
+        assert!(render(&mut detail).contains("line 30"));
+        detail.on_key(KeyEvent::from(KeyCode::Down));
+        assert_eq!(detail.offset(), bottom);
+
+        detail.on_key(KeyEvent::from(KeyCode::Home));
+        detail.set_query("line 12");
+        detail.on_key(KeyEvent::from(KeyCode::Char('n')));
+        // The header has the sender and the rule above the content.
+        assert_eq!(detail.offset(), 2 + 11);
+        let offset = detail.offset();
+        assert_eq!(wrap::plain(&detail.rows().rows[offset]), "line 12");
+    }
+}
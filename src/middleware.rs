@@ -0,0 +1,49 @@
+//! Ordered processing stages that messages pass through before a [`ChatApp`](crate::ChatApp) stores them.
+
+use crate::ChatMessage;
+
+/// A stage in the pipeline set up with [`ChatApp::add_middleware`](crate::ChatApp::add_middleware).
+///
+/// Each stage gets every message the previous stage produced, with its conversation index,
+/// and returns the messages to pass on: the message itself, rewritten or annotated, several
+/// messages split from it, or none to drop it. Closures taking `(usize, ChatMessage)` and
+/// returning `Vec<ChatMessage>` implement it too.
+pub trait MessageMiddleware {
+    fn process(&mut self, conversation: usize, msg: ChatMessage) -> Vec<ChatMessage>;
+}
+
+impl<F: FnMut(usize, ChatMessage) -> Vec<ChatMessage>> MessageMiddleware for F {
+    fn process(&mut self, conversation: usize, msg: ChatMessage) -> Vec<ChatMessage> {
+        self(conversation, msg)
+    }
+}
+
+/// Runs `msg` through each stage in order.
+pub(crate) fn run(stages: &mut [Box<dyn MessageMiddleware>], conversation: usize, msg: ChatMessage) -> Vec<ChatMessage> {
+    let mut messages = vec![msg];
+    for stage in stages {
+        messages = messages.into_iter().flat_map(|msg| stage.process(conversation, msg)).collect();
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stages_run_in_order() {
+        let split = |_, msg: ChatMessage| -> Vec<ChatMessage> {
+            msg.content.split('|').map(|part| ChatMessage::new(msg.sender.clone(), part)).collect()
+        };
+        let drop_empty = |_, msg: ChatMessage| if msg.content.is_empty() { vec![] } else { vec![msg] };
+        let shout = |_, mut msg: ChatMessage| {
+            msg.content = msg.content.to_uppercase();
+            vec![msg]
+        };
+        let mut stages: Vec<Box<dyn MessageMiddleware>> = vec![Box::new(split), Box::new(drop_empty), Box::new(shout)];
+        let out = run(&mut stages, 0, ChatMessage::new("AI", "one||two"));
+        let contents: Vec<&str> = out.iter().map(|msg| msg.content.as_str()).collect();
+        assert_eq!(contents, ["ONE", "TWO"]);
+    }
+}
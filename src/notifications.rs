@@ -0,0 +1,112 @@
+//! Desktop notifications for incoming messages.
+
+use crate::ChatMessage;
+
+/// Which messages trigger a desktop notification.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NotifyRule {
+    #[default]
+    Never,
+    AllMessages,
+    /// Only messages whose content mentions the configured nick.
+    Mentions,
+}
+
+/// Sends desktop notifications via the platform's notification service.
+#[derive(Clone, Debug)]
+pub struct DesktopNotifier {
+    rule: NotifyRule,
+    nick: Option<String>,
+    app_name: String,
+    // Hands a notification (app name, summary, body) to the notification service.
+    send: fn(String, String, String) -> bool,
+}
+
+impl Default for DesktopNotifier {
+    fn default() -> Self {
+        Self::new(NotifyRule::default())
+    }
+}
+
+impl DesktopNotifier {
+    pub fn new(rule: NotifyRule) -> Self {
+        Self {
+            rule,
+            nick: None,
+            app_name: "tui-chat".to_string(),
+            send: send_in_background,
+        }
+    }
+
+    pub fn set_rule(&mut self, rule: NotifyRule) {
+        self.rule = rule;
+    }
+
+    pub fn rule(&self) -> NotifyRule {
+        self.rule
+    }
+
+    /// Sets the nick that counts as a mention, matched case-insensitively.
+    pub fn set_nick(&mut self, nick: impl Into<String>) {
+        self.nick = Some(nick.into());
+    }
+
+    /// Sets the application name reported to the notification service.
+    pub fn set_app_name(&mut self, name: impl Into<String>) {
+        self.app_name = name.into();
+    }
+
+    /// Whether the rule selects this message.
+    pub fn matches(&self, msg: &ChatMessage) -> bool {
+        match self.rule {
+            NotifyRule::Never => false,
+            NotifyRule::AllMessages => true,
+            NotifyRule::Mentions => self
+                .nick
+                .as_ref()
+                .is_some_and(|nick| msg.content.to_lowercase().contains(&nick.to_lowercase())),
+        }
+    }
+
+    /// Shows a notification for the message if the rule selects it. Returns whether one was sent.
+    pub fn notify(&self, msg: &ChatMessage) -> bool {
+        self.matches(msg) && self.show(msg)
+    }
+
+    /// Shows a notification for the message whatever the rule. It is sent from a background
+    /// thread, since the notification service can take a while to answer, so this returns
+    /// whether it was handed off rather than whether it arrived.
+    pub fn show(&self, msg: &ChatMessage) -> bool {
+        (self.send)(self.app_name.clone(), msg.sender.clone(), msg.content.clone())
+    }
+
+    /// Replaces how notifications are sent, to observe them in tests.
+    #[cfg(test)]
+    pub(crate) fn set_send(&mut self, send: fn(String, String, String) -> bool) {
+        self.send = send;
+    }
+}
+
+fn send_in_background(app_name: String, summary: String, body: String) -> bool {
+    std::thread::Builder::new()
+        .name("desktop-notification".to_string())
+        .spawn(move || {
+            let _ = notify_rust::Notification::new().appname(&app_name).summary(&summary).body(&body).show();
+        })
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mention_rule() {
+        let mut notifier = DesktopNotifier::new(NotifyRule::Mentions);
+        let msg = ChatMessage::new("bob", "hey Alice, look");
+        assert!(!notifier.matches(&msg));
+        notifier.set_nick("alice");
+        assert!(notifier.matches(&msg));
+        assert!(!notifier.matches(&ChatMessage::new("bob", "hey all")));
+    }
+}
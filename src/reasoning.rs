@@ -0,0 +1,34 @@
+//! Reasoning an assistant did before answering, shown apart from the answer.
+
+/// Splits a leading `<think>…</think>` section off `content` into the reasoning and the
+/// answer after it. While the section is still open, as when a reply is streaming in,
+/// everything after the tag is reasoning.
+pub(crate) fn split(content: &str) -> Option<(&str, &str)> {
+    let rest = content.trim_start().strip_prefix("<think>")?;
+    Some(match rest.find("</think>") {
+        Some(end) => (rest[..end].trim(), rest[end + "</think>".len()..].trim_start()),
+        None => (rest.trim(), ""),
+    })
+}
+
+/// A character count in a few digits: `850`, `1.2k`, `36k`.
+pub(crate) fn format_count(count: usize) -> String {
+    match count {
+        0..1000 => count.to_string(),
+        1000..10_000 => format!("{:.1}k", count as f64 / 1000.0),
+        _ => format!("{}k", count / 1000),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_think_section() {
+        assert_eq!(split("<think>\nhmm, 2+2\n</think>\n\n4"), Some(("hmm, 2+2", "4")));
+        assert_eq!(split("<think>still going"), Some(("still going", "")));
+        assert_eq!(split("4 <think>no</think>"), None);
+        assert_eq!([850, 1234, 36_500].map(format_count), ["850", "1.2k", "36k"]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Classifying incoming messages by how much attention they ask for: mentions of the
+//! user's nick, watched keywords and watched senders.
+
+use crate::ChatMessage;
+
+/// How much attention a message asks for, as decided by [`NotifyRules`].
+///
+/// [`ChatArea`](crate::ChatArea) highlights messages at [`NotifyLevel::Low`] and above, and
+/// [`ChatApp`](crate::ChatApp) runs the [`NotifyActions`] set for the level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotifyLevel {
+    #[default]
+    None,
+    Low,
+    High,
+}
+
+/// What [`ChatApp`](crate::ChatApp) does when a message at some [`NotifyLevel`] arrives while
+/// the user is away: in another conversation, scrolled back, or with the terminal unfocused.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NotifyActions {
+    /// Ring the terminal bell.
+    pub bell: bool,
+    /// Show a toast with the sender and message.
+    pub toast: bool,
+    /// Send a desktop notification, with the `notifications` feature.
+    pub desktop: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Rule {
+    Keyword(String),
+    Sender(String),
+}
+
+/// Rules that classify messages into [`NotifyLevel`]s. A message gets the highest level of
+/// any rule it matches. Nicks and keywords match whole words and, like senders, ignore case.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NotifyRules {
+    nick: Option<(String, NotifyLevel)>,
+    rules: Vec<(Rule, NotifyLevel)>,
+}
+
+impl NotifyRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classifies messages mentioning `nick` at `level`. The user's own messages, sent
+    /// under `nick`, never count as mentions.
+    pub fn set_nick(&mut self, nick: impl Into<String>, level: NotifyLevel) {
+        self.nick = Some((nick.into().to_lowercase(), level));
+    }
+
+    pub fn nick(&self) -> Option<&str> {
+        self.nick.as_ref().map(|(nick, _)| nick.as_str())
+    }
+
+    /// Classifies messages containing `keyword` at `level`.
+    pub fn add_keyword(&mut self, keyword: impl Into<String>, level: NotifyLevel) {
+        self.rules.push((Rule::Keyword(keyword.into().to_lowercase()), level));
+    }
+
+    /// Classifies every message from `sender` at `level`.
+    pub fn add_sender(&mut self, sender: impl Into<String>, level: NotifyLevel) {
+        self.rules.push((Rule::Sender(sender.into().to_lowercase()), level));
+    }
+
+    /// Removes every rule, including the nick.
+    pub fn clear(&mut self) {
+        self.nick = None;
+        self.rules.clear();
+    }
+
+    /// The highest level of the rules `msg` matches.
+    pub fn classify(&self, msg: &ChatMessage) -> NotifyLevel {
+        let sender = msg.sender.to_lowercase();
+        let content = msg.content.to_lowercase();
+        let mut level = NotifyLevel::None;
+        if let Some((nick, nick_level)) = &self.nick
+            && sender != *nick
+            && contains_word(&content, nick)
+        {
+            level = *nick_level;
+        }
+        for (rule, rule_level) in &self.rules {
+            let matched = match rule {
+                Rule::Keyword(keyword) => contains_word(&content, keyword),
+                Rule::Sender(name) => sender == *name,
+            };
+            if matched {
+                level = level.max(*rule_level);
+            }
+        }
+        level
+    }
+}
+
+/// Whether `word` occurs in `text` with no letter, digit or underscore on either side.
+fn contains_word(text: &str, word: &str) -> bool {
+    if word.is_empty() {
+        return false;
+    }
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highest_matching_rule_wins() {
+        let mut rules = NotifyRules::new();
+        rules.set_nick("Alice", NotifyLevel::High);
+        rules.add_keyword("deploy", NotifyLevel::Low);
+        rules.add_sender("ci-bot", NotifyLevel::Low);
+        let level = |sender: &str, content: &str| rules.classify(&ChatMessage::new(sender, content));
+        assert_eq!(level("bob", "hey @alice, look"), NotifyLevel::High);
+        assert_eq!(level("bob", "alicent is here"), NotifyLevel::None);
+        assert_eq!(level("alice", "I am alice"), NotifyLevel::None);
+        assert_eq!(level("bob", "Deploy done"), NotifyLevel::Low);
+        assert_eq!(level("bob", "redeployed"), NotifyLevel::None);
+        assert_eq!(level("CI-Bot", "green"), NotifyLevel::Low);
+        assert_eq!(level("ci-bot", "deploy for alice"), NotifyLevel::High);
+    }
+}
@@ -0,0 +1,188 @@
+//! A ready-made event and draw loop for hosts that do not need their own.
+
+use std::{
+    io::{self, Write},
+    panic::{self, PanicHookInfo},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, SetCursorStyle, Show},
+    event::{self, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste, EnableFocusChange, EnableMouseCapture},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{Terminal, backend::CrosstermBackend};
+
+use crate::ChatApp;
+
+/// How often the app ticks while a scroll animation runs.
+const ANIMATION_FRAME: Duration = Duration::from_millis(16);
+
+type PanicHook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// Sets the terminal up, runs the loop until the app quits, and restores the terminal
+/// however the loop ends: returning, failing or panicking.
+pub(crate) fn run<W: Write>(
+    app: &mut ChatApp,
+    terminal: &mut Terminal<CrosstermBackend<W>>,
+    tick_rate: Duration,
+    poll: &mut dyn FnMut(&mut ChatApp),
+) -> io::Result<()> {
+    enable_raw_mode()?;
+    let mut guard = Restore::new(terminal, Some(hook_panics()));
+    execute!(guard.terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste, EnableFocusChange, Hide)?;
+    guard.terminal.clear()?;
+    let result = run_loop(app, guard.terminal, tick_rate, poll);
+    guard.restore()?;
+    result
+}
+
+/// Restores the terminal when dropped, so an error or a panic unwinding out of the loop
+/// does not leave it in raw mode on the alternate screen.
+struct Restore<'a, W: Write> {
+    terminal: &'a mut Terminal<CrosstermBackend<W>>,
+    previous_hook: Option<Arc<PanicHook>>,
+    restored: bool,
+}
+
+impl<'a, W: Write> Restore<'a, W> {
+    fn new(terminal: &'a mut Terminal<CrosstermBackend<W>>, previous_hook: Option<Arc<PanicHook>>) -> Self {
+        Self {
+            terminal,
+            previous_hook,
+            restored: false,
+        }
+    }
+
+    fn restore(&mut self) -> io::Result<()> {
+        self.restored = true;
+        // The hook cannot be swapped while panicking; ours only restores the terminal again.
+        if let Some(previous) = self.previous_hook.take()
+            && !std::thread::panicking()
+        {
+            unhook_panics(previous);
+        }
+        restore(self.terminal.backend_mut())
+    }
+}
+
+impl<W: Write> Drop for Restore<'_, W> {
+    fn drop(&mut self) {
+        if !self.restored {
+            let _ = self.restore();
+        }
+    }
+}
+
+fn restore(out: &mut impl Write) -> io::Result<()> {
+    let raw_mode = disable_raw_mode();
+    let screen = execute!(
+        out,
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        DisableFocusChange,
+        SetCursorStyle::DefaultUserShape,
+        Show
+    );
+    raw_mode.and(screen)
+}
+
+/// Installs a panic hook that restores the terminal before the panic message is printed to
+/// stderr, where it would otherwise land on the alternate screen and vanish with it.
+/// Returns the hook it wraps.
+///
+/// The hook has no access to the loop's writer, so it always writes to stdout.
+fn hook_panics() -> Arc<PanicHook> {
+    let previous = Arc::new(panic::take_hook());
+    let hook = previous.clone();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore(&mut io::stdout());
+        hook(info);
+    }));
+    previous
+}
+
+/// Puts back the panic hook [`hook_panics`] wrapped.
+fn unhook_panics(previous: Arc<PanicHook>) {
+    drop(panic::take_hook());
+    if let Ok(previous) = Arc::try_unwrap(previous) {
+        panic::set_hook(previous);
+    }
+}
+
+fn run_loop<W: Write>(
+    app: &mut ChatApp,
+    terminal: &mut Terminal<CrosstermBackend<W>>,
+    tick_rate: Duration,
+    poll: &mut dyn FnMut(&mut ChatApp),
+) -> io::Result<()> {
+    let mut last_tick = Instant::now();
+    loop {
+        poll(app);
+        if app.should_quit() {
+            return Ok(());
+        }
+        // Only draw when something changed
+        if app.needs_redraw() {
+            terminal.draw(|f| app.render(f))?;
+            match app.get_cursor_pos() {
+                Some((x, y)) => execute!(terminal.backend_mut(), MoveTo(x, y), Show)?,
+                None => execute!(terminal.backend_mut(), Hide)?,
+            }
+        }
+        app.write_terminal_effects(terminal.backend_mut())?;
+
+        let rate = if app.is_animating() { tick_rate.min(ANIMATION_FRAME) } else { tick_rate };
+        if event::poll(rate.saturating_sub(last_tick.elapsed()))? {
+            app.on_event(event::read()?);
+            // Ctrl+Z is only bound with the `suspend` feature, on Unix
+            #[cfg(all(unix, feature = "suspend"))]
+            if app.take_suspend_request() {
+                app.suspend(terminal.backend_mut())?;
+                execute!(terminal.backend_mut(), Hide)?;
+                terminal.clear()?;
+            }
+        }
+        if last_tick.elapsed() >= rate {
+            app.tick();
+            last_tick = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    /// A writer whose output the test can still read once the terminal borrowed it.
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_terminal_is_restored_when_the_loop_fails() {
+        let out = Shared::default();
+        let mut terminal = Terminal::new(CrosstermBackend::new(out.clone())).unwrap();
+        {
+            // Dropped without restore(), as when the loop returns early with an error.
+            let _guard = Restore::new(&mut terminal, None);
+        }
+        let written = String::from_utf8_lossy(&out.0.borrow()).into_owned();
+        assert!(written.contains("\x1b[?1049l"), "left the alternate screen: {written:?}");
+        assert!(written.ends_with("\x1b[?25h"), "showed the cursor: {written:?}");
+    }
+}
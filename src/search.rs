@@ -0,0 +1,130 @@
+//! Matching and highlighting for the message filter.
+
+use std::ops::Range;
+
+use ratatui::{style::Style, text::Span};
+
+/// A compiled filter query.
+#[derive(Clone, Debug)]
+pub(crate) enum Matcher {
+    /// Case-insensitive substring.
+    Substring(String),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl Matcher {
+    /// Compiles `query`, as a regular expression if `regex` is set.
+    ///
+    /// Returns a short description of the problem if the pattern is invalid.
+    #[cfg_attr(not(feature = "regex"), allow(unused_variables))]
+    pub(crate) fn new(query: &str, regex: bool) -> Result<Self, String> {
+        #[cfg(feature = "regex")]
+        if regex {
+            return regex::Regex::new(query).map(Matcher::Regex).map_err(|err| match err {
+                regex::Error::Syntax(msg) => msg.lines().last().unwrap_or_default().trim().to_string(),
+                err => err.to_string(),
+            });
+        }
+        Ok(Matcher::Substring(query.to_string()))
+    }
+
+    /// Byte ranges of every match in `text`.
+    pub(crate) fn find_iter(&self, text: &str) -> Vec<Range<usize>> {
+        match self {
+            Matcher::Substring(query) => find_ignore_case(text, query),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => regex.find_iter(text).map(|m| m.range()).filter(|r| !r.is_empty()).collect(),
+        }
+    }
+
+    pub(crate) fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Substring(query) => !find_ignore_case(text, query).is_empty(),
+            #[cfg(feature = "regex")]
+            Matcher::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Non-overlapping case-insensitive occurrences of `query` in `text`.
+fn find_ignore_case(text: &str, query: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    if query.is_empty() {
+        return ranges;
+    }
+    let mut from = 0;
+    for (start, _) in text.char_indices() {
+        if start < from {
+            continue;
+        }
+        let mut rest = text[start..].char_indices();
+        let mut end = start;
+        let matched = query.chars().all(|q| match rest.next() {
+            Some((i, c)) if c.to_lowercase().eq(q.to_lowercase()) => {
+                end = start + i + c.len_utf8();
+                true
+            }
+            _ => false,
+        });
+        if matched {
+            ranges.push(start..end);
+            from = end;
+        }
+    }
+    ranges
+}
+
+/// Patches `style` onto the parts of `spans` covered by `ranges`, which are byte ranges
+/// into the concatenated span text, sorted and non-overlapping.
+pub(crate) fn highlight(spans: Vec<Span<'static>>, ranges: &[Range<usize>], style: Style) -> Vec<Span<'static>> {
+    if ranges.is_empty() {
+        return spans;
+    }
+    let mut out = Vec::with_capacity(spans.len() + ranges.len() * 2);
+    let mut offset = 0;
+    for span in spans {
+        let text = span.content.as_ref();
+        let span_range = offset..offset + text.len();
+        let mut pos = 0;
+        for range in ranges.iter().filter(|r| r.start < span_range.end && r.end > span_range.start) {
+            let start = range.start.max(span_range.start) - offset;
+            let end = range.end.min(span_range.end) - offset;
+            if start > pos {
+                out.push(Span::styled(text[pos..start].to_string(), span.style));
+            }
+            out.push(Span::styled(text[start..end].to_string(), span.style.patch(style)));
+            pos = end;
+        }
+        if pos < text.len() {
+            out.push(Span::styled(text[pos..].to_string(), span.style));
+        }
+        offset = span_range.end;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Modifier;
+
+    #[test]
+    fn test_highlight_splits_spans_at_matches() {
+        let matcher = Matcher::new("disk", false).unwrap();
+        let spans = vec![Span::raw("DISK is di"), Span::raw("sk")];
+        let ranges = matcher.find_iter("DISK is disk");
+        assert_eq!(ranges, vec![0..4, 8..12]);
+        let reversed = Style::default().add_modifier(Modifier::REVERSED);
+        let out = highlight(spans, &ranges, reversed);
+        let parts: Vec<(&str, bool)> =
+            out.iter().map(|s| (s.content.as_ref(), s.style.add_modifier.contains(Modifier::REVERSED))).collect();
+        assert_eq!(parts, vec![("DISK", true), (" is ", false), ("di", true), ("sk", true)]);
+
+        #[cfg(feature = "regex")]
+        {
+            assert_eq!(Matcher::new(r"d\w+", true).unwrap().find_iter("a disk"), vec![2..6]);
+            assert!(Matcher::new("(", true).is_err());
+        }
+    }
+}
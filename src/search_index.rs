@@ -0,0 +1,148 @@
+//! Ranked full-text search over a chat's messages, kept up to date as messages change.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{ChatMessage, MessageId};
+
+/// An index [`ChatArea::search`](crate::ChatArea::search) queries instead of scanning every message.
+///
+/// [`ChatArea`](crate::ChatArea) keeps it current: messages are inserted when added,
+/// re-inserted when their content changes, and the index is cleared with the chat.
+/// Implement it over an external engine such as tantivy, or use [`InvertedIndex`].
+pub trait SearchIndex {
+    /// Indexes a message, replacing anything indexed for `id` before.
+    fn insert(&mut self, id: MessageId, msg: &ChatMessage);
+    fn remove(&mut self, id: MessageId);
+    fn clear(&mut self);
+    /// Up to `limit` messages matching `query`, best match first.
+    fn search(&self, query: &str, limit: usize) -> Vec<MessageId>;
+}
+
+/// An in-memory inverted index of the words in each message's sender and content.
+///
+/// Every word of a query must match a word in the message, the last one as a prefix so
+/// results follow along while the user types. Hits are ranked by TF-IDF, so rarer words
+/// count for more, and newer messages win ties.
+#[derive(Clone, Debug, Default)]
+pub struct InvertedIndex {
+    // Each word and how often it occurs in each message containing it.
+    postings: BTreeMap<String, HashMap<MessageId, u32>>,
+    // The distinct words of each message, to remove it again.
+    words: HashMap<MessageId, Vec<String>>,
+}
+
+impl InvertedIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of indexed messages.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Messages containing a word that equals `word`, or starts with it if `prefix` is set,
+    /// with a score for each.
+    fn matches(&self, word: &str, prefix: bool) -> HashMap<MessageId, f64> {
+        let total = self.words.len() as f64;
+        let mut scores: HashMap<MessageId, f64> = HashMap::new();
+        let postings: Box<dyn Iterator<Item = &HashMap<MessageId, u32>>> = if prefix {
+            Box::new(self.postings.range(word.to_string()..).take_while(|(w, _)| w.starts_with(word)).map(|(_, p)| p))
+        } else {
+            Box::new(self.postings.get(word).into_iter())
+        };
+        for posting in postings {
+            let idf = (1.0 + total / posting.len() as f64).ln();
+            for (&id, &count) in posting {
+                *scores.entry(id).or_default() += f64::from(count) * idf;
+            }
+        }
+        scores
+    }
+}
+
+/// The lowercased alphanumeric words of `text`.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase)
+}
+
+impl SearchIndex for InvertedIndex {
+    fn insert(&mut self, id: MessageId, msg: &ChatMessage) {
+        self.remove(id);
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for word in tokenize(&msg.sender).chain(tokenize(&msg.content)) {
+            *counts.entry(word).or_default() += 1;
+        }
+        let words = counts.keys().cloned().collect();
+        for (word, count) in counts {
+            self.postings.entry(word).or_default().insert(id, count);
+        }
+        self.words.insert(id, words);
+    }
+
+    fn remove(&mut self, id: MessageId) {
+        for word in self.words.remove(&id).unwrap_or_default() {
+            if let Some(posting) = self.postings.get_mut(&word) {
+                posting.remove(&id);
+                if posting.is_empty() {
+                    self.postings.remove(&word);
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.postings.clear();
+        self.words.clear();
+    }
+
+    fn search(&self, query: &str, limit: usize) -> Vec<MessageId> {
+        let words: Vec<String> = tokenize(query).collect();
+        let Some((last, rest)) = words.split_last() else {
+            return Vec::new();
+        };
+        let mut scores = self.matches(last, !query.ends_with(char::is_whitespace));
+        for word in rest {
+            let matches = self.matches(word, false);
+            scores.retain(|id, score| match matches.get(id) {
+                Some(more) => {
+                    *score += more;
+                    true
+                }
+                None => false,
+            });
+        }
+        let mut hits: Vec<(MessageId, f64)> = scores.into_iter().collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.cmp(&a.0)));
+        hits.into_iter().take(limit).map(|(id, _)| id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranked_prefix_search_and_removal() {
+        let mut index = InvertedIndex::new();
+        let messages = ["the build failed", "build passed, build green", "lunch?", "rebuild the cache"];
+        for (i, content) in messages.iter().enumerate() {
+            index.insert(MessageId(i as u64), &ChatMessage::new("ci", *content));
+        }
+        assert_eq!(index.search("build", 10), [MessageId(1), MessageId(0)]);
+        assert_eq!(index.search("the bu", 10), [MessageId(0)]);
+        assert_eq!(index.search("CI lun", 10), [MessageId(2)]);
+        assert_eq!(index.search("build ", 1), [MessageId(1)]);
+        assert!(index.search("", 10).is_empty());
+
+        index.insert(MessageId(1), &ChatMessage::new("ci", "all good"));
+        index.remove(MessageId(0));
+        assert!(index.search("build", 10).is_empty());
+        assert_eq!(index.len(), 3);
+        assert!(!index.postings.contains_key("failed"));
+    }
+}
@@ -0,0 +1,36 @@
+//! Snapshot of a [`ChatApp`](crate::ChatApp) session for saving and restoring.
+
+use crate::{ChatMessage, MessageId};
+
+/// Everything needed to resume a session: each conversation's messages and pins, scroll position
+/// and input draft, and which conversation was active.
+///
+/// With the `serde` feature it can be serialized in any serde format.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionState {
+    pub conversations: Vec<ConversationState>,
+    pub active: usize,
+}
+
+/// The saved state of one conversation.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConversationState {
+    pub name: String,
+    pub messages: Vec<ChatMessage>,
+    /// The id of each message, so ids held by the host stay valid after restoring.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ids: Vec<MessageId>,
+    /// Pinned messages, in the order they were pinned.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pinned: Vec<MessageId>,
+    /// First visible row of the chat.
+    pub offset: usize,
+    /// Whether the chat was following new messages rather than scrolled back.
+    pub following: bool,
+    /// Unsent input text.
+    pub draft: String,
+    /// Cursor position in the draft, in bytes.
+    pub cursor: usize,
+}
@@ -0,0 +1,157 @@
+//! Runtime settings overlay.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{Density, KeymapPreset, Strings, ThemePreset};
+
+/// User-tunable options, applied live by [`ChatApp`](crate::ChatApp) and exposed so the host can persist them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Settings {
+    pub theme: ThemePreset,
+    pub density: Density,
+    pub show_timestamps: bool,
+    /// Shows message metadata footers, see [`ChatArea::set_show_metadata`](crate::ChatArea::set_show_metadata).
+    pub show_metadata: bool,
+    pub keymap: KeymapPreset,
+    /// Turns off smooth scrolling and keeps the cursor from blinking.
+    pub reduced_motion: bool,
+}
+
+const ROWS: usize = 6;
+
+/// Cycles `value` through `all` by `delta` steps, wrapping around.
+fn cycle<T: Copy + PartialEq>(all: &[T], value: T, delta: isize) -> T {
+    let pos = all.iter().position(|v| *v == value).unwrap_or(0) as isize;
+    all[(pos + delta).rem_euclid(all.len() as isize) as usize]
+}
+
+/// An overlay for editing [`Settings`] with the arrow keys.
+#[derive(Clone, Debug)]
+pub struct SettingsPanel {
+    settings: Settings,
+    selected: usize,
+    strings: Strings,
+}
+
+impl SettingsPanel {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            settings,
+            selected: 0,
+            strings: Strings::default(),
+        }
+    }
+
+    /// Sets the panel title, option labels and help line.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    /// The settings as currently edited.
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Handles a key. Up/Down choose an option, Left/Right/Space change it.
+    /// Returns `false` once the panel should close (Esc or Enter).
+    pub fn on_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => return false,
+            KeyCode::Up => self.selected = (self.selected + ROWS - 1) % ROWS,
+            KeyCode::Down | KeyCode::Tab => self.selected = (self.selected + 1) % ROWS,
+            KeyCode::Left => self.change(-1),
+            KeyCode::Right | KeyCode::Char(' ') => self.change(1),
+            _ => {}
+        }
+        true
+    }
+
+    fn change(&mut self, delta: isize) {
+        let s = &mut self.settings;
+        match self.selected {
+            0 => s.theme = cycle(&ThemePreset::ALL, s.theme, delta),
+            1 => s.density = cycle(&[Density::Compact, Density::Cozy, Density::Comfortable], s.density, delta),
+            2 => s.show_timestamps = !s.show_timestamps,
+            3 => s.show_metadata = !s.show_metadata,
+            4 => s.keymap = cycle(&KeymapPreset::ALL, s.keymap, delta),
+            _ => s.reduced_motion = !s.reduced_motion,
+        }
+    }
+
+    fn value(&self, row: usize) -> String {
+        let s = &self.settings;
+        match row {
+            0 => s.theme.name().to_string(),
+            1 => format!("{:?}", s.density),
+            2 => self.on_off(s.show_timestamps),
+            3 => self.on_off(s.show_metadata),
+            4 => s.keymap.name().to_string(),
+            _ => self.on_off(s.reduced_motion),
+        }
+    }
+
+    fn on_off(&self, value: bool) -> String {
+        if value { &self.strings.on } else { &self.strings.off }.clone()
+    }
+
+    /// Draws the panel centered in `area`.
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let [popup] = Layout::horizontal([Constraint::Length(36.min(area.width))]).flex(Flex::Center).areas(area);
+        let height = (ROWS as u16 + 4).min(area.height);
+        let [popup] = Layout::vertical([Constraint::Length(height)]).flex(Flex::Center).areas(popup);
+        let labels = [
+            &self.strings.settings_theme,
+            &self.strings.settings_density,
+            &self.strings.settings_timestamps,
+            &self.strings.settings_metadata,
+            &self.strings.settings_keymap,
+            &self.strings.settings_reduced_motion,
+        ];
+        let mut lines: Vec<Line> = labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                let style = if i == self.selected {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::raw(format!(" {label:<12}")),
+                    Span::styled(format!("◀ {} ▶", self.value(i)), style),
+                ])
+            })
+            .collect();
+        lines.push(Line::default());
+        lines.push(Line::styled(format!(" {}", self.strings.settings_help), Style::default().add_modifier(Modifier::DIM)));
+        let block = Block::default().borders(Borders::ALL).title(self.strings.settings_title.as_str());
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrow_keys_edit_settings() {
+        let mut panel = SettingsPanel::new(Settings::default());
+        assert!(panel.on_key(KeyEvent::from(KeyCode::Right)));
+        assert_eq!(panel.settings().theme, ThemePreset::Ocean);
+        panel.on_key(KeyEvent::from(KeyCode::Down));
+        panel.on_key(KeyEvent::from(KeyCode::Left));
+        assert_eq!(panel.settings().density, Density::Comfortable);
+        panel.on_key(KeyEvent::from(KeyCode::Down));
+        panel.on_key(KeyEvent::from(KeyCode::Char(' ')));
+        assert!(panel.settings().show_timestamps);
+        assert!(!panel.on_key(KeyEvent::from(KeyCode::Esc)));
+    }
+}
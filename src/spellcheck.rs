@@ -0,0 +1,153 @@
+//! Spell checking for the input: finding misspelled words and cycling through suggestions.
+
+use std::ops::Range;
+
+use ratatui::style::{Color, Modifier, Style};
+
+/// The default style of misspelled words.
+pub(crate) const MISSPELLED: Style = Style::new().fg(Color::Red).add_modifier(Modifier::UNDERLINED);
+
+/// A dictionary that [`InputArea`](crate::InputArea) checks words against.
+///
+/// Implemented for [`zspell::Dictionary`], which loads Hunspell `.aff`/`.dic` files.
+pub trait SpellChecker {
+    /// Whether `word` is spelled correctly.
+    fn check(&self, word: &str) -> bool;
+
+    /// Replacements for a misspelled word, best first.
+    fn suggest(&self, word: &str) -> Vec<String>;
+}
+
+impl SpellChecker for zspell::Dictionary {
+    fn check(&self, word: &str) -> bool {
+        self.check_word(word)
+    }
+
+    fn suggest(&self, word: &str) -> Vec<String> {
+        self.entry(word).suggest().unwrap_or_default().into_iter().map(String::from).collect()
+    }
+}
+
+/// Byte ranges of the words in `text`: runs of letters, with apostrophes allowed inside.
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ' '))) {
+        let inside = c.is_alphabetic() || (c == '\'' && start.is_some());
+        match (start, inside) {
+            (None, true) => start = Some(i),
+            (Some(s), false) => {
+                let word = text[s..i].trim_end_matches('\'');
+                words.push(s..s + word.len());
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// The spell checker attached to an input, with the suggestion cycle in progress.
+pub(crate) struct Spelling {
+    checker: Box<dyn SpellChecker>,
+    cycle: Option<Cycle>,
+}
+
+/// Replacing a word with one suggestion after another.
+struct Cycle {
+    start: usize,
+    original: String,
+    suggestions: Vec<String>,
+    // Index into `suggestions` of the word currently in the text. Any other value means the
+    // original is in the text: `usize::MAX` before the first replacement, `suggestions.len()`
+    // after wrapping around.
+    current: usize,
+}
+
+impl Cycle {
+    fn current_word(&self) -> &str {
+        self.suggestions.get(self.current).unwrap_or(&self.original)
+    }
+}
+
+impl Spelling {
+    pub(crate) fn new(checker: Box<dyn SpellChecker>) -> Self {
+        Self {
+            checker,
+            cycle: None,
+        }
+    }
+
+    /// Byte ranges of the misspelled words in `text`.
+    pub(crate) fn misspelled(&self, text: &str) -> Vec<Range<usize>> {
+        words(text).into_iter().filter(|range| !self.checker.check(&text[range.clone()])).collect()
+    }
+
+    /// Replaces the word ending at or containing `cursor` with its next suggestion.
+    ///
+    /// Repeated calls step through the suggestions and then back to the original word.
+    /// Returns the replaced byte range and the new word, or `None` if there is nothing
+    /// to replace.
+    pub(crate) fn next_suggestion(&mut self, text: &str, cursor: usize) -> Option<(Range<usize>, String)> {
+        // Continue the cycle if the text still holds the word it last put in at the cursor.
+        let continuing = self.cycle.as_ref().is_some_and(|cycle| {
+            let end = cycle.start + cycle.current_word().len();
+            end == cursor && text.get(cycle.start..end) == Some(cycle.current_word())
+        });
+        if !continuing {
+            let range = words(text).into_iter().find(|range| range.start <= cursor && cursor <= range.end)?;
+            let word = &text[range.clone()];
+            if self.checker.check(word) {
+                return None;
+            }
+            let suggestions = self.checker.suggest(word);
+            if suggestions.is_empty() {
+                return None;
+            }
+            self.cycle = Some(Cycle {
+                start: range.start,
+                original: word.to_string(),
+                suggestions,
+                current: usize::MAX,
+            });
+        }
+        let cycle = self.cycle.as_mut().expect("cycle was just set");
+        let range = cycle.start..cycle.start + cycle.current_word().len();
+        cycle.current = match cycle.current {
+            usize::MAX => 0,
+            n => (n + 1) % (cycle.suggestions.len() + 1),
+        };
+        Some((range, cycle.current_word().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Words;
+
+    impl SpellChecker for Words {
+        fn check(&self, word: &str) -> bool {
+            ["hello", "help", "world", "don't"].contains(&word)
+        }
+
+        fn suggest(&self, word: &str) -> Vec<String> {
+            if word == "helo" { vec!["hello".into(), "help".into()] } else { Vec::new() }
+        }
+    }
+
+    #[test]
+    fn test_misspelled_words_and_suggestion_cycle() {
+        let mut spelling = Spelling::new(Box::new(Words));
+        assert_eq!(spelling.misspelled("helo world, don't wrold'"), vec![0..4, 18..23]);
+
+        let mut text = "say helo".to_string();
+        for expected in ["hello", "help", "helo", "hello"] {
+            let (range, word) = spelling.next_suggestion(&text, text.len()).unwrap();
+            text.replace_range(range, &word);
+            assert_eq!(text, format!("say {expected}"));
+        }
+        assert!(spelling.next_suggestion("say world", 9).is_none());
+    }
+}
@@ -0,0 +1,52 @@
+//! Spoilers marked `||like this||`, as Discord writes them and Matrix bridges translate them.
+
+use std::borrow::Cow;
+
+use crate::ChatMessage;
+
+/// Replaces the text of every `||spoiler||` in `content` with `glyph`, one per character,
+/// or if `revealed` just drops the markers. Line breaks are kept so the layout does not
+/// shift when the spoiler is revealed. An unpaired `||` is left as is.
+pub(crate) fn hide(content: &str, revealed: bool, glyph: char) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("||") {
+        let Some(len) = rest[start + 2..].find("||") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + len];
+        out.push_str(&rest[..start]);
+        if revealed {
+            out.push_str(inner);
+        } else {
+            out.extend(inner.chars().map(|c| if c == '\n' { c } else { glyph }));
+        }
+        rest = &rest[start + 4 + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `msg` with its spoilers hidden behind `glyph`, or as is without a glyph or spoilers.
+pub(crate) fn hide_message(msg: &ChatMessage, glyph: Option<char>) -> Cow<'_, ChatMessage> {
+    match glyph {
+        Some(glyph) if msg.content.contains("||") => {
+            let mut msg = msg.clone();
+            msg.content = hide(&msg.content, false, glyph);
+            Cow::Owned(msg)
+        }
+        _ => Cow::Borrowed(msg),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hides_spoilers_until_revealed() {
+        let text = "the ||butler did|| it, a || b";
+        assert_eq!(hide(text, false, '▒'), "the ▒▒▒▒▒▒▒▒▒▒ it, a || b");
+        assert_eq!(hide(text, true, '▒'), "the butler did it, a || b");
+    }
+}
@@ -0,0 +1,231 @@
+//! A [`MessageStore`] kept in an SQLite database.
+
+use std::{
+    io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{Connection, Row, params};
+
+use crate::{ChatMessage, MessageKind, MessageMetadata, store::MessageStore};
+
+/// The table as first released. Later columns are added by [`migrate`].
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        conversation TEXT NOT NULL,
+        sender TEXT NOT NULL,
+        content TEXT NOT NULL,
+        timestamp_ms INTEGER,
+        model TEXT,
+        elapsed_ms INTEGER,
+        prompt_tokens INTEGER,
+        completion_tokens INTEGER,
+        cost REAL
+    );
+    CREATE INDEX IF NOT EXISTS messages_by_conversation ON messages (conversation, id);
+";
+
+/// Columns added since the first release, oldest first. The database's `user_version` is
+/// the number of them it has.
+const ADDED_COLUMNS: [(&str, &str); 4] = [("kind", "TEXT"), ("tool", "TEXT"), ("reasoning", "TEXT"), ("duration_ms", "INTEGER")];
+
+const COLUMNS: &str = "id, sender, content, timestamp_ms, model, elapsed_ms, prompt_tokens, completion_tokens, cost, kind, tool, reasoning, duration_ms";
+
+/// Transcripts in a single SQLite table, created on first use. Timestamps are kept to the
+/// millisecond, along with any [`MessageMetadata`], the [`MessageKind`] and reasoning.
+pub struct SqliteStore {
+    connection: Connection,
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::with_connection(Connection::open(path).map_err(io::Error::other)?)
+    }
+
+    /// A database that lives only as long as the store.
+    pub fn in_memory() -> io::Result<Self> {
+        Self::with_connection(Connection::open_in_memory().map_err(io::Error::other)?)
+    }
+
+    fn with_connection(mut connection: Connection) -> io::Result<Self> {
+        migrate(&mut connection).map_err(io::Error::other)?;
+        Ok(Self { connection })
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> io::Result<Vec<(u64, ChatMessage)>> {
+        let mut statement = self.connection.prepare(sql).map_err(io::Error::other)?;
+        let rows = statement.query_map(params, message).map_err(io::Error::other)?;
+        rows.collect::<Result<_, _>>().map_err(io::Error::other)
+    }
+}
+
+/// Creates the table or brings an older one up to date, recording the version reached in
+/// `user_version`.
+fn migrate(connection: &mut Connection) -> rusqlite::Result<()> {
+    let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let version = version.clamp(0, ADDED_COLUMNS.len() as i64) as usize;
+    if version == ADDED_COLUMNS.len() {
+        return Ok(());
+    }
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(SCHEMA)?;
+    // Databases from before versioning may have some of the columns already.
+    let existing: Vec<String> = transaction
+        .prepare("SELECT name FROM pragma_table_info('messages')")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    for (name, kind) in &ADDED_COLUMNS[version..] {
+        if !existing.iter().any(|column| column == name) {
+            transaction.execute_batch(&format!("ALTER TABLE messages ADD COLUMN {name} {kind}"))?;
+        }
+    }
+    transaction.execute_batch(&format!("PRAGMA user_version = {}", ADDED_COLUMNS.len()))?;
+    transaction.commit()
+}
+
+fn message(row: &Row) -> rusqlite::Result<(u64, ChatMessage)> {
+    let id: i64 = row.get(0)?;
+    let timestamp: Option<i64> = row.get(3)?;
+    let model: Option<String> = row.get(4)?;
+    let elapsed: Option<i64> = row.get(5)?;
+    let prompt_tokens: Option<i64> = row.get(6)?;
+    let completion_tokens: Option<i64> = row.get(7)?;
+    let cost: Option<f64> = row.get(8)?;
+    let kind: Option<String> = row.get(9)?;
+    let tool: Option<String> = row.get(10)?;
+    let reasoning: Option<String> = row.get(11)?;
+    let duration: Option<i64> = row.get(12)?;
+    let metadata = (model.is_some() || elapsed.is_some() || prompt_tokens.is_some() || completion_tokens.is_some() || cost.is_some())
+        .then(|| MessageMetadata {
+            model,
+            elapsed: elapsed.map(|ms| Duration::from_millis(ms as u64)),
+            prompt_tokens: prompt_tokens.map(|n| n as u64),
+            completion_tokens: completion_tokens.map(|n| n as u64),
+            cost,
+        });
+    let msg = ChatMessage {
+        sender: row.get(1)?,
+        content: row.get(2)?,
+        timestamp: timestamp.map(|ms| UNIX_EPOCH + Duration::from_millis(ms as u64)),
+        metadata,
+        kind: match (kind.as_deref(), tool) {
+            (Some("tool_call"), Some(name)) => MessageKind::ToolCall { name },
+            (Some("tool_result"), Some(name)) => MessageKind::ToolResult { name },
+            (Some("voice"), Some(source)) => MessageKind::Voice { source, duration: duration.map(|ms| Duration::from_millis(ms as u64)) },
+            _ => MessageKind::Text,
+        },
+        reasoning,
+    };
+    Ok((id as u64, msg))
+}
+
+fn millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_millis() as i64)
+}
+
+impl MessageStore for SqliteStore {
+    fn append(&mut self, conversation: &str, msg: &ChatMessage) -> io::Result<u64> {
+        let metadata = msg.metadata.clone().unwrap_or_default();
+        let (kind, tool, duration) = match &msg.kind {
+            MessageKind::Text => (None, None, None),
+            MessageKind::ToolCall { name } => (Some("tool_call"), Some(name), None),
+            MessageKind::ToolResult { name } => (Some("tool_result"), Some(name), None),
+            MessageKind::Voice { source, duration } => (Some("voice"), Some(source), *duration),
+        };
+        self.connection
+            .execute(
+                "INSERT INTO messages (conversation, sender, content, timestamp_ms, model, elapsed_ms, prompt_tokens, completion_tokens, cost, kind, tool, reasoning, duration_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    conversation,
+                    msg.sender,
+                    msg.content,
+                    msg.timestamp.map(millis),
+                    metadata.model,
+                    metadata.elapsed.map(|elapsed| elapsed.as_millis() as i64),
+                    metadata.prompt_tokens.map(|n| n as i64),
+                    metadata.completion_tokens.map(|n| n as i64),
+                    metadata.cost,
+                    kind,
+                    tool,
+                    msg.reasoning,
+                    duration.map(|duration| duration.as_millis() as i64),
+                ],
+            )
+            .map_err(io::Error::other)?;
+        Ok(self.connection.last_insert_rowid() as u64)
+    }
+
+    fn range(&self, conversation: &str, before: Option<u64>, limit: usize) -> io::Result<Vec<(u64, ChatMessage)>> {
+        let before = before.map_or(i64::MAX, |key| key as i64);
+        let sql = format!("SELECT {COLUMNS} FROM messages WHERE conversation = ?1 AND id < ?2 ORDER BY id DESC LIMIT ?3");
+        let mut messages = self.query(&sql, params![conversation, before, limit as i64])?;
+        messages.reverse();
+        Ok(messages)
+    }
+
+    fn search(&self, conversation: &str, query: &str, limit: usize) -> io::Result<Vec<(u64, ChatMessage)>> {
+        let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let pattern = format!("%{escaped}%");
+        let sql = format!(
+            "SELECT {COLUMNS} FROM messages WHERE conversation = ?1
+             AND (content LIKE ?2 ESCAPE '\\' OR sender LIKE ?2 ESCAPE '\\') ORDER BY id DESC LIMIT ?3"
+        );
+        self.query(&sql, params![conversation, pattern, limit as i64])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_page_and_search() {
+        let mut store = SqliteStore::in_memory().unwrap();
+        let sent = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let mut keys = Vec::new();
+        for i in 0..5 {
+            let msg = ChatMessage::new("AI", format!("reply {i}")).with_timestamp(sent);
+            keys.push(store.append("general", &msg).unwrap());
+        }
+        store.append("random", &ChatMessage::new("Bob", "100% off_topic")).unwrap();
+        store.append("tools", &ChatMessage::tool_call("AI", "search", "{}")).unwrap();
+
+        let newest = store.range("general", None, 2).unwrap();
+        let contents: Vec<&str> = newest.iter().map(|(_, msg)| msg.content.as_str()).collect();
+        assert_eq!(contents, ["reply 3", "reply 4"]);
+        assert_eq!(newest[0].1.timestamp, Some(sent));
+        let older = store.range("general", Some(newest[0].0), 10).unwrap();
+        assert_eq!(older.iter().map(|(key, _)| *key).collect::<Vec<_>>(), keys[..3]);
+
+        let hits = store.search("general", "REPLY", 2).unwrap();
+        assert_eq!(hits.iter().map(|(key, _)| *key).collect::<Vec<_>>(), [keys[4], keys[3]]);
+        assert_eq!(store.search("random", "0% off_", 10).unwrap().len(), 1);
+        assert!(store.search("random", "0%_off", 10).unwrap().is_empty());
+        let (_, call) = store.range("tools", None, 1).unwrap().remove(0);
+        assert_eq!(call.kind, MessageKind::ToolCall { name: "search".to_string() });
+        let voice = ChatMessage::voice("bob", "voice/1.ogg", Some(Duration::from_secs(42)));
+        store.append("voice", &voice).unwrap();
+        assert_eq!(store.range("voice", None, 1).unwrap()[0].1.kind, voice.kind);
+    }
+
+    #[test]
+    fn test_older_databases_are_migrated() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(SCHEMA).unwrap();
+        connection.execute_batch("ALTER TABLE messages ADD COLUMN kind TEXT; ALTER TABLE messages ADD COLUMN tool TEXT").unwrap();
+        connection.execute("INSERT INTO messages (conversation, sender, content) VALUES ('general', 'AI', 'old')", []).unwrap();
+
+        let mut store = SqliteStore::with_connection(connection).unwrap();
+        let version: i64 = store.connection.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, ADDED_COLUMNS.len() as i64);
+        let thought = ChatMessage { reasoning: Some("hmm".to_string()), ..ChatMessage::new("AI", "new") };
+        store.append("general", &thought).unwrap();
+        let messages = store.range("general", None, 10).unwrap();
+        assert_eq!(messages[0].1.content, "old");
+        assert_eq!(messages[1].1.reasoning.as_deref(), Some("hmm"));
+    }
+}
@@ -0,0 +1,111 @@
+//! Single-line status bar.
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+};
+
+use crate::{Strings, Theme, strings};
+
+/// State of the host's connection to its chat backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    /// No connection indicator is shown.
+    #[default]
+    Hidden,
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+impl ConnectionStatus {
+    fn span(self, strings: &Strings, theme: &Theme) -> Option<Span<'static>> {
+        let (label, color) = match self {
+            ConnectionStatus::Hidden => return None,
+            ConnectionStatus::Connecting => (&strings.connecting, Color::Yellow),
+            ConnectionStatus::Connected => (&strings.connected, Color::Green),
+            ConnectionStatus::Disconnected => (&strings.disconnected, Color::Red),
+        };
+        Some(Span::styled(format!("{} {label}", theme.glyph("●", "*")), Style::default().fg(color)))
+    }
+}
+
+/// A one-line bar showing the editing mode, connection status, key hints and scroll position.
+#[derive(Clone, Debug, Default)]
+pub struct StatusBar {
+    mode: String,
+    connection: ConnectionStatus,
+    hints: Vec<(String, String)>,
+    position: Option<(usize, usize)>,
+    strings: Strings,
+    theme: Theme,
+}
+
+impl StatusBar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_mode(&mut self, mode: impl Into<String>) {
+        self.mode = mode.into();
+    }
+
+    pub fn set_connection(&mut self, status: ConnectionStatus) {
+        self.connection = status;
+    }
+
+    pub fn connection(&self) -> ConnectionStatus {
+        self.connection
+    }
+
+    /// Sets the theme; only its glyph choice affects the status bar.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Sets the connection labels and the scroll position template.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    /// Sets the key hints as `(key, description)` pairs, e.g. `("Enter", "send")`.
+    pub fn set_hints(&mut self, hints: Vec<(String, String)>) {
+        self.hints = hints;
+    }
+
+    /// Sets the scroll position readout as `(line, total)`, or hides it with `None`.
+    pub fn set_position(&mut self, position: Option<(usize, usize)>) {
+        self.position = position;
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let dim = Style::default().add_modifier(Modifier::DIM);
+        let mut spans = Vec::new();
+        if !self.mode.is_empty() {
+            spans.push(Span::styled(format!(" {} ", self.mode), Style::default().add_modifier(Modifier::REVERSED)));
+            spans.push(Span::raw(" "));
+        }
+        if let Some(connection) = self.connection.span(&self.strings, &self.theme) {
+            spans.push(connection);
+            spans.push(Span::raw("  "));
+        }
+        for (i, (key, description)) in self.hints.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::styled(self.theme.glyph(" · ", " | "), dim));
+            }
+            spans.push(Span::styled(key.clone(), Style::default().add_modifier(Modifier::BOLD)));
+            spans.push(Span::styled(format!(" {description}"), dim));
+        }
+        let position = self
+            .position
+            .map(|(line, total)| format!(" {} ", strings::fill(&self.strings.scroll_position, &[("line", &line), ("total", &total)])))
+            .unwrap_or_default();
+        let width = Line::raw(position.as_str()).width() as u16;
+        let [left, right] = Layout::horizontal([Constraint::Min(0), Constraint::Length(width)]).areas(area);
+        frame.render_widget(Paragraph::new(Line::from(spans)), left);
+        frame.render_widget(Paragraph::new(position), right);
+    }
+}
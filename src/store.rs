@@ -0,0 +1,32 @@
+//! Durable message history that a [`ChatApp`](crate::ChatApp) saves to and pages through.
+
+use std::io;
+
+use crate::ChatMessage;
+
+/// Storage for each conversation's transcript, set up with
+/// [`ChatApp::set_message_store`](crate::ChatApp::set_message_store).
+///
+/// Conversations are keyed by name. Every message gets a key from [`append`](Self::append)
+/// that is larger than the keys of the messages stored before it, which is what
+/// [`range`](Self::range) pages by. Use [`SqliteStore`](crate::SqliteStore) with the
+/// `sqlite` feature, or implement it over your own storage.
+pub trait MessageStore {
+    /// Saves a message at the end of `conversation` and returns its key.
+    fn append(&mut self, conversation: &str, msg: &ChatMessage) -> io::Result<u64>;
+    /// Up to `limit` messages of `conversation` stored before the key `before`, or the
+    /// newest ones if it is `None`, oldest first and with their keys.
+    fn range(&self, conversation: &str, before: Option<u64>, limit: usize) -> io::Result<Vec<(u64, ChatMessage)>>;
+    /// Up to `limit` messages of `conversation` whose sender or content contains `query`,
+    /// newest first and with their keys.
+    fn search(&self, conversation: &str, query: &str, limit: usize) -> io::Result<Vec<(u64, ChatMessage)>>;
+}
+
+/// How far back a conversation's history has been loaded from the store.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct HistoryCursor {
+    /// Key of the oldest stored message in the chat.
+    pub(crate) before: Option<u64>,
+    /// Whether there is nothing older left to load.
+    pub(crate) exhausted: bool,
+}
@@ -0,0 +1,296 @@
+//! User-visible text used by the built-in widgets.
+
+use std::collections::HashMap;
+
+use crate::{Action, Theme};
+
+/// The text the widgets draw as titles, labels and hints.
+///
+/// [`Strings::default`] is English. Replace the table wholesale with [`ChatApp::set_strings`](crate::ChatApp::set_strings),
+/// or per widget with its `set_strings`, to localize the interface. Templates contain
+/// `{name}` placeholders that are substituted when drawn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Strings {
+    pub chat_title: String,
+    pub input_title: String,
+    pub preview_title: String,
+    /// Estimated tokens in the input, see [`InputArea::set_token_counter`](crate::InputArea::set_token_counter).
+    /// Placeholder: `{count}`.
+    pub token_count: String,
+    /// Shown instead of `token_count` over the token budget. Placeholders: `{count}`, `{budget}`.
+    pub token_budget_exceeded: String,
+    /// Shown on the input border while submission is on cooldown. Placeholder: `{seconds}`.
+    pub cooldown: String,
+    /// Appended to a streaming message the user cancelled.
+    pub cancelled: String,
+    pub pinned_title: String,
+    /// Shown on the chat border while the pinned panel is hidden. Placeholder: `{count}`.
+    pub pinned_count: String,
+    pub channels_title: String,
+    /// Shown in the tab bar and channel list next to conversations with an unsent draft.
+    pub draft_marker: String,
+    /// [`Strings::draft_marker`] in ASCII, used with [`Theme::plain_glyphs`].
+    pub draft_marker_plain: String,
+    /// Placeholders: `{online}`, `{total}`.
+    pub members_title: String,
+    pub settings_title: String,
+    pub settings_theme: String,
+    pub settings_density: String,
+    pub settings_timestamps: String,
+    pub settings_metadata: String,
+    pub settings_keymap: String,
+    pub settings_reduced_motion: String,
+    pub settings_help: String,
+    pub on: String,
+    pub off: String,
+    pub confirm_title: String,
+    pub date_picker_title: String,
+    pub date_picker_help: String,
+    /// Titles of the message detail popup, showing the message rendered or as its raw source.
+    pub detail_title: String,
+    pub detail_source_title: String,
+    pub detail_help: String,
+    /// Search line of the message detail popup. Placeholders: `{query}`, `{count}` (the number of matching lines).
+    pub detail_search: String,
+    pub yes: String,
+    pub no: String,
+    pub toast_info: String,
+    pub toast_warn: String,
+    pub toast_error: String,
+    /// Toast for a message that notification rules flag. Placeholders: `{sender}`, `{content}`.
+    pub notify_toast: String,
+    /// Toasts for copying a code block. Placeholder: `{n}` (the block's number).
+    pub code_copied: String,
+    pub code_missing: String,
+    /// Toast for copying text selected with the mouse. Placeholder: `{count}` (the number of characters).
+    pub selection_copied: String,
+    /// Toast for copying a whole message from its detail popup.
+    pub message_copied: String,
+    /// Shown when the clipboard cannot be written. Placeholder: `{error}`.
+    pub clipboard_error: String,
+    /// Shown when the message store fails. Placeholder: `{error}`.
+    pub history_error: String,
+    /// Placeholder for a voice message, after `▶` and before its duration.
+    pub voice_message: String,
+    /// Name of an imported conversation that has no title.
+    pub imported_title: String,
+    pub connecting: String,
+    pub connected: String,
+    pub disconnected: String,
+    /// Scroll readout in the status bar. Placeholders: `{line}`, `{total}`.
+    pub scroll_position: String,
+    pub mode_input: String,
+    pub mode_channels: String,
+    pub mode_filter: String,
+    pub mode_messages: String,
+    /// Shown on the chat border while a filter is set. Placeholders: `{query}`, `{count}`.
+    pub filter_matches: String,
+    /// Like `filter_matches`, for a regular expression filter.
+    pub regex_matches: String,
+    /// Shown on the chat border while the filter pattern is invalid. Placeholders: `{query}`, `{error}`.
+    pub filter_invalid: String,
+    pub hint_regex: String,
+    pub hint_keep: String,
+    pub hint_clear: String,
+    pub hint_select: String,
+    pub hint_open: String,
+    pub hint_back: String,
+    pub hint_expand: String,
+    pub hint_fold: String,
+    pub hint_reveal: String,
+    pub hint_detail: String,
+    pub hint_scroll_code: String,
+    pub hint_copy_code: String,
+    /// Shown on the chat border while the selected message's unwrapped code is wider than
+    /// the chat. Placeholders: `{from}`, `{to}` (the visible columns), `{total}`.
+    pub code_columns: String,
+    /// Appended to the chat title while the scroll position is shown and the messages do
+    /// not fit. Placeholders: `{line}` (the bottom visible row), `{total}` (all rows),
+    /// `{percent}`.
+    pub title_position: String,
+    /// Summary of a folded code block. Placeholders: `{lang}`, `{count}` (the number of lines).
+    pub folded_code: String,
+    /// Summary of a folded quote. Placeholder: `{count}`.
+    pub folded_quote: String,
+    /// Label of the divider above the first unread message.
+    pub unread_marker: String,
+    /// Shown at the right of the newest message participants have read. Placeholders:
+    /// `{check}` (`✓`, or `v` with plain glyphs), `{names}`.
+    pub read_by: String,
+    /// Row standing in for a message's reasoning. Placeholder: `{count}` (the number of characters).
+    pub reasoning_folded: String,
+    /// Heading above a message's reasoning once expanded.
+    pub reasoning_expanded: String,
+    /// Footer of a collapsed long message. Placeholder: `{count}` (the number of hidden rows).
+    pub show_more: String,
+    /// Placeholder: `{a}`.
+    pub typing_one: String,
+    /// Placeholders: `{a}`, `{b}`.
+    pub typing_two: String,
+    /// Placeholders: `{a}`, `{b}`, `{c}`.
+    pub typing_three: String,
+    /// Placeholders: `{a}`, `{b}`, `{count}` (the number of remaining users).
+    pub typing_many: String,
+    /// Relative timestamp of a message less than a minute old.
+    pub time_just_now: String,
+    /// Relative timestamps. Placeholder: `{count}`.
+    pub time_minutes_ago: String,
+    pub time_hours_ago: String,
+    pub time_days_ago: String,
+    /// Token usage in a message's metadata footer. Placeholders: `{prompt}`, `{completion}`.
+    pub metadata_tokens: String,
+    /// Key hint descriptions. Actions missing from the map fall back to [`Action::description`].
+    pub actions: HashMap<Action, String>,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        let s = |s: &str| s.to_string();
+        Self {
+            chat_title: s("Chat"),
+            input_title: s("Input"),
+            preview_title: s("Preview"),
+            token_count: s("~{count} tokens"),
+            token_budget_exceeded: s("~{count}/{budget} tokens"),
+            cooldown: s("(wait {seconds}s)"),
+            cancelled: s("(cancelled)"),
+            pinned_title: s("Pinned"),
+            pinned_count: s("({count} pinned)"),
+            channels_title: s("Channels"),
+            draft_marker: s("✎"),
+            draft_marker_plain: s("*"),
+            members_title: s("Members {online}/{total}"),
+            settings_title: s("Settings"),
+            settings_theme: s("Theme"),
+            settings_density: s("Density"),
+            settings_timestamps: s("Timestamps"),
+            settings_metadata: s("Metadata"),
+            settings_keymap: s("Keymap"),
+            settings_reduced_motion: s("Reduced motion"),
+            settings_help: s("↑↓ choose · ←→ change · Esc close"),
+            on: s("On"),
+            off: s("Off"),
+            confirm_title: s("Confirm"),
+            date_picker_title: s("Jump to"),
+            date_picker_help: s("←→ day · ↑↓ hour · ⇧↑↓ 10 min"),
+            detail_title: s("Message"),
+            detail_source_title: s("Message source"),
+            detail_help: s("↑↓ scroll · / search · n/N next · r source · y copy · 1-9 code · Esc close"),
+            detail_search: s("/{query} · {count} lines"),
+            yes: s("Yes"),
+            no: s("No"),
+            toast_info: s("Info"),
+            toast_warn: s("Warning"),
+            toast_error: s("Error"),
+            notify_toast: s("{sender}: {content}"),
+            code_copied: s("Copied code block {n}"),
+            code_missing: s("No code block {n}"),
+            selection_copied: s("Copied {count} characters"),
+            message_copied: s("Copied message"),
+            clipboard_error: s("Clipboard unavailable: {error}"),
+            history_error: s("History unavailable: {error}"),
+            voice_message: s("Voice message"),
+            imported_title: s("Imported"),
+            connecting: s("connecting"),
+            connected: s("connected"),
+            disconnected: s("disconnected"),
+            scroll_position: s("line {line}/{total}"),
+            mode_input: s("INPUT"),
+            mode_channels: s("CHANNELS"),
+            mode_filter: s("FILTER"),
+            mode_messages: s("SELECT"),
+            filter_matches: s("/{query} · {count} matches"),
+            regex_matches: s("re/{query} · {count} matches"),
+            filter_invalid: s("re/{query} · {error}"),
+            hint_regex: s("regex"),
+            hint_keep: s("keep"),
+            hint_clear: s("clear"),
+            hint_select: s("select"),
+            hint_open: s("open"),
+            hint_back: s("back"),
+            hint_expand: s("expand"),
+            hint_fold: s("fold"),
+            hint_reveal: s("reveal"),
+            hint_detail: s("details"),
+            hint_scroll_code: s("scroll code"),
+            hint_copy_code: s("copy code"),
+            code_columns: s("‹ col {from}-{to}/{total} ›"),
+            title_position: s("— {line}/{total} ({percent}%)"),
+            folded_code: s("▸ ```{lang} … ({count} lines)"),
+            folded_quote: s("▸ > … ({count} lines)"),
+            unread_marker: s("new"),
+            read_by: s("{check} {names}"),
+            reasoning_folded: s("▸ reasoning ({count} chars)"),
+            reasoning_expanded: s("▾ reasoning"),
+            show_more: s("… show more ({count} lines)"),
+            typing_one: s("{a} is typing…"),
+            typing_two: s("{a} and {b} are typing…"),
+            typing_three: s("{a}, {b} and {c} are typing…"),
+            typing_many: s("{a}, {b} and {count} others are typing…"),
+            time_just_now: s("just now"),
+            time_minutes_ago: s("{count}m ago"),
+            time_hours_ago: s("{count}h ago"),
+            time_days_ago: s("{count}d ago"),
+            metadata_tokens: s("{prompt} → {completion} tokens"),
+            actions: HashMap::new(),
+        }
+    }
+}
+
+impl Strings {
+    /// The key hint description for an action.
+    pub fn action(&self, action: Action) -> &str {
+        self.actions.get(&action).map_or(action.description(), String::as_str)
+    }
+
+    /// The draft marker to draw with `theme`.
+    pub(crate) fn draft_marker(&self, theme: &Theme) -> &str {
+        if theme.plain_glyphs { &self.draft_marker_plain } else { &self.draft_marker }
+    }
+}
+
+/// Substitutes `{name}` placeholders in `template` with the matching values, in one pass,
+/// so braces in a value are never taken for placeholders. Unknown placeholders are kept.
+pub(crate) fn fill(template: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| Some((args.iter().find(|(name, _)| *name == &after[..end])?.1, end)));
+        match value {
+            Some((value, end)) => {
+                let _ = write!(out, "{value}");
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_substitutes_in_one_pass() {
+        assert_eq!(fill("{a} and {b}", &[("a", &"{b}"), ("b", &1)]), "{b} and 1");
+        assert_eq!(fill("{{a}} {x} {", &[("a", &"é")]), "{é} {x} {");
+    }
+
+    #[test]
+    fn test_fill_and_action_fallback() {
+        let mut strings = Strings::default();
+        assert_eq!(fill(&strings.members_title, &[("online", &2), ("total", &5)]), "Members 2/5");
+        assert_eq!(strings.action(Action::Submit), "send");
+        strings.actions.insert(Action::Submit, "senden".to_string());
+        assert_eq!(strings.action(Action::Submit), "senden");
+    }
+}
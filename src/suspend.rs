@@ -0,0 +1,23 @@
+//! Suspending to the shell with Ctrl+Z, which raw mode otherwise delivers as an ordinary key.
+
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor::Show,
+    event::{DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste, EnableFocusChange, EnableMouseCapture},
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+
+/// Restores the terminal, stops the process with `SIGTSTP` as the shell would, and sets the
+/// terminal up again once the process is resumed with `fg`.
+pub(crate) fn suspend(out: &mut impl Write) -> io::Result<()> {
+    crossterm::execute!(out, LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste, DisableFocusChange, Show)?;
+    disable_raw_mode()?;
+    // SAFETY: raise has no memory safety requirements. The default SIGTSTP action stops
+    // the process here until it is continued.
+    unsafe {
+        libc::raise(libc::SIGTSTP);
+    }
+    enable_raw_mode()?;
+    crossterm::execute!(out, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste, EnableFocusChange)
+}
@@ -0,0 +1,178 @@
+//! Markdown pipe tables drawn as aligned, box-drawn tables.
+
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// Border characters: corners and junctions top to bottom, then the horizontal and vertical lines.
+struct Glyphs {
+    top: [char; 3],
+    middle: [char; 3],
+    bottom: [char; 3],
+    horizontal: char,
+    vertical: char,
+}
+
+const BOX: Glyphs = Glyphs {
+    top: ['┌', '┬', '┐'],
+    middle: ['├', '┼', '┤'],
+    bottom: ['└', '┴', '┘'],
+    horizontal: '─',
+    vertical: '│',
+};
+
+const ASCII: Glyphs = Glyphs {
+    top: ['+', '+', '+'],
+    middle: ['+', '+', '+'],
+    bottom: ['+', '+', '+'],
+    horizontal: '-',
+    vertical: '|',
+};
+
+/// Redraws every pipe table in `content` to fit within `width` columns, or returns `None`
+/// if there is no table. Columns wider than the space allows are narrowed, widest first,
+/// and their cells wrapped; a table that cannot fit even then is left as written.
+/// Tables inside fenced code blocks are not touched.
+pub(crate) fn render(content: &str, width: usize, plain: bool) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut found = false;
+    let mut in_code = false;
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let aligns = lines.get(i + 1).and_then(|line| separator(line));
+        if !in_code && lines[i].contains('|') && let Some(aligns) = aligns {
+            let header = cells(lines[i]);
+            let end = (i + 2..lines.len()).find(|&j| !lines[j].contains('|')).unwrap_or(lines.len());
+            let body: Vec<Vec<String>> = lines[i + 2..end].iter().map(|line| cells(line)).collect();
+            if let Some(table) = draw(&header, &aligns, &body, width, if plain { &ASCII } else { &BOX }) {
+                out.extend(table);
+                found = true;
+                i = end;
+                continue;
+            }
+        }
+        out.push(lines[i].to_string());
+        i += 1;
+    }
+    found.then(|| out.join("\n"))
+}
+
+/// The column alignments of a separator row such as `|:---|--:|`, or `None` if `line` is not one.
+fn separator(line: &str) -> Option<Vec<Align>> {
+    let aligns: Option<Vec<Align>> = cells(line)
+        .iter()
+        .map(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Align::Center,
+                (false, true) => Align::Right,
+                _ => Align::Left,
+            })
+        })
+        .collect();
+    aligns.filter(|aligns| !aligns.is_empty())
+}
+
+/// The trimmed cells of a table row, without the optional outer pipes.
+fn cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(|cell| cell.trim().to_string()).collect()
+}
+
+fn draw(header: &[String], aligns: &[Align], body: &[Vec<String>], width: usize, glyphs: &Glyphs) -> Option<Vec<String>> {
+    let columns = aligns.len();
+    let cell = |row: &[String], c: usize| row.get(c).map_or("", String::as_str).to_string();
+    let rows: Vec<Vec<String>> =
+        std::iter::once(header).chain(body.iter().map(Vec::as_slice)).map(|row| (0..columns).map(|c| cell(row, c)).collect()).collect();
+    let mut widths: Vec<usize> =
+        (0..columns).map(|c| rows.iter().map(|row| row[c].width()).max().unwrap_or(0).max(1)).collect();
+
+    // Each column takes its width plus a space either side and a border.
+    let available = width.checked_sub(3 * columns + 1)?;
+    if available < columns {
+        return None;
+    }
+    while widths.iter().sum::<usize>() > available {
+        let widest = (0..columns).max_by_key(|&c| widths[c]).expect("at least one column");
+        widths[widest] -= 1;
+    }
+
+    let rule = |[left, junction, right]: [char; 3]| {
+        let parts: Vec<String> = widths.iter().map(|&w| glyphs.horizontal.to_string().repeat(w + 2)).collect();
+        format!("{left}{}{right}", parts.join(&junction.to_string()))
+    };
+    let mut out = vec![rule(glyphs.top)];
+    for (r, row) in rows.iter().enumerate() {
+        let wrapped: Vec<Vec<String>> =
+            row.iter().zip(&widths).map(|(text, &w)| textwrap::wrap(text, w).into_iter().map(String::from).collect()).collect();
+        let height = wrapped.iter().map(Vec::len).max().unwrap_or(1).max(1);
+        for line in 0..height {
+            let mut text = glyphs.vertical.to_string();
+            for c in 0..columns {
+                let part = wrapped[c].get(line).map_or("", String::as_str);
+                let pad = widths[c].saturating_sub(part.width());
+                let (before, after) = match aligns[c] {
+                    Align::Left => (0, pad),
+                    Align::Right => (pad, 0),
+                    Align::Center => (pad / 2, pad - pad / 2),
+                };
+                text.push_str(&format!(" {}{part}{} {}", " ".repeat(before), " ".repeat(after), glyphs.vertical));
+            }
+            out.push(text);
+        }
+        if r == 0 {
+            out.push(rule(glyphs.middle));
+        }
+    }
+    out.push(rule(glyphs.bottom));
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_align_and_wrap_to_width() {
+        let content = "Results:\n| name | score |\n|:-----|------:|\n| alice | 7 |\n| bob | 12 |\nend";
+        let expected = [
+            "Results:",
+            "┌───────┬───────┐",
+            "│ name  │ score │",
+            "├───────┼───────┤",
+            "│ alice │     7 │",
+            "│ bob   │    12 │",
+            "└───────┴───────┘",
+            "end",
+        ];
+        assert_eq!(render(content, 40, false).unwrap(), expected.join("\n"));
+
+        let narrow = render("a | b\n--|:-:\nlong words here | x", 15, true).unwrap();
+        let expected = [
+            "+---------+---+",
+            "| a       | b |",
+            "+---------+---+",
+            "| long    | x |",
+            "| words   |   |",
+            "| here    |   |",
+            "+---------+---+",
+        ];
+        assert_eq!(narrow, expected.join("\n"));
+        assert!(render("a || b\nno table", 40, false).is_none());
+        assert!(render("| a | b |\n|---|---|", 5, false).is_none());
+    }
+}
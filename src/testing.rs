@@ -0,0 +1,441 @@
+//! Helpers for driving a [`ChatApp`] with synthetic events in tests, without a terminal.
+//!
+//! ```
+//! use crossterm::event::KeyCode;
+//! use tui_chat::testing::{AppHarness, key};
+//!
+//! let mut harness = AppHarness::new();
+//! harness.type_text("hello").press(key(KeyCode::Enter));
+//! assert_eq!(harness.submitted(), ["hello"]);
+//! assert_eq!(harness.input(), "");
+//! ```
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{Frame, Terminal, backend::TestBackend, buffer::Buffer, layout::Rect};
+use unicode_width::UnicodeWidthStr;
+
+use crate::ChatApp;
+
+/// A key press without modifiers.
+pub fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+/// A character key pressed with Ctrl.
+pub fn ctrl(c: char) -> KeyEvent {
+    KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+}
+
+/// A character key pressed with Alt.
+pub fn alt(c: char) -> KeyEvent {
+    KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT)
+}
+
+/// A key pressed with Shift.
+pub fn shift(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::SHIFT)
+}
+
+/// A mouse event of `kind` at a screen cell.
+pub fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+    MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    }
+}
+
+/// Draws into `buf` within `area` by rendering to an in-memory terminal and copying the cells over.
+pub(crate) fn render_headless(buf: &mut Buffer, area: Rect, draw: impl FnOnce(&mut Frame, Rect)) {
+    let area = area.intersection(buf.area);
+    let mut terminal = Terminal::new(TestBackend::new(area.right(), area.bottom())).expect("test backend never fails");
+    terminal.draw(|frame| draw(frame, area)).expect("test backend never fails");
+    let rendered = terminal.backend().buffer();
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            buf[(x, y)] = rendered[(x, y)].clone();
+        }
+    }
+}
+
+/// The text of a buffer as a grid, one line per row with trailing spaces trimmed, for
+/// snapshot tests. Styles are not included.
+pub fn buffer_to_string(buf: &Buffer) -> String {
+    let mut out = String::new();
+    for y in buf.area.top()..buf.area.bottom() {
+        let mut row = String::new();
+        let mut x = buf.area.left();
+        while x < buf.area.right() {
+            let symbol = buf[(x, y)].symbol();
+            row.push_str(symbol);
+            // The cells covered by a wide character hold no text of their own.
+            x += symbol.width().max(1) as u16;
+        }
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Owns a [`ChatApp`] drawn to an in-memory terminal, and feeds it events.
+///
+/// The app is redrawn after every event, so layout-dependent handling such as mouse
+/// hit-testing and the cursor position match what a real terminal would show.
+pub struct AppHarness {
+    app: ChatApp,
+    terminal: Terminal<TestBackend>,
+}
+
+impl Default for AppHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppHarness {
+    /// A harness around a new [`ChatApp`] on an 80x24 screen.
+    pub fn new() -> Self {
+        Self::with_app(ChatApp::new(), 80, 24)
+    }
+
+    /// A harness around an already configured app, on a screen of the given size.
+    pub fn with_app(app: ChatApp, width: u16, height: u16) -> Self {
+        let terminal = Terminal::new(TestBackend::new(width, height)).expect("test backend never fails");
+        let mut harness = Self { app, terminal };
+        harness.draw();
+        harness
+    }
+
+    pub fn app(&self) -> &ChatApp {
+        &self.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut ChatApp {
+        &mut self.app
+    }
+
+    pub fn into_app(self) -> ChatApp {
+        self.app
+    }
+
+    fn draw(&mut self) {
+        let app = &mut self.app;
+        self.terminal.draw(|frame| app.render(frame)).expect("test backend never fails");
+    }
+
+    /// Feeds a terminal event, as the event loop would.
+    pub fn send(&mut self, event: Event) -> &mut Self {
+        if let Event::Resize(width, height) = event {
+            self.terminal.backend_mut().resize(width, height);
+        }
+        self.app.on_event(event);
+        self.draw();
+        self
+    }
+
+    /// Feeds a sequence of events in order.
+    pub fn send_all(&mut self, events: impl IntoIterator<Item = Event>) -> &mut Self {
+        for event in events {
+            self.send(event);
+        }
+        self
+    }
+
+    pub fn press(&mut self, key: KeyEvent) -> &mut Self {
+        self.send(Event::Key(key))
+    }
+
+    /// Types each character of `text` as a separate key press. Newlines press Enter.
+    pub fn type_text(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            self.press(key(if c == '\n' { KeyCode::Enter } else { KeyCode::Char(c) }));
+        }
+        self
+    }
+
+    /// Pastes `text` as a single bracketed paste.
+    pub fn paste(&mut self, text: &str) -> &mut Self {
+        self.send(Event::Paste(text.to_string()))
+    }
+
+    /// Left-clicks a screen cell.
+    pub fn click(&mut self, column: u16, row: u16) -> &mut Self {
+        self.send(Event::Mouse(mouse(MouseEventKind::Down(MouseButton::Left), column, row)));
+        self.send(Event::Mouse(mouse(MouseEventKind::Up(MouseButton::Left), column, row)))
+    }
+
+    /// Turns the mouse wheel over a screen cell, `up` or down.
+    pub fn scroll(&mut self, column: u16, row: u16, up: bool) -> &mut Self {
+        let kind = if up { MouseEventKind::ScrollUp } else { MouseEventKind::ScrollDown };
+        self.send(Event::Mouse(mouse(kind, column, row)))
+    }
+
+    /// Text in the active conversation's input.
+    pub fn input(&self) -> String {
+        self.app.conversations.active().input_area().buffer.to_string()
+    }
+
+    /// Cursor position in the active input, in bytes.
+    pub fn cursor(&self) -> usize {
+        self.app.conversations.active().input_area().cursor
+    }
+
+    /// Contents of the messages the user has submitted in the active conversation, oldest first.
+    pub fn submitted(&self) -> Vec<&str> {
+        let chat = self.app.conversations.active().chat_area();
+        chat.messages.iter().filter(|m| m.sender == "User").map(|m| m.content.as_str()).collect()
+    }
+
+    /// The screen as last drawn.
+    pub fn buffer(&self) -> &Buffer {
+        self.terminal.backend().buffer()
+    }
+
+    /// The screen as last drawn, one string per row with trailing spaces trimmed.
+    pub fn screen(&self) -> Vec<String> {
+        buffer_to_string(self.buffer()).lines().map(String::from).collect()
+    }
+
+    /// Whether any row of the screen contains `text`.
+    pub fn screen_contains(&self, text: &str) -> bool {
+        self.screen().iter().any(|row| row.contains(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_editing_and_submit() {
+        let mut harness = AppHarness::new();
+        harness.type_text("helo").press(key(KeyCode::Left)).type_text("l");
+        assert_eq!(harness.input(), "hello");
+        assert_eq!(harness.cursor(), 4);
+        harness.press(key(KeyCode::Enter)).paste("second").press(key(KeyCode::Enter));
+        assert_eq!(harness.submitted(), ["hello", "second"]);
+        assert!(harness.screen_contains("User: second"));
+        harness.press(ctrl('c'));
+        assert!(harness.app().should_quit());
+    }
+
+    #[test]
+    fn test_needs_redraw_after_changes_only() {
+        let mut harness = AppHarness::new();
+        assert!(!harness.app().needs_redraw());
+        harness.app_mut().tick();
+        assert!(!harness.app().needs_redraw());
+        harness.app_mut().on_paste("x".to_string());
+        assert!(harness.app().needs_redraw());
+        harness.send(Event::FocusGained);
+        assert!(!harness.app().needs_redraw());
+        harness.app_mut().conversations_mut().active_mut().chat_area_mut().add_message(crate::ChatMessage::new("AI", "hi"));
+        assert!(harness.app().needs_redraw());
+    }
+
+    #[test]
+    fn test_markdown_preview_modes() {
+        let mut harness = AppHarness::new();
+        harness.type_text("**bold** `code`");
+        assert!(!harness.screen_contains("Preview"));
+        harness.press(ctrl('p'));
+        assert_eq!(harness.app().preview_mode(), crate::PreviewMode::SideBySide);
+        assert!(harness.screen_contains("│> **bold** `code`"));
+        assert!(harness.screen_contains("│bold code"));
+        harness.press(ctrl('p'));
+        assert!(!harness.screen_contains("**bold**"));
+        assert!(harness.app().get_cursor_pos().is_none());
+        harness.type_text("!").press(ctrl('p'));
+        assert_eq!(harness.app().preview_mode(), crate::PreviewMode::Off);
+        assert!(harness.screen_contains("**bold** `code`!"));
+    }
+
+    #[test]
+    fn test_cooldown_rejects_submit_until_ready() {
+        use std::{cell::Cell, rc::Rc, time::Duration};
+        let mut harness = AppHarness::new();
+        let ready = Rc::new(Cell::new(false));
+        let flag = ready.clone();
+        let input = harness.app_mut().conversations_mut().active_mut().input_area_mut();
+        input.set_on_cooldown_end(move || flag.set(true));
+        harness.app_mut().set_cooldown(Duration::from_secs(5));
+        harness.type_text("hi").press(key(KeyCode::Enter));
+        assert!(harness.submitted().is_empty());
+        assert_eq!(harness.input(), "hi");
+        assert!(harness.screen_contains("Input─(wait 5s)"));
+
+        harness.app_mut().set_cooldown(Duration::ZERO);
+        harness.app_mut().tick();
+        assert!(ready.get());
+        harness.press(key(KeyCode::Enter));
+        assert_eq!(harness.submitted(), ["hi"]);
+        assert!(!harness.screen_contains("wait"));
+    }
+
+    #[test]
+    fn test_drafts_survive_switching_conversations() {
+        let mut harness = AppHarness::new();
+        harness.app_mut().conversations_mut().add_conversation("other");
+        harness.type_text("half done").press(key(KeyCode::Left));
+        harness.press(KeyEvent::new(KeyCode::Tab, KeyModifiers::CONTROL));
+        assert_eq!(harness.input(), "");
+        assert!(harness.screen_contains("Chat ✎"));
+        harness.press(alt('1'));
+        assert_eq!(harness.input(), "half done");
+        assert_eq!(harness.cursor(), 8);
+        assert!(!harness.screen_contains("✎"));
+    }
+
+    #[test]
+    fn test_drag_and_keys_move_splitter() {
+        let mut harness = AppHarness::new();
+        assert_eq!(harness.app().input_split(), None);
+        let border = harness.screen().iter().position(|row| row.starts_with("┌Input")).unwrap() as u16;
+        assert_eq!(border, 21);
+        harness.send(Event::Mouse(mouse(MouseEventKind::Down(MouseButton::Left), 10, border)));
+        harness.send(Event::Mouse(mouse(MouseEventKind::Drag(MouseButton::Left), 10, 12)));
+        harness.send(Event::Mouse(mouse(MouseEventKind::Up(MouseButton::Left), 10, 12)));
+        assert_eq!(harness.app().input_split(), Some(50));
+        assert!(harness.screen()[12].starts_with("┌Input"));
+
+        harness.press(KeyEvent::new(KeyCode::Down, KeyModifiers::ALT | KeyModifiers::SHIFT));
+        assert_eq!(harness.app().input_split(), Some(45));
+        harness.app_mut().set_input_split(None);
+        harness.send(Event::FocusGained);
+        harness.press(KeyEvent::new(KeyCode::Up, KeyModifiers::ALT | KeyModifiers::SHIFT));
+        assert_eq!(harness.app().input_split(), Some(17));
+    }
+
+    #[test]
+    fn test_long_messages_collapse_until_expanded() {
+        let mut harness = AppHarness::new();
+        let chat = harness.app_mut().conversations_mut().active_mut().chat_area_mut();
+        chat.set_collapse_long_messages(Some(3));
+        let long: Vec<String> = (1..=20).map(|i| format!("row {i}")).collect();
+        let id = chat.add_message(crate::ChatMessage::new("AI", long.join("\n")));
+        chat.add_message(crate::ChatMessage::new("AI", "short"));
+        harness.send(Event::FocusGained);
+        assert!(harness.screen_contains("… show more (17 lines)"));
+        assert!(!harness.screen_contains("row 4"));
+
+        harness.press(KeyEvent::new(KeyCode::Up, KeyModifiers::CONTROL)).press(key(KeyCode::Up));
+        let chat = harness.app().conversations().active().chat_area();
+        assert_eq!(chat.selected_message(), Some(id));
+        harness.press(key(KeyCode::Enter));
+        assert!(harness.screen_contains("row 4"));
+        assert!(!harness.screen_contains("show more"));
+        harness.press(key(KeyCode::Esc));
+        assert_eq!(harness.app().conversations().active().chat_area().selected_message(), None);
+        harness.type_text("x");
+        assert_eq!(harness.input(), "x");
+    }
+
+    #[test]
+    fn test_fold_code_blocks_of_selected_message() {
+        let mut harness = AppHarness::new();
+        let code: String = (0..50).map(|i| format!("let x{i} = {i};\n")).collect();
+        let chat = harness.app_mut().conversations_mut().active_mut().chat_area_mut();
+        let id = chat.add_message(crate::ChatMessage::new("AI", format!("Try:\n```rust\n{code}```\nDone.")));
+        harness.press(KeyEvent::new(KeyCode::Up, KeyModifiers::CONTROL)).press(key(KeyCode::Char('z')));
+        assert!(harness.app().conversations().active().chat_area().is_folded(id));
+        assert!(harness.screen_contains("▸ ```rust … (50 lines)"));
+        assert!(harness.screen_contains("Done."));
+        assert!(!harness.screen_contains("let x49"));
+        harness.press(key(KeyCode::Char('z')));
+        assert!(harness.screen_contains("let x49 = 49;"));
+    }
+
+    #[test]
+    fn test_spoilers_hidden_until_revealed() {
+        let mut harness = AppHarness::new();
+        let chat = harness.app_mut().conversations_mut().active_mut().chat_area_mut();
+        chat.set_parse_spoilers(true);
+        chat.add_message(crate::ChatMessage::new("bob", "it was ||the butler||, a || b"));
+        harness.send(Event::FocusGained);
+        assert!(harness.screen_contains("bob: it was ▒▒▒▒▒▒▒▒▒▒, a || b"));
+        harness.press(KeyEvent::new(KeyCode::Up, KeyModifiers::CONTROL)).press(key(KeyCode::Char('r')));
+        assert!(harness.screen_contains("bob: it was the butler, a || b"));
+    }
+
+    #[test]
+    fn test_inline_math_is_shown_in_unicode() {
+        let mut harness = AppHarness::new();
+        let chat = harness.app_mut().conversations_mut().active_mut().chat_area_mut();
+        chat.set_render_math(true);
+        chat.add_message(crate::ChatMessage::new("AI", r"so $a^2 + b^2 \neq \pi$, not $\unknown$"));
+        harness.send(Event::FocusGained);
+        assert!(harness.screen_contains(r"AI: so a² + b² ≠ π, not $\unknown$"));
+    }
+
+    #[test]
+    fn test_clicks_switch_tabs_and_channels() {
+        let mut harness = AppHarness::new();
+        harness.app_mut().conversations_mut().add_conversation("second");
+        harness.send(Event::FocusGained);
+        // The tab bar reads " General │ second ".
+        harness.click(12, 0);
+        assert_eq!(harness.app().conversations().active_index(), 1);
+        harness.click(2, 0);
+        assert_eq!(harness.app().conversations().active_index(), 0);
+
+        harness.app_mut().set_show_channels(true);
+        harness.send(Event::FocusGained).click(3, 2);
+        assert_eq!(harness.app().conversations().active_index(), 1);
+    }
+
+    #[test]
+    fn test_resize_keeps_scrolled_back_message_in_view() {
+        let mut harness = AppHarness::with_app(ChatApp::new(), 40, 12);
+        let chat = harness.app_mut().chat_area_mut();
+        let ids: Vec<_> = (0..30)
+            .map(|i| chat.add_message(crate::ChatMessage::new("AI", format!("message {i} with some words to wrap around"))))
+            .collect();
+        harness.send(Event::FocusGained);
+        harness.app_mut().chat_area_mut().scroll_to_message(ids[10], crate::ScrollAlignment::Top);
+        harness.send(Event::FocusGained);
+        assert!(harness.screen()[1].contains("AI: message 10 "));
+        harness.send(Event::Resize(80, 12));
+        assert!(harness.screen()[1].contains("AI: message 10 with some words to wrap around"));
+    }
+
+    #[test]
+    fn test_table_fits_chat_width() {
+        let mut chat = crate::ChatArea::new();
+        chat.set_render_tables(true);
+        chat.add_message(crate::ChatMessage::new("AI", "Scores:\n| name | notes |\n|---|---|\n| alice | very long remark |"));
+        let area = Rect::new(0, 0, 28, 10);
+        let mut buf = Buffer::empty(area);
+        chat.render_to_buffer(&mut buf, area);
+        let expected = concat!(
+            "┌Chat─────────────────────┐\n",
+            "│AI: Scores:              │\n",
+            "│┌───────┬───────────────┐│\n",
+            "││ name  │ notes         ││\n",
+            "│├───────┼───────────────┤│\n",
+            "││ alice │ very long     ││\n",
+            "││       │ remark        ││\n",
+            "│└───────┴───────────────┘│\n",
+            "│                         │\n",
+            "└─────────────────────────┘\n",
+        );
+        assert_eq!(buffer_to_string(&buf), expected);
+    }
+
+    #[test]
+    fn test_render_to_buffer_snapshot() {
+        let mut chat = crate::ChatArea::new();
+        chat.add_message(crate::ChatMessage::new("AI", "hello wide 世界 world"));
+        let mut buf = Buffer::empty(Rect::new(0, 0, 16, 6));
+        chat.render_to_buffer(&mut buf, Rect::new(1, 1, 15, 5));
+        let expected = concat!(
+            "\n",
+            " ┌Chat────────┐\n",
+            " │AI: hello   │\n",
+            " │wide 世界   │\n",
+            " │world       │\n",
+            " └────────────┘\n",
+        );
+        assert_eq!(buffer_to_string(&buf), expected);
+    }
+}
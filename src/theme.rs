@@ -0,0 +1,163 @@
+//! Color themes for the chat widgets.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    symbols::border,
+};
+
+/// Borders drawn with plain ASCII characters, for [`Theme::plain_glyphs`].
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Styles applied by [`ChatArea`](crate::ChatArea) and [`InputArea`](crate::InputArea).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Style of widget borders.
+    pub border: Style,
+    /// Style of widget titles.
+    pub title: Style,
+    /// Base style of the sender prefix; styles set by a custom prefix formatter take precedence.
+    pub sender: Style,
+    /// Style of timestamps and other secondary text.
+    pub muted: Style,
+    /// Style patched onto messages that [`NotifyRules`](crate::NotifyRules) highlight.
+    pub highlight: Style,
+    /// Style of inline code and the lines of fenced code blocks in rendered markdown.
+    pub code: Style,
+    /// Style of object keys in the JSON of tool messages.
+    pub json_key: Style,
+    /// Style of string values in the JSON of tool messages.
+    pub json_string: Style,
+    /// Style of numbers, `true`, `false` and `null` in the JSON of tool messages.
+    pub json_literal: Style,
+    /// Draws borders, markers and indicators with plain ASCII instead of box-drawing
+    /// characters and symbols, for screen readers and braille displays.
+    pub plain_glyphs: bool,
+}
+
+impl Default for Theme {
+    /// The terminal's own colors, with code cyan and JSON keys cyan, strings green and
+    /// literals yellow.
+    fn default() -> Self {
+        Self {
+            border: Style::default(),
+            title: Style::default(),
+            sender: Style::default(),
+            muted: Style::default(),
+            highlight: Style::default(),
+            code: Style::default().fg(Color::Cyan),
+            json_key: Style::default().fg(Color::Cyan),
+            json_string: Style::default().fg(Color::Green),
+            json_literal: Style::default().fg(Color::Yellow),
+            plain_glyphs: false,
+        }
+    }
+}
+
+impl Theme {
+    /// The border characters to draw with.
+    pub fn border_set(&self) -> border::Set {
+        if self.plain_glyphs { ASCII_BORDER } else { border::PLAIN }
+    }
+
+    /// `fancy` normally, or its ASCII stand-in `plain` when [`Theme::plain_glyphs`] is set.
+    pub fn glyph(&self, fancy: &'static str, plain: &'static str) -> &'static str {
+        if self.plain_glyphs { plain } else { fancy }
+    }
+}
+
+/// The built-in themes, selectable by name from the settings panel.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ThemePreset {
+    /// The terminal's own colors.
+    #[default]
+    Default,
+    Ocean,
+    Forest,
+    /// Bright text on the terminal background, no dimmed text and ASCII-only glyphs.
+    HighContrast,
+}
+
+impl ThemePreset {
+    pub const ALL: [ThemePreset; 4] =
+        [ThemePreset::Default, ThemePreset::Ocean, ThemePreset::Forest, ThemePreset::HighContrast];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemePreset::Default => "Default",
+            ThemePreset::Ocean => "Ocean",
+            ThemePreset::Forest => "Forest",
+            ThemePreset::HighContrast => "High contrast",
+        }
+    }
+
+    pub fn theme(self) -> Theme {
+        let muted = Style::default().add_modifier(Modifier::DIM);
+        let highlight = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+        match self {
+            ThemePreset::Default => Theme {
+                muted,
+                highlight,
+                ..Theme::default()
+            },
+            ThemePreset::Ocean => Theme {
+                border: Style::default().fg(Color::Blue),
+                title: Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                sender: Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                muted,
+                highlight,
+                ..Theme::default()
+            },
+            ThemePreset::Forest => Theme {
+                border: Style::default().fg(Color::Green),
+                title: Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+                sender: Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+                muted,
+                highlight,
+                ..Theme::default()
+            },
+            ThemePreset::HighContrast => Theme {
+                border: Style::default().fg(Color::White),
+                title: Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                sender: Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                muted: Style::default().fg(Color::White),
+                highlight: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                code: Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                json_key: Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+                json_string: Style::default().fg(Color::White),
+                json_literal: Style::default().fg(Color::Yellow),
+                plain_glyphs: true,
+            },
+        }
+    }
+}
+
+/// Colors that read well on both dark and light terminals, for
+/// [`ChatArea::set_sender_colors`](crate::ChatArea::set_sender_colors).
+pub const SENDER_PALETTE: [Color; 10] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightBlue,
+    Color::LightMagenta,
+];
+
+/// The palette color for a sender, picked by a hash of the name that is the same on every run.
+pub(crate) fn sender_color(sender: &str, palette: &[Color]) -> Option<Color> {
+    // FNV-1a, which unlike the standard library's hasher is specified not to change.
+    let hash = sender.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100_0000_01b3));
+    palette.get((hash % palette.len().max(1) as u64) as usize).copied()
+}
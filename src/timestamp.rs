@@ -0,0 +1,206 @@
+//! Timestamp formatting, without a calendar dependency unless the `chrono` feature is on.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Strings, strings};
+
+/// Formats the time of day as `HH:MM` in UTC.
+pub(crate) fn format_clock(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let minutes = secs / 60;
+    format!("{:02}:{:02}", (minutes / 60) % 24, minutes % 60)
+}
+
+/// Formats a date and time as `YYYY-MM-DD HH:MM` in UTC.
+pub(crate) fn format_date_time(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02} {}", format_clock(time))
+}
+
+/// How timestamps are shown: the timezone and `strftime`-style patterns, such as
+/// `%I:%M %p` for a 12-hour clock or `%d.%m.%Y %H:%M` for a European date.
+///
+/// Timestamps are stored as absolute [`SystemTime`]s, so messages imported with UTC
+/// timestamps are shown in the local timezone like any other.
+#[cfg(feature = "chrono")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeFormat {
+    /// Shows times in the system's local timezone rather than UTC.
+    pub local: bool,
+    /// Pattern for the time of day before each message.
+    pub clock: String,
+    /// Pattern for a full date and time, as in the jump-to-date popup.
+    pub date_time: String,
+}
+
+#[cfg(feature = "chrono")]
+impl Default for TimeFormat {
+    /// UTC, with a 24-hour clock and ISO dates, as shown without the `chrono` feature.
+    fn default() -> Self {
+        Self {
+            local: false,
+            clock: "%H:%M".to_string(),
+            date_time: "%Y-%m-%d %H:%M".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TimeFormat {
+    /// The default patterns in the local timezone.
+    pub fn local() -> Self {
+        Self {
+            local: true,
+            ..Self::default()
+        }
+    }
+
+    /// The local timezone with the clock and date order of the user's locale, from
+    /// `LC_ALL`, `LC_TIME` or `LANG`. Falls back to [`TimeFormat::local`] without one.
+    pub fn from_locale() -> Self {
+        let locale = ["LC_ALL", "LC_TIME", "LANG"].into_iter().find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+        Self::for_locale(locale.as_deref().unwrap_or("C"))
+    }
+
+    /// The local timezone with the clock and date order used in a POSIX locale such as
+    /// `en_US.UTF-8`: a 12-hour clock and month first in the US, day first in most of
+    /// Europe, year first in East Asia. Unknown regions get ISO dates and a 24-hour clock.
+    pub fn for_locale(locale: &str) -> Self {
+        let region = locale.split(['.', '@']).next().and_then(|name| name.split_once('_')).map_or("", |(_, region)| region);
+        let clock = match region {
+            "US" | "CA" | "AU" | "NZ" | "IN" | "PH" => "%I:%M %p",
+            _ => "%H:%M",
+        };
+        let date = match region {
+            "US" | "PH" => "%m/%d/%Y",
+            "GB" | "IE" | "FR" | "BE" | "IT" | "ES" | "PT" | "BR" | "AU" | "NZ" | "IN" | "GR" => "%d/%m/%Y",
+            "DE" | "AT" | "CH" | "RU" | "UA" | "PL" | "CZ" | "SK" | "FI" | "NO" | "DK" | "TR" | "RO" => "%d.%m.%Y",
+            "NL" => "%d-%m-%Y",
+            "JP" | "CN" | "TW" => "%Y/%m/%d",
+            _ => "%Y-%m-%d",
+        };
+        Self {
+            local: true,
+            clock: clock.to_string(),
+            date_time: format!("{date} {clock}"),
+        }
+    }
+
+    pub(crate) fn format_clock(&self, time: SystemTime) -> String {
+        self.format(time, &self.clock)
+    }
+
+    pub(crate) fn format_date_time(&self, time: SystemTime) -> String {
+        self.format(time, &self.date_time)
+    }
+
+    /// The timezone times are shown in, for labels: `UTC`, or the local offset from it such
+    /// as `UTC+02:00`, since chrono knows no names for local timezones.
+    pub(crate) fn zone(&self) -> String {
+        if self.local { format!("UTC{}", chrono::Local::now().format("%:z")) } else { "UTC".to_string() }
+    }
+
+    fn format(&self, time: SystemTime, pattern: &str) -> String {
+        use std::fmt::Write;
+
+        let utc = chrono::DateTime::<chrono::Utc>::from(time);
+        let mut out = String::new();
+        // An invalid pattern makes the formatter fail rather than panic.
+        let written = if self.local {
+            write!(out, "{}", utc.with_timezone(&chrono::Local).format(pattern))
+        } else {
+            write!(out, "{}", utc.format(pattern))
+        };
+        if written.is_err() {
+            return format_date_time(time);
+        }
+        out
+    }
+}
+
+/// Formats how long before `now` a message was sent, such as `5m ago`, or `None` if it is
+/// older than `max_age` seconds. Times after `now`, from clock skew, count as just now.
+pub(crate) fn format_relative(time: SystemTime, now: SystemTime, max_age: u64, strings: &Strings) -> Option<String> {
+    let secs = now.duration_since(time).map(|d| d.as_secs()).unwrap_or(0);
+    if secs > max_age {
+        return None;
+    }
+    let (template, count) = match secs {
+        0..60 => return Some(strings.time_just_now.clone()),
+        60..3600 => (&strings.time_minutes_ago, secs / 60),
+        3600..86_400 => (&strings.time_hours_ago, secs / 3600),
+        _ => (&strings.time_days_ago, secs / 86_400),
+    };
+    Some(strings::fill(template, &[("count", &count)]))
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian `(year, month, day)`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's algorithm, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_clock() {
+        let time = UNIX_EPOCH + Duration::from_secs(3 * 86_400 + 13 * 3600 + 7 * 60 + 59);
+        assert_eq!(format_clock(time), "13:07");
+        assert_eq!(format_date_time(time), "1970-01-04 13:07");
+        assert_eq!(format_date_time(UNIX_EPOCH + Duration::from_secs(1_709_210_096)), "2024-02-29 12:34");
+    }
+
+    #[test]
+    fn test_format_relative() {
+        let strings = Strings::default();
+        let now = UNIX_EPOCH + Duration::from_secs(10 * 86_400);
+        let ago = |secs| format_relative(now - Duration::from_secs(secs), now, 2 * 86_400, &strings);
+        assert_eq!(ago(59).as_deref(), Some("just now"));
+        assert_eq!(ago(150).as_deref(), Some("2m ago"));
+        assert_eq!(ago(3 * 3600).as_deref(), Some("3h ago"));
+        assert_eq!(ago(86_400 + 5).as_deref(), Some("1d ago"));
+        assert_eq!(ago(3 * 86_400), None);
+        assert_eq!(format_relative(now + Duration::from_secs(30), now, 60, &strings).as_deref(), Some("just now"));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_time_format_patterns() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        let format = TimeFormat {
+            clock: "%I:%M %p".to_string(),
+            date_time: "%d.%m.%Y".to_string(),
+            ..TimeFormat::default()
+        };
+        assert_eq!(format.format_clock(time), "12:34 PM");
+        assert_eq!(format.format_date_time(time), "29.02.2024");
+        assert_eq!(TimeFormat::default().format_clock(time), format_clock(time));
+        let bad = TimeFormat { clock: "%Q".to_string(), ..TimeFormat::default() };
+        assert_eq!(bad.format_clock(time), "2024-02-29 12:34");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_time_format_for_locale() {
+        let us = TimeFormat::for_locale("en_US.UTF-8");
+        assert!(us.local);
+        assert_eq!((us.clock.as_str(), us.date_time.as_str()), ("%I:%M %p", "%m/%d/%Y %I:%M %p"));
+        assert_eq!(TimeFormat::for_locale("de_DE@euro").date_time, "%d.%m.%Y %H:%M");
+        assert_eq!(TimeFormat::for_locale("ja_JP.UTF-8").date_time, "%Y/%m/%d %H:%M");
+        assert_eq!(TimeFormat::for_locale("C"), TimeFormat::local());
+        assert!(TimeFormat::local().zone().starts_with("UTC"));
+    }
+}
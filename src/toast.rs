@@ -0,0 +1,156 @@
+//! Transient notifications rendered as a corner overlay.
+
+use std::collections::VecDeque;
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+
+use crate::Strings;
+
+/// Severity of a toast, which determines its styling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl ToastLevel {
+    fn style(self) -> Style {
+        match self {
+            ToastLevel::Info => Style::default().fg(Color::Cyan),
+            ToastLevel::Warn => Style::default().fg(Color::Yellow),
+            ToastLevel::Error => Style::default().fg(Color::Red),
+        }
+    }
+
+    fn title(self, strings: &Strings) -> &str {
+        match self {
+            ToastLevel::Info => &strings.toast_info,
+            ToastLevel::Warn => &strings.toast_warn,
+            ToastLevel::Error => &strings.toast_error,
+        }
+    }
+}
+
+/// A queued notification.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub text: String,
+    /// Ticks left before the toast is dismissed.
+    pub remaining: u32,
+}
+
+/// A queue of toasts, dismissed automatically as ticks elapse.
+#[derive(Clone, Debug)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+    max_visible: usize,
+    width: u16,
+    strings: Strings,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+            max_visible: 3,
+            width: 40,
+            strings: Strings::default(),
+        }
+    }
+
+    /// Queues a toast that stays visible for `ticks` calls to [`Toasts::tick`].
+    pub fn push(&mut self, level: ToastLevel, text: impl Into<String>, ticks: u32) {
+        self.queue.push_back(Toast {
+            level,
+            text: text.into(),
+            remaining: ticks,
+        });
+    }
+
+    /// Counts down every visible toast and drops those that have expired.
+    ///
+    /// Toasts waiting behind the visible ones keep their full duration until they are shown.
+    /// Returns whether any expired.
+    pub fn tick(&mut self) -> bool {
+        for toast in self.queue.iter_mut().take(self.max_visible) {
+            toast.remaining = toast.remaining.saturating_sub(1);
+        }
+        let len = self.queue.len();
+        self.queue.retain(|t| t.remaining > 0);
+        self.queue.len() != len
+    }
+
+    pub fn dismiss_all(&mut self) {
+        self.queue.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn visible(&self) -> impl Iterator<Item = &Toast> {
+        self.queue.iter().take(self.max_visible)
+    }
+
+    /// Sets the titles shown for each level.
+    pub fn set_strings(&mut self, strings: Strings) {
+        self.strings = strings;
+    }
+
+    /// Sets how many toasts are stacked on screen at once.
+    pub fn set_max_visible(&mut self, max: usize) {
+        self.max_visible = max.max(1);
+    }
+
+    /// Renders the visible toasts stacked in the top-right corner of `area`.
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let width = self.width.min(area.width);
+        let inner_width = width.saturating_sub(2).max(1) as usize;
+        let mut y = area.y;
+        for toast in self.visible() {
+            let rows = textwrap::wrap(&toast.text, inner_width).len() as u16;
+            let height = (rows + 2).min(area.bottom().saturating_sub(y));
+            if height < 3 {
+                break;
+            }
+            let rect = Rect::new(area.right() - width, y, width, height);
+            let style = toast.level.style();
+            let block = Block::default().borders(Borders::ALL).border_style(style).title(toast.level.title(&self.strings));
+            frame.render_widget(Clear, rect);
+            frame.render_widget(Paragraph::new(toast.text.as_str()).wrap(Wrap { trim: true }).block(block), rect);
+            y += height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toasts_expire_after_their_ticks() {
+        let mut toasts = Toasts::new();
+        toasts.set_max_visible(1);
+        toasts.push(ToastLevel::Info, "saved", 1);
+        toasts.push(ToastLevel::Error, "failed", 2);
+        toasts.tick();
+        assert_eq!(toasts.visible().map(|t| t.text.as_str()).collect::<Vec<_>>(), vec!["failed"]);
+        assert_eq!(toasts.visible().next().unwrap().remaining, 2);
+        toasts.tick();
+        toasts.tick();
+        assert!(toasts.is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+//! Token count estimates for the input, for composing prompts within a model's context budget.
+
+/// Counts the tokens a model would see in a piece of text.
+///
+/// Implement it over a real tokenizer such as tiktoken for exact counts. Closures taking
+/// `&str` and returning `usize` implement it too.
+pub trait TokenCounter {
+    fn count(&self, text: &str) -> usize;
+}
+
+impl<F: Fn(&str) -> usize> TokenCounter for F {
+    fn count(&self, text: &str) -> usize {
+        self(text)
+    }
+}
+
+/// A tokenizer-free estimate: about four characters per token, and at least one per word.
+///
+/// Close enough for English prose with GPT-style tokenizers; code and other scripts vary.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeuristicCounter;
+
+impl TokenCounter for HeuristicCounter {
+    fn count(&self, text: &str) -> usize {
+        let chars = text.chars().filter(|c| !c.is_whitespace()).count();
+        let words = text.split_whitespace().count();
+        chars.div_ceil(4).max(words)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heuristic_estimate() {
+        assert_eq!(HeuristicCounter.count(""), 0);
+        assert_eq!(HeuristicCounter.count("a b c"), 3);
+        assert_eq!(HeuristicCounter.count("internationalization"), 5);
+        assert_eq!((|text: &str| text.len()).count("four"), 4);
+    }
+}
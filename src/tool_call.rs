@@ -0,0 +1,174 @@
+//! Showing tool calls and their results: the tool's name, then its JSON arguments or result
+//! collapsed to one line or pretty-printed.
+
+use ratatui::{style::Style, text::Span};
+
+use crate::Theme;
+
+/// Characters of a collapsed tool message's JSON shown before it is cut short.
+const PREVIEW: usize = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    /// A string, including its quotes. An unterminated string runs to the end.
+    Str,
+    /// One of `{}[],:`.
+    Punct,
+    /// A number, `true`, `false`, `null`, or anything else that is not JSON.
+    Literal,
+    Space,
+}
+
+fn tokens(text: &str) -> Vec<(Token, &str)> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let (token, len) = match c {
+            '"' => {
+                let mut escaped = false;
+                let end = rest[1..]
+                    .char_indices()
+                    .find(|&(_, c)| {
+                        let close = c == '"' && !escaped;
+                        escaped = c == '\\' && !escaped;
+                        close
+                    })
+                    .map_or(rest.len(), |(i, _)| i + 2);
+                (Token::Str, end)
+            }
+            '{' | '}' | '[' | ']' | ',' | ':' => (Token::Punct, 1),
+            c if c.is_whitespace() => (Token::Space, rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len())),
+            _ => {
+                let end = rest.find(|c: char| c.is_whitespace() || "{}[],:\"".contains(c)).unwrap_or(rest.len());
+                (Token::Literal, end)
+            }
+        };
+        tokens.push((token, &rest[..len]));
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// Whether `text` looks like a JSON object or array, which is worth reformatting.
+fn is_json(text: &str) -> bool {
+    text.trim_start().starts_with(['{', '['])
+}
+
+/// `text` on one line with a space after each `,` and `:`, cut short with `…` after
+/// [`PREVIEW`] characters. Text that is not JSON keeps only its first line, followed by
+/// ` …` if there are more.
+pub(crate) fn compact(text: &str) -> String {
+    let mut out = String::new();
+    if is_json(text) {
+        for (token, s) in tokens(text) {
+            match (token, s) {
+                (Token::Space, _) => {}
+                (Token::Punct, "," | ":") => {
+                    out.push_str(s);
+                    out.push(' ');
+                }
+                _ => out.push_str(s),
+            }
+        }
+    } else {
+        out.push_str(text.trim().lines().next().unwrap_or(""));
+    }
+    let multiline = !is_json(text) && text.trim().contains('\n');
+    match out.char_indices().nth(PREVIEW) {
+        Some((end, _)) => format!("{}…", &out[..end]),
+        None if multiline => format!("{out} …"),
+        None => out,
+    }
+}
+
+/// `text` indented two spaces per level of nesting, one value per line. Text that is not
+/// JSON is returned trimmed.
+pub(crate) fn pretty(text: &str) -> String {
+    if !is_json(text) {
+        return text.trim().to_string();
+    }
+    let tokens: Vec<(Token, &str)> = tokens(text).into_iter().filter(|&(token, _)| token != Token::Space).collect();
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+    };
+    for (i, &(token, s)) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1).map(|&(_, s)| s);
+        match (token, s) {
+            (Token::Punct, "{" | "[") => {
+                out.push_str(s);
+                depth += 1;
+                // Keep empty containers on one line.
+                if !matches!(next, Some("}" | "]")) {
+                    newline(&mut out, depth);
+                }
+            }
+            (Token::Punct, "}" | "]") => {
+                let empty = i > 0 && matches!(tokens[i - 1].1, "{" | "[");
+                depth = depth.saturating_sub(1);
+                if !empty {
+                    newline(&mut out, depth);
+                }
+                out.push_str(s);
+            }
+            (Token::Punct, ",") => {
+                out.push(',');
+                newline(&mut out, depth);
+            }
+            (Token::Punct, ":") => out.push_str(": "),
+            _ => out.push_str(s),
+        }
+    }
+    out
+}
+
+/// The tool message's `header` followed by its `body` with JSON syntax colored by `theme`'s
+/// key, string and literal styles. A one-line body stays on the header's row.
+pub(crate) fn spans(header: Span<'static>, body: &str, theme: &Theme) -> Vec<Span<'static>> {
+    let mut spans = vec![header];
+    if body.is_empty() {
+        return spans;
+    }
+    spans.push(Span::raw(if body.contains('\n') { "\n" } else { " " }));
+    let tokens = tokens(body);
+    for (i, &(token, s)) in tokens.iter().enumerate() {
+        let style = match token {
+            Token::Str => {
+                let key = tokens[i + 1..].iter().find(|&&(token, _)| token != Token::Space).is_some_and(|&(_, s)| s == ":");
+                if key { theme.json_key } else { theme.json_string }
+            }
+            Token::Literal if is_json(body) => theme.json_literal,
+            _ => Style::default(),
+        };
+        spans.push(Span::styled(s.to_string(), style));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::*;
+
+    #[test]
+    fn test_pretty_compact_and_colored() {
+        let json = r#"{"query":"rust \"tui\"","limit":3,"tags":[],"opts":{"safe":true}}"#;
+        assert_eq!(
+            pretty(json),
+            "{\n  \"query\": \"rust \\\"tui\\\"\",\n  \"limit\": 3,\n  \"tags\": [],\n  \"opts\": {\n    \"safe\": true\n  }\n}"
+        );
+        assert_eq!(compact(&pretty(json)), r#"{"query": "rust \"tui\"", "limit": 3, "tags": [], "opts": {"…"#);
+        assert_eq!(compact("not found\nat line 2"), "not found …");
+        assert_eq!(pretty("  done  "), "done");
+
+        let spans = spans(Span::raw("⚙ search"), r#"{"limit": 3, "q": "x"}"#, &Theme::default());
+        let style = |text: &str| spans.iter().find(|span| span.content == text).unwrap().style.fg;
+        assert_eq!(spans[1].content, " ");
+        assert_eq!(style("\"limit\""), Some(Color::Cyan));
+        assert_eq!(style("3"), Some(Color::Yellow));
+        assert_eq!(style("\"x\""), Some(Color::Green));
+    }
+}
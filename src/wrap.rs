@@ -0,0 +1,382 @@
+//! Style-preserving word wrapping.
+//!
+//! `textwrap` only deals in plain strings, so styled content is flattened into a
+//! single string plus a list of style runs, wrapped with textwrap's building blocks,
+//! and the style runs are mapped back onto each wrapped row.
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use textwrap::{
+    Options, WordSeparator, WordSplitter,
+    core::{Word, break_words, display_width},
+    word_splitters::split_words,
+};
+
+/// How [`ChatArea`](crate::ChatArea) wraps message text that does not fit a row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WrapPolicy {
+    /// Breaks words wider than a row, such as long URLs or base64 blobs, at the row's
+    /// edge. Otherwise they get a row of their own and are cut off at the edge.
+    pub break_words: bool,
+    /// Lets rows end at any Unicode line break opportunity, such as after a `/` in a URL or
+    /// between CJK characters. Otherwise rows only end at spaces.
+    pub unicode_breaks: bool,
+    /// Lets rows end after a hyphen inside a word, as in `tui-` / `chat`.
+    pub split_hyphens: bool,
+    /// Keeps the whitespace at the end of each row instead of dropping it, for content
+    /// where spacing matters.
+    pub keep_trailing_whitespace: bool,
+}
+
+impl Default for WrapPolicy {
+    fn default() -> Self {
+        Self {
+            break_words: true,
+            unicode_breaks: true,
+            split_hyphens: true,
+            keep_trailing_whitespace: false,
+        }
+    }
+}
+
+impl WrapPolicy {
+    /// textwrap options for rows of `width` columns.
+    pub(crate) fn options<'a>(&self, width: usize) -> Options<'a> {
+        let splitter = if self.split_hyphens { WordSplitter::HyphenSplitter } else { WordSplitter::NoHyphenation };
+        let separator = if self.unicode_breaks { WordSeparator::UnicodeBreakProperties } else { WordSeparator::AsciiSpace };
+        Options::new(width).break_words(self.break_words).word_separator(separator).word_splitter(splitter)
+    }
+}
+
+/// Paragraphs, such as the lines of a code block, shown unwrapped on one row each with the
+/// first `offset` columns scrolled out of view.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NoWrap {
+    /// Indices of the paragraphs, in ascending order.
+    pub(crate) paragraphs: Vec<usize>,
+    pub(crate) offset: usize,
+    /// Ends a row cut short on the right, such as `›`.
+    pub(crate) marker: &'static str,
+}
+
+/// Wraps a sequence of styled spans into display rows no wider than `options.width`.
+///
+/// Newlines inside span contents start a new paragraph, exactly like `textwrap::wrap`.
+/// Indentation from `options` is emitted as unstyled spans. Whitespace at the end of rows
+/// is dropped unless `keep_whitespace` is set.
+pub(crate) fn wrap_spans(spans: &[Span<'_>], options: &Options<'_>, keep_whitespace: bool) -> Vec<Line<'static>> {
+    wrap_spans_with(spans, options, keep_whitespace, &NoWrap::default())
+}
+
+/// Like [`wrap_spans`], leaving the paragraphs in `no_wrap` unwrapped. A row cut short on
+/// the right ends in its marker.
+pub(crate) fn wrap_spans_with(spans: &[Span<'_>], options: &Options<'_>, keep_whitespace: bool, no_wrap: &NoWrap) -> Vec<Line<'static>> {
+    let mut text = String::new();
+    let mut runs: Vec<(usize, usize, Style)> = Vec::new();
+    for span in spans {
+        let start = text.len();
+        text.push_str(&span.content);
+        if text.len() > start {
+            runs.push((start, text.len(), span.style));
+        }
+    }
+
+    let mut rows = Vec::new();
+    let mut para_start = 0;
+    for (i, raw) in text.split('\n').enumerate() {
+        let para = raw.strip_suffix('\r').unwrap_or(raw);
+        if no_wrap.paragraphs.binary_search(&i).is_ok() {
+            rows.push(unwrapped_row(&text, para_start, para, &runs, options, no_wrap, rows.is_empty()));
+        } else {
+            wrap_paragraph(&text, para_start, para, &runs, options, keep_whitespace, &mut rows);
+        }
+        para_start += raw.len() + 1;
+    }
+    rows
+}
+
+/// The characters of `text` starting within display columns `from..to`.
+pub(crate) fn slice_columns(text: &str, from: usize, to: usize) -> String {
+    let mut column = 0;
+    let mut out = String::new();
+    for ch in text.chars() {
+        if column >= to {
+            break;
+        }
+        if column >= from {
+            out.push(ch);
+        }
+        column += display_width(ch.encode_utf8(&mut [0; 4]));
+    }
+    out
+}
+
+/// The columns of `para` from `offset` on that fit a row, as one row.
+fn unwrapped_row(
+    text: &str,
+    para_start: usize,
+    para: &str,
+    runs: &[(usize, usize, Style)],
+    options: &Options<'_>,
+    no_wrap: &NoWrap,
+    first: bool,
+) -> Line<'static> {
+    let indent = if first { options.initial_indent } else { options.subsequent_indent };
+    let width = options.width.saturating_sub(display_width(indent));
+    let offset = no_wrap.offset;
+    let overflows = display_width(para) > offset + width;
+    let mut spans = Vec::new();
+    if !indent.is_empty() {
+        spans.push(Span::raw(indent.to_string()));
+    }
+    let mut column = 0;
+    let mut start = None;
+    let mut end = para.len();
+    let mut cut = false;
+    for (i, ch) in para.char_indices() {
+        let w = display_width(ch.encode_utf8(&mut [0; 4]));
+        if column >= offset && start.is_none() {
+            start = Some(i);
+        }
+        // Leave a column for the marker if anything follows.
+        if start.is_some() && overflows && column + w > offset + width.saturating_sub(1) {
+            end = i;
+            cut = true;
+            break;
+        }
+        column += w;
+    }
+    let start = start.unwrap_or(para.len());
+    push_styled(text, para_start + start, para_start + end.max(start), runs, &mut spans);
+    if cut {
+        spans.push(Span::raw(no_wrap.marker));
+    }
+    Line::from(spans)
+}
+
+/// Where each row of `para`, wrapped as [`wrap_spans`] wraps it, starts, in bytes. A row
+/// starts after the whitespace ending the one before.
+pub(crate) fn row_starts(para: &str, options: &Options<'_>) -> Vec<usize> {
+    let para = para.strip_suffix('\r').unwrap_or(para);
+    let words = paragraph_words(para, options);
+    let wrapped = wrap_words(&words, options);
+    let mut starts = vec![0];
+    for words in wrapped.iter().take(wrapped.len().saturating_sub(1)) {
+        let len: usize = words.iter().map(|w| w.len() + w.whitespace.len()).sum();
+        starts.push(starts[starts.len() - 1] + len);
+    }
+    starts
+}
+
+/// The words of `para`, split and broken as `options` ask.
+fn paragraph_words<'a>(para: &'a str, options: &'a Options<'_>) -> Vec<Word<'a>> {
+    let subsequent_width = options.width.saturating_sub(display_width(options.subsequent_indent));
+    let words = options.word_separator.find_words(para);
+    let split = split_words(words, &options.word_splitter);
+    if options.break_words {
+        let mut broken = break_words(split, subsequent_width);
+        if !options.initial_indent.is_empty() {
+            broken.insert(0, Word::from(""));
+        }
+        broken
+    } else {
+        split.collect::<Vec<_>>()
+    }
+}
+
+/// `words` grouped into the rows `options` fit them on.
+fn wrap_words<'a, 'b>(words: &'b [Word<'a>], options: &Options<'_>) -> Vec<&'b [Word<'a>]> {
+    let initial_width = options.width.saturating_sub(display_width(options.initial_indent));
+    let subsequent_width = options.width.saturating_sub(display_width(options.subsequent_indent));
+    let line_widths = [initial_width, subsequent_width];
+    let mut rest = words;
+    let mut rows = Vec::new();
+    for row in options.wrap_algorithm.wrap(words, &line_widths) {
+        let (row, tail) = rest.split_at(row.len());
+        rows.push(row);
+        rest = tail;
+    }
+    rows
+}
+
+fn wrap_paragraph(
+    text: &str,
+    para_start: usize,
+    para: &str,
+    runs: &[(usize, usize, Style)],
+    options: &Options<'_>,
+    keep_whitespace: bool,
+    rows: &mut Vec<Line<'static>>,
+) {
+    let words = paragraph_words(para, options);
+    let wrapped = wrap_words(&words, options);
+    let mut idx = 0;
+    for (row, words) in wrapped.iter().enumerate() {
+        let indent = if rows.is_empty() && row == 0 {
+            options.initial_indent
+        } else {
+            options.subsequent_indent
+        };
+        let mut spans = Vec::new();
+        if !indent.is_empty() {
+            spans.push(Span::raw(indent.to_string()));
+        }
+        let Some(last) = words.last() else {
+            rows.push(Line::from(spans));
+            continue;
+        };
+        // Words are contiguous in `para`, so the row is a plain byte range.
+        let len = words.iter().map(|w| w.len() + w.whitespace.len()).sum::<usize>() - last.whitespace.len();
+        let start = para_start + idx;
+        let end = if keep_whitespace { start + len + last.whitespace.len() } else { start + len };
+        push_styled(text, start, end, runs, &mut spans);
+        if !last.penalty.is_empty() {
+            let style = runs
+                .iter()
+                .find(|(s, e, _)| *s < start + len && start + len <= *e)
+                .map(|(_, _, style)| *style)
+                .unwrap_or_default();
+            spans.push(Span::styled(last.penalty.to_string(), style));
+        }
+        rows.push(Line::from(spans));
+        idx += len + last.whitespace.len();
+    }
+}
+
+/// Pushes the part of `text[start..end]` covered by each style run as its own span.
+fn push_styled(text: &str, start: usize, end: usize, runs: &[(usize, usize, Style)], out: &mut Vec<Span<'static>>) {
+    for &(run_start, run_end, style) in runs {
+        let s = run_start.max(start);
+        let e = run_end.min(end);
+        if s < e {
+            out.push(Span::styled(text[s..e].to_string(), style));
+        }
+    }
+}
+
+/// Pads or truncates a span so it occupies exactly `width` columns.
+pub(crate) fn fit_span(span: Span<'_>, width: usize) -> Span<'static> {
+    let mut content = String::new();
+    let mut used = 0;
+    for ch in span.content.chars() {
+        let w = display_width(ch.encode_utf8(&mut [0; 4]));
+        if used + w > width {
+            break;
+        }
+        content.push(ch);
+        used += w;
+    }
+    content.extend(std::iter::repeat_n(' ', width - used));
+    Span::styled(content, span.style)
+}
+
+/// Joins `spans` into a single row of at most `width` columns, with line breaks shown as
+/// spaces. If it does not fit, it is cut short and `ellipsis` is appended.
+pub(crate) fn truncate_spans(spans: &[Span<'_>], width: usize, ellipsis: &str) -> Line<'static> {
+    let spans: Vec<Span<'static>> =
+        spans.iter().map(|span| Span::styled(span.content.replace('\n', " "), span.style)).collect();
+    if spans.iter().map(Span::width).sum::<usize>() <= width {
+        return Line::from(spans);
+    }
+    let mut budget = width.saturating_sub(display_width(ellipsis));
+    let mut out = Vec::new();
+    let mut style = Style::default();
+    for span in spans {
+        style = span.style;
+        let mut content = String::new();
+        for ch in span.content.chars() {
+            let w = display_width(ch.encode_utf8(&mut [0; 4]));
+            if w > budget {
+                break;
+            }
+            content.push(ch);
+            budget -= w;
+        }
+        let cut = content.len() < span.content.len();
+        if !content.is_empty() {
+            out.push(Span::styled(content, span.style));
+        }
+        if cut {
+            break;
+        }
+    }
+    out.push(Span::styled(ellipsis.to_string(), style));
+    Line::from(out)
+}
+
+/// Returns the concatenated content of a wrapped row, without styling.
+pub(crate) fn plain(line: &Line<'_>) -> String {
+    line.spans.iter().map(|s| s.content.as_ref()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    #[test]
+    fn test_wrap_spans_matches_textwrap() {
+        let text = "AI: the quick brown fox jumps over the lazy dog\nsecond paragraph here";
+        let options = Options::new(12);
+        let expected: Vec<String> = textwrap::wrap(text, &options).into_iter().map(|c| c.into_owned()).collect();
+        let wrapped: Vec<String> = wrap_spans(&[Span::raw(text)], &options, false).iter().map(plain).collect();
+        assert_eq!(wrapped, expected);
+    }
+
+    #[test]
+    fn test_wrap_spans_keeps_styles_across_rows() {
+        let spans = [Span::raw("ab "), Span::styled("red words here", Style::default().fg(Color::Red))];
+        let rows = wrap_spans(&spans, &Options::new(9), false);
+        assert_eq!(rows.iter().map(plain).collect::<Vec<_>>(), vec!["ab red", "words", "here"]);
+        assert_eq!(rows[0].spans[1].style.fg, Some(Color::Red));
+        assert_eq!(rows[2].spans[0].style.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_row_starts_follow_wrapped_rows() {
+        let options = Options::new(9);
+        assert_eq!(row_starts("ab red words here", &options), [0, 7, 13]);
+        assert_eq!(row_starts("", &options), [0]);
+        // Wide characters take two columns each.
+        assert_eq!(row_starts("日本語日本語", &Options::new(4)), [0, 6, 12]);
+    }
+
+    #[test]
+    fn test_truncate_spans_with_ellipsis() {
+        let red = Style::default().fg(Color::Red);
+        let spans = [Span::raw("AI: "), Span::styled("line one\nline two", red)];
+        assert_eq!(plain(&truncate_spans(&spans, 30, "…")), "AI: line one line two");
+        let row = truncate_spans(&spans, 10, "…");
+        assert_eq!(plain(&row), "AI: line …");
+        assert_eq!(row.spans.last().unwrap().style, red);
+        assert_eq!(plain(&truncate_spans(&[Span::raw("日本語")], 5, "...")), "日...");
+    }
+
+    #[test]
+    fn test_wrap_policy() {
+        let text = [Span::raw("see https://example.com/a-very-long-path now")];
+        let wrap = |policy: WrapPolicy| -> Vec<String> {
+            wrap_spans(&text, &policy.options(14), policy.keep_trailing_whitespace).iter().map(plain).collect()
+        };
+        assert_eq!(wrap(WrapPolicy::default()), ["see https://", "example.com/", "a-very-long-", "path now"]);
+        let whole = WrapPolicy { break_words: false, unicode_breaks: false, split_hyphens: false, ..WrapPolicy::default() };
+        assert_eq!(wrap(whole), ["see", "https://example.com/a-very-long-path", "now"]);
+        let spaced = WrapPolicy { keep_trailing_whitespace: true, ..whole };
+        assert_eq!(wrap(spaced), ["see ", "https://example.com/a-very-long-path ", "now"]);
+    }
+
+    #[test]
+    fn test_no_wrap_paragraphs_scroll_sideways() {
+        let text = [Span::raw("code:\n0123456789abcdef\nend")];
+        let rows = |offset, marker| -> Vec<String> {
+            let no_wrap = NoWrap { paragraphs: vec![1], offset, marker };
+            wrap_spans_with(&text, &Options::new(10), false, &no_wrap).iter().map(plain).collect()
+        };
+        assert_eq!(rows(0, "›"), ["code:", "012345678›", "end"]);
+        assert_eq!(rows(4, "›"), ["code:", "456789abc›", "end"]);
+        assert_eq!(rows(6, "›"), ["code:", "6789abcdef", "end"]);
+        assert_eq!(rows(0, ">"), ["code:", "012345678>", "end"]);
+    }
+}
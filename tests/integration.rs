@@ -1,13 +1,28 @@
-use tui_chat::{ChatArea, ChatMessage};
+// The smoke test below asserts a constant on purpose.
+#![allow(clippy::assertions_on_constants)]
+
+use std::time::Duration;
+
+use tui_chat::{ChatArea, ChatMessage, MessageMetadata};
 
 #[test]
 fn test_chat_area_add_message() {
     let mut chat_area = ChatArea::new();
-    let message = ChatMessage {
-        sender: "Test".to_string(),
-        content: "Hello World".to_string(),
-    };
+    let message = ChatMessage::new("Test", "Hello World");
     chat_area.add_message(message);
     // Basic check that it doesn't panic
     assert!(true);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_messages_are_built_outside_the_crate() {
+    let metadata = MessageMetadata::default().with_model("gpt-4o").with_tokens(12, 40).with_cost(0.01);
+    let message = ChatMessage::voice("bob", "media/42.ogg", Some(Duration::from_secs(42)))
+        .with_content("see you at 5")
+        .with_metadata(metadata);
+    assert_eq!(message.content, "see you at 5");
+    assert_eq!(message.metadata.as_ref().and_then(|m| m.completion_tokens), Some(40));
+    let mut chat_area = ChatArea::new();
+    let id = chat_area.add_message(message);
+    assert!(chat_area.message(id).is_some_and(|m| m.timestamp.is_some()));
+}